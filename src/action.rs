@@ -1,9 +1,17 @@
 use serde::{Serialize, Deserialize};
-use crate::{game::{PlayerGame, Game}, interface::Interface};
+use crate::{game::{PlayerGame, GameAccess}, interface::{Idle, Interface}};
 
 pub enum Action {
-  UserAction(String, fn (&mut dyn PlayerGame, &mut Interface)),
-  GeneralAction(String, fn (&mut dyn Game, &mut Interface)),
+  UserAction(String, fn (&mut dyn PlayerGame, &mut dyn Interface) -> Result<(), Idle>),
+  GeneralAction(String, fn (&mut GameAccess, &mut dyn Interface) -> Result<(), Idle>),
+}
+
+impl Action {
+  pub fn description(&self) -> &str {
+    match self {
+      Action::UserAction(description, _) | Action::GeneralAction(description, _) => description,
+    }
+  }
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
@@ -15,6 +23,7 @@ pub enum ActionType {
   Cure,
   Psychoanalyze,
   Genomyze,
+  ElectCaptain,
 }
 
 pub fn get_menu_text(action: ActionType) -> String {
@@ -26,6 +35,7 @@ pub fn get_menu_text(action: ActionType) -> String {
     ActionType::Cure => String::from("Choisir un·e humain·e à soigner"),
     ActionType::Psychoanalyze => String::from("Choisir un·e client·e à psychanalyser"),
     ActionType::Genomyze => String::from("Choisir un génome à inspecter"),
+    ActionType::ElectCaptain => String::from("Voter pour élire le·a capitaine"),
   }
 }
 
@@ -38,5 +48,6 @@ pub fn get_header_text(action: ActionType) -> String {
     ActionType::Cure => String::from("Choisissez un·e humain·e à soigner:"),
     ActionType::Psychoanalyze => String::from("Choisissez votre client:"),
     ActionType::Genomyze => String::from("Choisissez votre cobaye:"),
+    ActionType::ElectCaptain => String::from("Choisissez qui vous voulez comme capitaine:"),
   }
 }
@@ -1,17 +1,343 @@
 use std::fs;
 use std::fs::File;
-use std::io;
+use std::io::Read;
 use std::error;
+use std::path::{Path, PathBuf};
 
-use crate::game::GameStatus;
+use rand::SeedableRng;
+use serde::Serialize;
+use serde_json::{json, Value};
 
-pub fn backup_game (game: &GameStatus, path: String) -> Result<(), Box<dyn error::Error>> {
-  let serialized = serde_json::to_string(game)?;
-  return Ok(fs::write(path, serialized)?);
+use crate::game::{GameRng, GameStatus};
+use crate::keys::hash_key;
+use crate::role::RoleCatalog;
+
+pub mod encryption;
+
+// Version of the format written by this binary, to be increased (with a new migration) whenever
+// a change of GameStatus or of what it contains would prevent older backups from loading
+pub const BACKUP_VERSION: u32 = 2;
+
+type Migration = fn(&mut Value) -> Result<(), Box<dyn error::Error>>;
+
+// MIGRATIONS[n] turns a game saved with the version n into a game of the version n + 1
+const MIGRATIONS: [Migration; BACKUP_VERSION as usize] = [
+  migrate_unversioned,
+  hash_keys,
+];
+
+#[derive(Serialize)]
+struct Backup<'a> {
+  version: u32,
+  game: &'a GameStatus,
+}
+
+// The backup is written next to its destination then renamed, so a crash while writing never leaves a truncated file
+// Without a passphrase, anyone with access to the computer can read the roles and keys in the backup
+pub fn backup_game (game: &GameStatus, path: String, passphrase: Option<&str>) -> Result<(), Box<dyn error::Error>> {
+  let mut serialized = serde_json::to_vec(&Backup { version: BACKUP_VERSION, game })?;
+  if let Some(passphrase) = passphrase {
+    serialized = encryption::encrypt(&serialized, passphrase)?;
+  }
+  let temporary = format!("{path}.tmp");
+  fs::write(&temporary, serialized)?;
+  Ok(fs::rename(temporary, path)?)
+}
+
+// Backups are named <prefix><anything><separator><number>, only the `keep` highest numbers are kept
+pub fn prune_backups (dir: &Path, prefix: &str, separator: &str, keep: usize) -> Result<(), Box<dyn error::Error>> {
+  let mut backups = numbered_backups(dir, prefix, separator)?;
+  backups.sort();
+  for (_, path) in backups.iter().rev().skip(keep) {
+    fs::remove_file(path)?;
+  }
+  Ok(())
+}
+
+// The highest number of the backups of a game, 0 if there are none (or if the directory cannot be read)
+pub fn last_backup_number (dir: &Path, prefix: &str, separator: &str) -> u32 {
+  numbered_backups(dir, prefix, separator).unwrap_or_default().into_iter().map(|(number, _)| number).max().unwrap_or(0)
 }
 
-pub fn restore_game (path: &String) -> Result<GameStatus, Box<dyn error::Error>> {
-  let input = File::open(path)?;
-  let reader = io::BufReader::new(input);
-  return Ok(serde_json::from_reader(reader)?);
+fn numbered_backups (dir: &Path, prefix: &str, separator: &str) -> Result<Vec<(u32, PathBuf)>, Box<dyn error::Error>> {
+  let mut backups = Vec::new();
+  for entry in fs::read_dir(dir)? {
+    let path = entry?.path();
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+      continue;
+    };
+    if !name.starts_with(prefix) {
+      continue;
+    }
+    if let Some(number) = name.rsplit_once(separator).and_then(|(_, number)| number.parse::<u32>().ok()) {
+      backups.push((number, path));
+    }
+  }
+  Ok(backups)
+}
+
+// The passphrase is only needed for encrypted backups
+pub fn restore_game (path: &String, passphrase: Option<&str>) -> Result<GameStatus, Box<dyn error::Error>> {
+  let mut content = fs::read(path)?;
+  if encryption::is_encrypted(&content) {
+    content = encryption::decrypt(&content, passphrase)?;
+  }
+  read_backup(serde_json::from_slice(&content)?)
+}
+
+pub fn is_encrypted (path: &Path) -> Result<bool, Box<dyn error::Error>> {
+  let mut header = Vec::new();
+  File::open(path)?.take(64).read_to_end(&mut header)?;
+  Ok(encryption::is_encrypted(&header))
+}
+
+fn read_backup (backup: Value) -> Result<GameStatus, Box<dyn error::Error>> {
+  // The first backups were the game itself, without any version
+  let (version, mut game) = match backup.get("version") {
+    None => (0, backup),
+    Some(version) => {
+      let version = version.as_u64().ok_or("The version of the backup is not a number")? as u32;
+      let game = backup.get("game").cloned().ok_or("The backup does not contain any game")?;
+      (version, game)
+    },
+  };
+  if version > BACKUP_VERSION {
+    return Err(format!(
+      "This backup was written by a newer version of rusty-spores (format version {version}, this one reads up to {BACKUP_VERSION}), please upgrade"
+    ).into());
+  }
+  for migration in &MIGRATIONS[version as usize..] {
+    migration(&mut game)?;
+  }
+  Ok(serde_json::from_value(game)?)
+}
+
+fn object (value: &mut Value) -> Result<&mut serde_json::Map<String, Value>, Box<dyn error::Error>> {
+  value.as_object_mut().ok_or("Unexpected content in the backup".into())
+}
+
+// Unversioned backups can come from any release before the versions were introduced, so every
+// change made since the first backups is checked here, and only applied if it is missing
+fn migrate_unversioned (game: &mut Value) -> Result<(), Box<dyn error::Error>> {
+  let catalog = RoleCatalog::default();
+  let role_value = |role: &Value| -> Result<Value, Box<dyn error::Error>> {
+    let Some(name) = role.as_str() else {
+      return Ok(role.clone()); // already a role definition
+    };
+    let role = catalog.get(legacy_role_id(name)?).ok_or(format!("Unknown role in the backup: {name}"))?;
+    Ok(serde_json::to_value(role)?)
+  };
+
+  let game = object(game)?;
+  for player in game.get_mut("players").and_then(Value::as_array_mut).ok_or("The backup does not contain any player")? {
+    let player = object(player)?;
+    let role = role_value(player.get("role").ok_or("A player of the backup has no role")?)?;
+    player.insert(String::from("role"), role);
+    if let Some(target) = player.get("hacker_target").filter(|target| !target.is_null()) {
+      let target = role_value(target)?;
+      player.insert(String::from("hacker_target"), target);
+    }
+    player.entry("traitor_signal").or_insert(json!(false));
+  }
+
+  // The hacking events used to name the hacked role instead of the player whose report was intercepted,
+  // they are given to the first player with that role, or dropped if nobody had it
+  let roles: Vec<Value> = game["players"].as_array().unwrap().iter().map(|player| player["role"]["id"].clone()).collect();
+  let events = game.entry("events").or_insert(json!([])).as_array_mut().ok_or("Unexpected events in the backup")?;
+  let mut migrated_events = Vec::new();
+  for mut event in events.drain(..) {
+    if let Some(hacked) = event.pointer_mut("/kind/Hacked").and_then(Value::as_object_mut) {
+      if let Some(role) = hacked.remove("role") {
+        let id = role_value(&role)?["id"].clone();
+        let Some(target) = roles.iter().position(|role| role == &id) else {
+          continue;
+        };
+        hacked.insert(String::from("target"), json!({ "id": target }));
+      }
+    }
+    migrated_events.push(event);
+  }
+  *events = migrated_events;
+
+  // Games without a seed get one from their creation, there is no way to replay them anyway
+  if !game.contains_key("seed") {
+    let seed = game.get("creation").and_then(Value::as_u64).unwrap_or(0);
+    game.insert(String::from("seed"), json!(seed));
+  }
+  if !game.contains_key("rng") {
+    let seed = game["seed"].as_u64().unwrap_or(0);
+    game.insert(String::from("rng"), serde_json::to_value(GameRng::seed_from_u64(seed))?);
+  }
+  // The rules were moved into the configuration of the game
+  if let Some(rules) = game.remove("rules") {
+    game.entry("config").or_insert(json!({}));
+    object(&mut game["config"])?.insert(String::from("rules"), rules);
+  }
+  Ok(())
+}
+
+// The keys of the players used to be saved as they were typed
+fn hash_keys (game: &mut Value) -> Result<(), Box<dyn error::Error>> {
+  for player in game.get_mut("players").and_then(Value::as_array_mut).ok_or("The backup does not contain any player")? {
+    let player = object(player)?;
+    let key = player.remove("key").ok_or("A player of the backup has no key")?;
+    let key = key.as_str().ok_or("A player of the backup has an invalid key")?;
+    player.insert(String::from("key_hash"), json!(hash_key(key)));
+  }
+  Ok(())
+}
+
+// Before roles.toml, roles were saved as the name of a fixed list of roles
+fn legacy_role_id (name: &str) -> Result<&'static str, Box<dyn error::Error>> {
+  Ok(match name {
+    "Patient0" => "patient0",
+    "Physician" => "physician",
+    "Psychologist" => "psychologist",
+    "ITEngineer" => "it_engineer",
+    "Spy" => "spy",
+    "Geneticist" => "geneticist",
+    "Hacker" => "hacker",
+    "Traitor" => "traitor",
+    "Astronaut" => "astronaut",
+    _ => return Err(format!("Unknown role in the backup: {name}").into()),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use rand::SeedableRng;
+
+  use super::*;
+  use crate::game::{Game, GameRng};
+  use crate::helper::select_votes_winner;
+  use crate::player::{Player, PlayerId};
+  use crate::role::{RoleAction, RoleCatalog};
+
+  // A backup as written by the first releases: no version, no events, and the roles saved by name
+  const UNVERSIONED_BACKUP: &str = r#"{
+    "creation": 1700000000, "name": "Koursk", "date": 2, "current_player_id": null, "debug": false, "phase": "Day",
+    "players": [
+      { "id": {"id": 0}, "key": "100", "name": "Zoe", "role": "Patient0", "messages": [], "host": false, "resilient": false,
+        "alive": true, "infected": true, "paralyzed": false, "death_date": null, "death_cause": null,
+        "auto_cure_physician": false, "auto_kill_physician": false, "physician_kill": false, "mutant_kill": false,
+        "has_connected_today": true, "actions": {"Infect": {"id": 1}},
+        "spy_info": {"woke_up": false, "was_cured": false, "was_infected": false, "was_paralyzed": false, "was_psychoanalyzed": false},
+        "hacker_target": null },
+      { "id": {"id": 1}, "key": "101", "name": "Mal", "role": "Hacker", "messages": [{"date": 1, "source": "Ordinateur Central", "content": "Bonjour"}],
+        "host": false, "resilient": false,
+        "alive": true, "infected": false, "paralyzed": false, "death_date": null, "death_cause": null,
+        "auto_cure_physician": false, "auto_kill_physician": false, "physician_kill": false, "mutant_kill": false,
+        "has_connected_today": false, "actions": {},
+        "spy_info": {"woke_up": false, "was_cured": false, "was_infected": false, "was_paralyzed": false, "was_psychoanalyzed": false},
+        "hacker_target": "Spy" }
+    ]
+  }"#;
+
+  #[test]
+  fn unversioned_backups_are_migrated() {
+    let game = read_backup(serde_json::from_str(UNVERSIONED_BACKUP).unwrap()).unwrap();
+    let players: Vec<_> = game.get_all_players().collect();
+    assert_eq!(game.get_name(), "Koursk");
+    assert_eq!(game.get_seed(), 1700000000);
+    assert!(players[0].role.infected);
+    assert!(players[1].can(RoleAction::Hack));
+    assert_eq!(players[1].hacker_target.as_ref().map(|role| role.id.as_str()), Some("spy"));
+    assert!(players[0].has_connected_today);
+    assert!(players[1].check_key("101") && !players[1].check_key("100"));
+    let saved = serde_json::to_string(&Backup { version: BACKUP_VERSION, game: &game }).unwrap();
+    assert!(!saved.contains("\"101\""));
+  }
+
+  #[test]
+  fn hacking_events_are_given_to_the_hacked_player() {
+    let mut backup: Value = serde_json::from_str(UNVERSIONED_BACKUP).unwrap();
+    backup["events"] = json!([
+      { "date": 1, "cause": {"Player": {"id": 1}}, "kind": {"Hacked": {"hacker": {"id": 1}, "role": "Patient0"}} },
+      { "date": 1, "cause": {"Player": {"id": 1}}, "kind": {"Hacked": {"hacker": {"id": 1}, "role": "Spy"}} },
+    ]);
+    let game = read_backup(backup).unwrap();
+    let events = serde_json::to_value(game.get_events()).unwrap();
+    assert_eq!(events, json!([{ "date": 1, "cause": {"Player": {"id": 1}}, "kind": {"Hacked": {"hacker": {"id": 1}, "target": {"id": 0}}} }]));
+  }
+
+  #[test]
+  fn current_backups_load_as_they_were_saved() {
+    let game = read_backup(serde_json::from_str(UNVERSIONED_BACKUP).unwrap()).unwrap();
+    let saved = serde_json::to_value(Backup { version: BACKUP_VERSION, game: &game }).unwrap();
+    let loaded = read_backup(saved.clone()).unwrap();
+    assert_eq!(serde_json::to_value(Backup { version: BACKUP_VERSION, game: &loaded }).unwrap()["game"]["players"], saved["game"]["players"]);
+  }
+
+  #[test]
+  fn a_game_restored_mid_day_draws_as_the_original() {
+    let catalog = RoleCatalog::default();
+    let players = ["patient0", "physician", "astronaut", "astronaut"].iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let mut game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), Default::default());
+    let ids = game.get_player_ids(&|_| true);
+    select_votes_winner(HashMap::from([(ids[0], 1), (ids[1], 1)]), game.rng()); // the day already used the rng
+    for id in &ids {
+      game.get_mut_player(*id).has_connected_today = true;
+    }
+
+    let saved = serde_json::to_value(Backup { version: BACKUP_VERSION, game: &game }).unwrap();
+    let mut restored = read_backup(saved).unwrap();
+    let tie: HashMap<PlayerId, usize> = ids.iter().map(|id| (*id, 1)).collect();
+    let draws = |game: &mut GameStatus| (0..20).map(|_| select_votes_winner(tie.clone(), game.rng())).collect::<Vec<_>>();
+    assert_eq!(draws(&mut restored), draws(&mut game));
+  }
+
+  #[test]
+  fn only_the_latest_backups_are_kept() {
+    let dir = std::env::temp_dir().join(format!("rusty-spores-prune-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for (day, number) in [(1, 1), (1, 2), (2, 3), (2, 10), (3, 11)] {
+      fs::write(dir.join(format!("sporz-Koursk-1-day-{day}-autosave-{number}")), "").unwrap();
+    }
+    fs::write(dir.join("sporz-Koursk-1-day-2"), "").unwrap(); // end of day backups are not autosaves
+    fs::write(dir.join("sporz-Other-1-day-1-autosave-1"), "").unwrap();
+
+    prune_backups(&dir, "sporz-Koursk-1-", "-autosave-", 3).unwrap();
+    let mut files: Vec<String> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name().into_string().unwrap()).collect();
+    files.sort();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(files, vec![
+      "sporz-Koursk-1-day-2",
+      "sporz-Koursk-1-day-2-autosave-10",
+      "sporz-Koursk-1-day-2-autosave-3",
+      "sporz-Koursk-1-day-3-autosave-11",
+      "sporz-Other-1-day-1-autosave-1",
+    ]);
+  }
+
+  #[test]
+  fn autosaves_resume_where_the_last_player_left_off() {
+    let dir = std::env::temp_dir().join(format!("rusty-spores-autosave-{}", std::process::id()));
+    let catalog = RoleCatalog::default();
+    let players = ["patient0", "astronaut"].iter().enumerate()
+      .map(|(idx, role)| crate::player::Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let mut game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), Default::default());
+    game.set_backup_dir(Some(dir.to_string_lossy().to_string()));
+    let player = game.get_player_ids(&|_| true)[1];
+    game.get_mut_player(player).has_connected_today = true;
+    game.autosave().unwrap();
+
+    let files: Vec<_> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+    let restored = restore_game(&files[0].to_string_lossy().to_string(), None).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(files.len(), 1);
+    assert!(restored.get_player(player).has_connected_today);
+    assert!(!restored.get_player(game.get_player_ids(&|_| true)[0]).has_connected_today);
+  }
+
+  #[test]
+  fn backups_from_newer_versions_are_refused() {
+    let backup = json!({ "version": BACKUP_VERSION + 1, "game": {} });
+    let error = read_backup(backup).err().unwrap().to_string();
+    assert!(error.contains("newer version"));
+  }
 }
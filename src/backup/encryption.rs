@@ -0,0 +1,75 @@
+use std::error;
+use std::fmt;
+
+use argon2::Argon2;
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce, aead::{Aead, KeyInit}};
+use rand::Rng;
+
+// Encrypted backups start with this header, followed by the salt of the key, the nonce and the encrypted backup
+const HEADER: &[u8] = b"sporz-encrypted-1\n";
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+
+#[derive(Debug, PartialEq)]
+pub enum EncryptionError {
+  PassphraseNeeded,
+  WrongPassphrase, // or a damaged file, there is no way to tell them apart
+}
+
+impl fmt::Display for EncryptionError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      EncryptionError::PassphraseNeeded => write!(f, "This backup is encrypted, its passphrase is needed"),
+      EncryptionError::WrongPassphrase => write!(f, "Wrong passphrase, or damaged backup"),
+    }
+  }
+}
+
+impl error::Error for EncryptionError {}
+
+pub fn is_encrypted (content: &[u8]) -> bool {
+  content.starts_with(HEADER)
+}
+
+fn cipher (passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305, Box<dyn error::Error>> {
+  let mut key = [0u8; 32];
+  Argon2::default().hash_password_into(passphrase.as_bytes(), salt, &mut key).map_err(|error| error.to_string())?;
+  Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+pub fn encrypt (content: &[u8], passphrase: &str) -> Result<Vec<u8>, Box<dyn error::Error>> {
+  // Not the game's rng: the salt and nonce must be unpredictable, and must not change how the game unfolds
+  let mut rng = rand::thread_rng();
+  let salt: [u8; SALT_LENGTH] = rng.gen();
+  let nonce: [u8; NONCE_LENGTH] = rng.gen();
+  let encrypted = cipher(passphrase, &salt)?.encrypt(Nonce::from_slice(&nonce), content).map_err(|_| "Cannot encrypt the backup")?;
+  Ok([HEADER, &salt, &nonce, &encrypted].concat())
+}
+
+pub fn decrypt (content: &[u8], passphrase: Option<&str>) -> Result<Vec<u8>, Box<dyn error::Error>> {
+  let passphrase = passphrase.ok_or(EncryptionError::PassphraseNeeded)?;
+  let content = content.strip_prefix(HEADER).ok_or("This backup is not encrypted")?;
+  if content.len() < SALT_LENGTH + NONCE_LENGTH {
+    return Err(EncryptionError::WrongPassphrase.into());
+  }
+  let (salt, content) = content.split_at(SALT_LENGTH);
+  let (nonce, encrypted) = content.split_at(NONCE_LENGTH);
+  Ok(cipher(passphrase, salt)?.decrypt(Nonce::from_slice(nonce), encrypted).map_err(|_| EncryptionError::WrongPassphrase)?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn only_the_right_passphrase_decrypts() {
+    let encrypted = encrypt(b"{\"name\": \"Koursk\"}", "mot de passe").unwrap();
+    assert!(is_encrypted(&encrypted));
+    assert!(!encrypted.windows(6).any(|window| window == b"Koursk"));
+    assert_eq!(decrypt(&encrypted, Some("mot de passe")).unwrap(), b"{\"name\": \"Koursk\"}");
+    let error = decrypt(&encrypted, Some("mauvais")).unwrap_err();
+    assert_eq!(error.downcast_ref::<EncryptionError>(), Some(&EncryptionError::WrongPassphrase));
+    let error = decrypt(&encrypted, None).unwrap_err();
+    assert_eq!(error.downcast_ref::<EncryptionError>(), Some(&EncryptionError::PassphraseNeeded));
+  }
+}
@@ -0,0 +1,170 @@
+use std::fmt;
+use std::error;
+
+pub static USAGE: &str = "Usage: rusty-spores [OPTIONS] [COMMAND]
+
+Commands:
+  (none)               Choose between the saved games and a new game, or create a new game if none is saved
+  new                  Create a new game
+  resume [backup]      Resume a game from a backup file, or choose between the saved games
+  inspect <backup> [other]
+                       Display the content of a backup file, or what changed between two backups
+  simulate [games]     Play games automatically with random choices, and display who won (default: 1 game)
+  help                 Display this message
+
+Options:
+  --debug              Create players and votes automatically, and display the hidden information
+  --no-sound           Do not play any sound
+  --seed <number>      Seed used for all the random choices, to replay a game
+  --backup-dir <dir>   Directory where the backups are written (default: backups/)
+  --config <file>      Game configuration, see the README
+  --roles <file>       Roles that can be dealt, see roles.toml
+  --serve <address>    Let the players use their own phones, connected to this address (for example 0.0.0.0:8080)
+  --serve-tcp <address>
+                       Let the players use their own terminals, with nc or telnet (for example 0.0.0.0:2323)
+  --players <number>   Number of players in the simulated games (default: 7)";
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+  Start,
+  New,
+  Resume { backup: Option<String> },
+  Inspect { backup: String, other: Option<String> },
+  Simulate { games: usize },
+  Help,
+}
+
+#[derive(Debug)]
+pub struct Cli {
+  pub command: Command,
+  pub debug: bool,
+  pub sound: bool,
+  pub seed: Option<u64>,
+  pub backup_dir: String,
+  pub config: Option<String>,
+  pub roles: Option<String>,
+  pub players: usize,
+  pub serve: Option<String>, // address of the web server for the players, the terminal is then left to the game master
+  pub serve_tcp: Option<String>, // same, for the players who prefer a terminal
+}
+
+#[derive(Debug)]
+pub struct CliError(String);
+
+impl fmt::Display for CliError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}\n\n{}", self.0, USAGE)
+  }
+}
+
+impl error::Error for CliError {}
+
+fn error<T> (message: String) -> Result<T, CliError> {
+  Err(CliError(message))
+}
+
+// Arguments are expected without the name of the program
+pub fn parse (args: impl IntoIterator<Item = String>) -> Result<Cli, CliError> {
+  let mut cli = Cli {
+    command: Command::Start,
+    debug: false,
+    sound: true,
+    seed: None,
+    backup_dir: String::from("backups/"),
+    config: None,
+    roles: None,
+    players: 7,
+    serve: None,
+    serve_tcp: None,
+  };
+  let mut positionals = Vec::new();
+  let mut args = args.into_iter();
+  while let Some(arg) = args.next() {
+    let mut value = |name: &str| match args.next() {
+      Some(value) if !value.starts_with("--") => Ok(value),
+      _ => error(format!("{name} expects a value")),
+    };
+    match arg.as_str() {
+      "--debug" => cli.debug = true,
+      "--no-sound" => cli.sound = false,
+      "--seed" => {
+        let seed = value("--seed")?;
+        cli.seed = Some(seed.parse().or(error(format!("--seed expects a number, got '{seed}'")))?);
+      },
+      "--backup-dir" => cli.backup_dir = value("--backup-dir")?,
+      "--config" => cli.config = Some(value("--config")?),
+      "--roles" => cli.roles = Some(value("--roles")?),
+      "--serve" => cli.serve = Some(value("--serve")?),
+      "--serve-tcp" => cli.serve_tcp = Some(value("--serve-tcp")?),
+      "--players" => {
+        let players = value("--players")?;
+        cli.players = players.parse().or(error(format!("--players expects a number, got '{players}'")))?;
+      },
+      "--from-backup" => positionals.extend([String::from("resume"), value("--from-backup")?]), // kept for older scripts
+      "--help" | "-h" => cli.command = Command::Help,
+      option if option.starts_with('-') => return error(format!("Unknown option: {option}")),
+      _ => positionals.push(arg),
+    }
+  }
+
+  if cli.command == Command::Help {
+    return Ok(cli);
+  }
+  let mut positionals = positionals.into_iter();
+  cli.command = match positionals.next().as_deref() {
+    None => Command::Start,
+    Some("new") => Command::New,
+    Some("resume") => Command::Resume { backup: positionals.next() },
+    Some("inspect") => Command::Inspect {
+      backup: positionals.next().ok_or(CliError(String::from("inspect expects a backup file")))?,
+      other: positionals.next(),
+    },
+    Some("simulate") => match positionals.next() {
+      Some(games) => Command::Simulate { games: games.parse().or(error(format!("simulate expects a number of games, got '{games}'")))? },
+      None => Command::Simulate { games: 1 },
+    },
+    Some("help") => Command::Help,
+    Some(command) => return error(format!("Unknown command: {command}")),
+  };
+  if let Some(extra) = positionals.next() {
+    return error(format!("Unexpected argument: {extra}"));
+  }
+  Ok(cli)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse_str(args: &str) -> Result<Cli, CliError> {
+    parse(args.split_whitespace().map(String::from))
+  }
+
+  #[test]
+  fn no_command_offers_the_saved_games() {
+    let cli = parse_str("--debug --seed 12").unwrap();
+    assert_eq!(cli.command, Command::Start);
+    assert!(cli.debug);
+    assert_eq!(cli.seed, Some(12));
+  }
+
+  #[test]
+  fn commands_take_their_arguments() {
+    assert_eq!(parse_str("new").unwrap().command, Command::New);
+    assert_eq!(parse_str("resume backups/a --no-sound").unwrap().command, Command::Resume { backup: Some(String::from("backups/a")) });
+    assert_eq!(parse_str("--from-backup backups/a").unwrap().command, Command::Resume { backup: Some(String::from("backups/a")) });
+    assert_eq!(parse_str("resume").unwrap().command, Command::Resume { backup: None });
+    assert_eq!(parse_str("simulate 20").unwrap().command, Command::Simulate { games: 20 });
+    assert_eq!(parse_str("inspect day-3 day-4").unwrap().command, Command::Inspect { backup: String::from("day-3"), other: Some(String::from("day-4")) });
+    assert_eq!(parse_str("new --serve 0.0.0.0:8080").unwrap().serve.as_deref(), Some("0.0.0.0:8080"));
+    assert_eq!(parse_str("--serve-tcp 0.0.0.0:2323").unwrap().serve_tcp.as_deref(), Some("0.0.0.0:2323"));
+  }
+
+  #[test]
+  fn invalid_arguments_are_reported() {
+    assert!(parse_str("--seed").is_err());
+    assert!(parse_str("--seed abc").is_err());
+    assert!(parse_str("--what").is_err());
+    assert!(parse_str("new extra").is_err());
+  }
+}
@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::error;
+use std::time::Duration;
+
+use serde::{Serialize, Deserialize};
+
+use crate::keys::{KeyStyle, MIN_COMBINATIONS};
+use crate::login::LoginRules;
+use crate::role::RoleCatalog;
+use crate::rules::GameRules;
+
+// When the host and resilient genomes are given to two players
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub enum GenomeAssignment {
+  #[default]
+  WithGeneticist, // only if someone can analyze them
+  Always,
+  Never,
+}
+
+// Everything that can be set before creating a game, loaded with --config and saved with the game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+  pub roles: BTreeMap<String, usize>, // roles dealt by default, by id
+  pub filler_role: Option<String>, // role given to the players left over by the default roles
+  pub min_players: usize,
+  pub genomes: GenomeAssignment,
+  pub sound: bool,
+  pub pause_seconds: u64, // how long the suspense lasts before announcing results
+  pub autosaves_kept: usize, // the backups written after each log out, the end of day backups are all kept
+  pub keys: KeyStyle, // how the secret codes of the players look
+  pub login: LoginRules,
+  pub idle_logout_seconds: u64, // a logged in player who stops answering is logged out, 0 to never do it
+  pub gm_password: Option<String>, // needed to go back in time during the game, which is impossible without it
+  pub rules: GameRules,
+}
+
+impl Default for GameConfig {
+  fn default() -> Self {
+    GameConfig {
+      roles: BTreeMap::from([
+        (String::from("patient0"), 1),
+        (String::from("physician"), 2),
+        (String::from("psychologist"), 1),
+        (String::from("it_engineer"), 1),
+        (String::from("spy"), 1),
+      ]),
+      filler_role: Some(String::from("astronaut")),
+      min_players: 7,
+      genomes: GenomeAssignment::WithGeneticist,
+      sound: true,
+      pause_seconds: 5,
+      autosaves_kept: 20,
+      keys: KeyStyle::default(),
+      login: LoginRules::default(),
+      idle_logout_seconds: 60,
+      gm_password: None,
+      rules: GameRules::default(),
+    }
+  }
+}
+
+impl GameConfig {
+  pub fn load (path: &str) -> Result<GameConfig, Box<dyn error::Error>> {
+    let content = fs::read_to_string(path).map_err(|error| format!("Cannot read {path}: {error}"))?;
+    toml::from_str(&content).map_err(|error| format!("Invalid configuration in {path}: {error}").into())
+  }
+
+  pub fn save (&self, path: &str) -> Result<(), Box<dyn error::Error>> {
+    fs::write(path, toml::to_string_pretty(self)?)?;
+    Ok(())
+  }
+
+  // The roles are referenced by id, so they have to exist in the roles used for the game
+  pub fn check_roles (&self, catalog: &RoleCatalog) -> Result<(), Box<dyn error::Error>> {
+    for id in self.roles.keys().chain(self.filler_role.iter()) {
+      if catalog.get(id).is_none() {
+        return Err(format!("Unknown role in the configuration: '{id}'").into());
+      }
+    }
+    Ok(())
+  }
+
+  pub fn check_keys (&self) -> Result<(), Box<dyn error::Error>> {
+    if self.keys.combinations() < MIN_COMBINATIONS {
+      return Err(format!("The player codes are too easy to guess, there are only {} possible codes", self.keys.combinations()).into());
+    }
+    Ok(())
+  }
+
+  pub fn pause (&self) -> Duration {
+    Duration::from_secs(self.pause_seconds)
+  }
+
+  pub fn idle_timeout (&self) -> Option<Duration> {
+    match self.idle_logout_seconds {
+      0 => None,
+      seconds => Some(Duration::from_secs(seconds)),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn saved_configuration_can_be_loaded_back() {
+    let mut config = GameConfig::default();
+    config.rules.early_end.push(crate::rules::EarlyEndRule::MutantsMajority);
+    config.sound = false;
+    let loaded: GameConfig = toml::from_str(&toml::to_string_pretty(&config).unwrap()).unwrap();
+    assert_eq!(loaded.roles, config.roles);
+    assert_eq!(loaded.rules.early_end, config.rules.early_end);
+    assert!(!loaded.sound);
+  }
+
+  #[test]
+  fn missing_settings_keep_their_default() {
+    let config: GameConfig = toml::from_str("min_players = 5\ngenomes = \"Always\"").unwrap();
+    assert_eq!(config.min_players, 5);
+    assert_eq!(config.genomes, GenomeAssignment::Always);
+    assert_eq!(config.pause_seconds, 5);
+    assert!(config.check_roles(&RoleCatalog::default()).is_ok());
+  }
+
+  #[test]
+  fn key_style_is_checked() {
+    let config: GameConfig = toml::from_str("[keys]\nstyle = \"Words\"\ncount = 2").unwrap();
+    assert_eq!(config.keys, KeyStyle::Words { count: 2 });
+    assert!(config.check_keys().is_ok());
+    let config: GameConfig = toml::from_str("[keys]\nstyle = \"Alphabet\"\nalphabet = \"AB\"\nlength = 4").unwrap();
+    assert!(config.check_keys().is_err());
+  }
+}
@@ -1,4 +1,4 @@
-use crate::{interface::Interface, game::Game};
+use crate::interface::Interface;
 
 
 pub static DEBUG_PLAYER_NAMES: [&str; 9] = [
@@ -13,7 +13,12 @@ pub static DEBUG_PLAYER_NAMES: [&str; 9] = [
   "River",
 ];
 
-pub fn mock_game_creator (interface: &mut Interface) {
+// When debugging, the keys are given in order instead of being random: 100, 101, 102...
+pub fn debug_key (player_idx: usize) -> String {
+  (100 + player_idx).to_string()
+}
+
+pub fn mock_game_creator (interface: &mut dyn Interface) {
   let mut inputs = Vec::new();
   inputs.push(String::from("1\n"));
   inputs.push(String::from("Koursk\n"));
@@ -23,22 +28,30 @@ pub fn mock_game_creator (interface: &mut Interface) {
     inputs.push(String::from(""));
   }
   inputs.push(String::from("5\n"));
+  inputs.push(String::from("mj\n")); // game master password
+  inputs.push(String::from("7\n"));
   interface.mock(inputs);
 }
 
-pub fn mock_game_vote_tie (interface: &mut Interface, game: &mut dyn Game) { // create votes to have a tie
+pub fn mock_game_vote_tie (interface: &mut dyn Interface) { // create votes to have a tie
   // This only works if the options (here the names of the players to vote for) are always proposed in the same order
   let mut inputs = Vec::new();
-  let get_player_key = |name: &str| &game.get_players().iter().find(|player| player.name == name).unwrap().key;
-  let mut idx = 0;
-  for name in DEBUG_PLAYER_NAMES {
-    let player_key = get_player_key(name);
+  for idx in 0..DEBUG_PLAYER_NAMES.len() { // the day starts with the election of the captain
+    inputs.push(String::from("0\n")); // log-in
+    inputs.push(debug_key(idx)); // key
+    inputs.push(String::from("1\n")); // vote for the captain
+    inputs.push(String::from("0\n"));
+    inputs.push(String::from("0\n")); // exit
+  }
+  inputs.push(String::from("2\n")); // election of the captain
+  inputs.push(String::from("\n"));
+  for idx in 0..DEBUG_PLAYER_NAMES.len() {
+    let player_key = debug_key(idx); // the players are created in that order by mock_game_creator
     inputs.push(String::from("0\n")); // log-in
-    inputs.push(String::from(player_key)); // key
+    inputs.push(player_key); // key
     inputs.push(String::from("1\n")); // vote to eliminate
     inputs.push((idx / 3).to_string());
     inputs.push(String::from("0\n")); // exit
-    idx += 1;
   }
   interface.mock(inputs);
 }
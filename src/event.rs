@@ -0,0 +1,150 @@
+use serde::{Serialize, Deserialize};
+
+use crate::{action::ActionType, game::Game, player::PlayerId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum EventCause {
+  Mutants,
+  Physicians,
+  CrewVote,
+  Player(PlayerId),
+  Unknown, // someone who could not be identified
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum EventKind {
+  LoggedIn { player: PlayerId },
+  Voted { voter: PlayerId, action: ActionType, target: Option<PlayerId> },
+  Infected { target: PlayerId },
+  InfectionResisted { target: PlayerId }, // resilient genome
+  Cured { target: PlayerId },
+  CureFailed { target: PlayerId }, // host genome or patient 0
+  CureUnneeded { target: PlayerId }, // the target was not infected
+  Killed { target: PlayerId },
+  Paralyzed { target: PlayerId },
+  Eliminated { target: PlayerId },
+  Hacked { hacker: PlayerId, target: PlayerId }, // the report sent to the target was intercepted
+  CaptainAppointed { captain: PlayerId },
+  LoginFailed { failures: u32 }, // consecutive failures on the terminal, the wrong code itself is not kept
+  TerminalLocked { seconds: u64 },
+}
+
+// Every change of the game state is recorded, so we can know afterward what happened and why
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameEvent {
+  pub date: u32,
+  pub cause: EventCause,
+  pub kind: EventKind,
+}
+
+impl GameEvent {
+  pub fn describe(&self, game: &dyn Game) -> String {
+    let name = |player: &PlayerId| game.get_player(*player).name.clone();
+    let cause = match self.cause {
+      EventCause::Mutants => String::from("les mutants"),
+      EventCause::Physicians => String::from("l'équipe médicale"),
+      EventCause::CrewVote => String::from("le vote de l'équipage"),
+      EventCause::Player(player) => name(&player),
+      EventCause::Unknown => String::from("un·e inconnu·e"),
+    };
+    let description = match &self.kind {
+      EventKind::LoggedIn { player } => format!("{} s'est identifié·e", name(player)),
+      EventKind::Voted { voter, action, target } => match target {
+        Some(target) => format!("{} a choisi {} pour l'action {:?}", name(voter), name(target), action),
+        None => format!("{} n'a choisi personne pour l'action {:?}", name(voter), action),
+      },
+      EventKind::Infected { target } => format!("{} a été infecté·e par {cause}", name(target)),
+      EventKind::InfectionResisted { target } => format!("{} a résisté à l'infection par {cause}", name(target)),
+      EventKind::Cured { target } => format!("{} a été soigné·e par {cause}", name(target)),
+      EventKind::CureFailed { target } => format!("{} a été traité·e par {cause}, sans effet sur la mutation", name(target)),
+      EventKind::CureUnneeded { target } => format!("{} a été traité·e par {cause}, sans être infecté·e", name(target)),
+      EventKind::Killed { target } => format!("{} a été tué·e par {cause}", name(target)),
+      EventKind::Paralyzed { target } => format!("{} a été paralysé·e par {cause}", name(target)),
+      EventKind::Eliminated { target } => format!("{} a été éliminé·e par {cause}", name(target)),
+      EventKind::Hacked { hacker, target } => format!("{} a piraté le rapport de {} ({})", name(hacker), name(target), game.get_player(*target).role),
+      EventKind::CaptainAppointed { captain } => format!("{} a été nommé·e capitaine par {cause}", name(captain)),
+      EventKind::LoginFailed { failures } => format!("Code invalide saisi par {cause} ({failures} échec·s consécutif·s)"),
+      EventKind::TerminalLocked { seconds } => format!("Terminal verrouillé pendant {seconds} secondes"),
+    };
+    format!("* Jour {}: {}", self.date, description)
+  }
+
+  // Logins and votes are not secret during the game, but are too verbose for a summary
+  pub fn is_notable(&self) -> bool {
+    !matches!(self.kind, EventKind::LoggedIn { .. } | EventKind::Voted { .. })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::SeedableRng;
+
+  use super::*;
+  use crate::backup::{backup_game, restore_game};
+  use crate::game::{GameAccess, GameRng, GameStatus, PlayerGame};
+  use crate::interface::scripted::ScriptedInterface;
+  use crate::login::{log_in, Terminal};
+  use crate::night::default_pipeline;
+  use crate::phases::run_elimination_phase;
+  use crate::player::Player;
+  use crate::role::RoleCatalog;
+
+  fn find_event(game: &GameStatus, kind: EventKind) -> &GameEvent {
+    game.get_events().iter().find(|event| event.kind == kind).unwrap_or_else(|| panic!("No event {kind:?}"))
+  }
+
+  #[test]
+  fn every_change_of_a_game_is_recorded_and_saved() {
+    let catalog = RoleCatalog::default();
+    let players = ["patient0", "physician", "hacker", "geneticist", "astronaut", "astronaut", "astronaut"].iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let mut game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), Default::default());
+    let ids = game.get_player_ids(&|_| true);
+
+    // Day 1: everyone logs in and votes against the player 5
+    for (idx, id) in ids.iter().enumerate() {
+      log_in(&mut game, Terminal::Local, idx.to_string());
+      game.get_player_game(*id).set_current_target(&ActionType::Eliminate, Some(ids[5]));
+    }
+    let mut interface = ScriptedInterface::new(["", "1", "1"].map(String::from).to_vec());
+    run_elimination_phase(&mut interface, &mut GameAccess::Owned(&mut game)).unwrap();
+
+    // Night 1: the mutants infect and paralyze, the physician cures, the hacker intercepts the report of the geneticist
+    game.get_mut_player(ids[0]).set_target(&ActionType::Infect, Some(ids[4]));
+    game.get_mut_player(ids[0]).set_target(&ActionType::Paralyze, Some(ids[6]));
+    game.get_mut_player(ids[1]).set_target(&ActionType::Cure, Some(ids[4]));
+    game.get_mut_player(ids[3]).set_target(&ActionType::Genomyze, Some(ids[0]));
+    game.get_mut_player(ids[2]).hacker_target = Some(catalog.get("geneticist").unwrap().clone());
+    default_pipeline().run(&mut game);
+    game.prepare_new_turn();
+
+    // Night 2: the mutants kill
+    game.get_mut_player(ids[0]).set_target(&ActionType::Infect, Some(ids[6]));
+    game.get_mut_player(ids[0]).mutant_kill = true;
+    default_pipeline().run(&mut game);
+
+    let expected = [
+      (EventKind::LoggedIn { player: ids[3] }, 1, EventCause::Player(ids[3])),
+      (EventKind::Voted { voter: ids[2], action: ActionType::Eliminate, target: Some(ids[5]) }, 1, EventCause::Player(ids[2])),
+      (EventKind::Eliminated { target: ids[5] }, 1, EventCause::CrewVote),
+      (EventKind::Infected { target: ids[4] }, 1, EventCause::Mutants),
+      (EventKind::Paralyzed { target: ids[6] }, 1, EventCause::Mutants),
+      (EventKind::Cured { target: ids[4] }, 1, EventCause::Physicians),
+      (EventKind::Hacked { hacker: ids[2], target: ids[3] }, 1, EventCause::Player(ids[2])),
+      (EventKind::Killed { target: ids[6] }, 2, EventCause::Mutants),
+    ];
+    for (kind, date, cause) in expected {
+      let event = find_event(&game, kind);
+      assert_eq!((event.date, event.cause), (date, cause), "{kind:?}");
+    }
+
+    // The whole chronicle is kept in the backups
+    let path = std::env::temp_dir().join(format!("rusty-spores-events-{}.json", std::process::id())).to_string_lossy().to_string();
+    backup_game(&game, path.clone(), None).unwrap();
+    let restored = restore_game(&path, None).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let events = |game: &GameStatus| serde_json::to_value(game.get_events()).unwrap();
+    assert_eq!(events(&restored), events(&game));
+  }
+}
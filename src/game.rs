@@ -1,16 +1,34 @@
+use std::collections::HashMap;
 use std::slice::Iter;
 use std::error;
 use std::time::SystemTime;
+use std::fs;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
 
 use serde::{Serialize, Deserialize};
+use rand_chacha::ChaCha8Rng;
 
-use crate::backup::{backup_game, restore_game};
+use crate::backup::{backup_game, last_backup_number, prune_backups, restore_game};
+use crate::event::{EventCause, EventKind, GameEvent};
 use crate::message::Message;
-use crate::player::{Player, PlayerId};
+use crate::player::{CrewMember, Player, PlayerId};
 use crate::action::ActionType;
-use crate::role::Role;
+use crate::role::{Role, RoleAction};
+use crate::phases::hacker::hackable_roles;
+use crate::rules::{EndReason, GameRules};
+use crate::config::GameConfig;
+use crate::rollback::{Snapshot, SnapshotMoment};
+use crate::login::{LoginGuard, Terminal};
+use crate::session::{SessionId, Sessions};
 
-#[derive(PartialEq, Serialize, Deserialize)]
+// All the randomness of a game comes from here, so a game can be replayed from its seed or its backups
+pub type GameRng = ChaCha8Rng;
+
+const AUTOSAVE_SEPARATOR: &str = "-autosave-";
+
+#[derive(PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum PhaseOfDay {
   Day,
   Twilight,
@@ -18,52 +36,153 @@ pub enum PhaseOfDay {
 
 #[derive(Serialize, Deserialize)]
 pub struct GameStatus {
-  #[serde(skip_deserializing)]
-  creation: u64, // this is to avoid collisions after loading a backup, so we want it unique
+  #[serde(default)]
+  creation: u64, // keeps apart the backups of the games with the same name, kept when the game is resumed
   name: String,
   date: u32,
   players: Vec<Player>,
-  current_player_id: Option<PlayerId>,
   debug: bool,
   phase: PhaseOfDay,
+  events: Vec<GameEvent>,
+  seed: u64,
+  rng: GameRng,
+  #[serde(default)]
+  config: GameConfig,
+  captain: Option<PlayerId>,
+  captain_successor: Option<PlayerId>,
+  end_reason: Option<EndReason>,
+  #[serde(default)]
+  autosaves: u32, // number of the last autosave, they are numbered across days to know which ones are the oldest
+  #[serde(skip)]
+  backup_dir: Option<String>, // where to write the backups, nowhere by default (simulations and tests)
+  #[serde(skip)]
+  snapshots: Vec<Snapshot>,
+  #[serde(skip)]
+  login_guards: HashMap<Terminal, LoginGuard>, // each terminal is locked on its own
+  #[serde(skip)]
+  sessions: Sessions, // the players logged in, one cursor per terminal
+  #[serde(skip)]
+  game_master_busy: bool, // the game master is running one of their actions, the api must not run a phase meanwhile
 }
 
 impl GameStatus {
-  pub fn new (name: String, players: Vec<Player>, debug: bool) -> GameStatus {
+  pub fn new (name: String, players: Vec<Player>, debug: bool, seed: u64, rng: GameRng, config: GameConfig) -> GameStatus {
     GameStatus{
       creation: SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs(),
       name,
       players,
-      current_player_id: None,
       debug,
       date: 1,
       phase: PhaseOfDay::Day,
+      events: Vec::new(),
+      seed,
+      rng,
+      config,
+      captain: None,
+      captain_successor: None,
+      end_reason: None,
+      autosaves: 0,
+      backup_dir: None,
+      snapshots: Vec::new(),
+      login_guards: HashMap::new(),
+      sessions: Sessions::default(),
+      game_master_busy: false,
     }
   }
 
-  pub fn restore_from_backup (path: &String) -> Result<GameStatus, Box<dyn error::Error>> {
-    let mut game = restore_game(path)?;
-    game.creation = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+  pub fn game_master_busy (&self) -> bool {
+    self.game_master_busy
+  }
+
+  pub fn set_game_master_busy (&mut self, busy: bool) {
+    self.game_master_busy = busy;
+  }
+
+  // All the backups of a game start with it, the creation keeps apart the games with the same name
+  fn backup_prefix (&self) -> String {
+    format!("sporz-{}-{}-", self.name, self.creation)
+  }
+
+  pub fn restore_from_backup (path: &String, passphrase: Option<&str>) -> Result<GameStatus, Box<dyn error::Error>> {
+    let mut game = restore_game(path, passphrase)?;
+    if game.creation == 0 { // older backups did not keep it
+      game.creation = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+    }
     return Ok(game);
   }
 }
 
+// The game as the actions of the game master see it (end of the day, night, rollback...)
+// When it is shared with the devices of the players, it is only locked for each step, never while the terminal waits for an answer
+pub enum GameAccess<'a> {
+  Owned(&'a mut dyn Game),
+  Shared(&'a Mutex<GameStatus>),
+}
+
+pub enum GameGuard<'a> {
+  Owned(&'a mut dyn Game),
+  Shared(MutexGuard<'a, GameStatus>),
+}
+
+impl GameAccess<'_> {
+  // The guard must be dropped before asking anything: keep it to one statement, or to a block without inputs
+  pub fn lock (&mut self) -> GameGuard<'_> {
+    match self {
+      GameAccess::Owned(game) => GameGuard::Owned(&mut **game),
+      GameAccess::Shared(game) => GameGuard::Shared(game.lock().unwrap()), // only poisoned if a server thread panicked
+    }
+  }
+}
+
+impl<'a> Deref for GameGuard<'a> {
+  type Target = dyn Game + 'a;
+
+  fn deref (&self) -> &Self::Target {
+    match self {
+      GameGuard::Owned(game) => &**game,
+      GameGuard::Shared(game) => &**game,
+    }
+  }
+}
+
+impl DerefMut for GameGuard<'_> {
+  fn deref_mut (&mut self) -> &mut Self::Target {
+    match self {
+      GameGuard::Owned(game) => &mut **game,
+      GameGuard::Shared(game) => &mut **game,
+    }
+  }
+}
+
 pub struct PlayerTurn<'a> {
   game: &'a mut GameStatus,
   current_player_id: PlayerId,
+  session: Option<SessionId>, // none when the core plays for the player (simulations)
 }
 
 pub trait Game {
   fn debug(&self) -> bool;
-  fn backup(&self, path: &str) -> Result<(), Box<dyn error::Error>>;
+  fn backup(&self) -> Result<(), Box<dyn error::Error>>; // does nothing if there is no backup directory
+  fn autosave(&mut self) -> Result<(), Box<dyn error::Error>>; // same, but only the latest autosaves are kept
+  fn set_backup_dir(&mut self, backup_dir: Option<String>);
+  fn take_snapshot(&mut self, moment: SnapshotMoment) -> Result<(), Box<dyn error::Error>>;
+  fn get_snapshots(&self) -> &Vec<Snapshot>;
+  fn rollback(&mut self, snapshot: usize) -> Result<(), Box<dyn error::Error>>; // the later snapshots are dropped
   fn get_name(&self) -> &str;
+  fn get_seed(&self) -> u64;
+  fn rng(&mut self) -> &mut GameRng;
   fn get_date(&self) -> u32;
   fn get_phase_of_day(&self) -> &PhaseOfDay;
   fn set_phase_of_day(&mut self, phase: PhaseOfDay);
+  fn get_config(&self) -> &GameConfig;
+  fn get_rules(&self) -> &GameRules;
   fn ended(&self) -> bool;
+  fn get_end_reason(&self) -> Option<EndReason>;
+  fn check_end_of_game(&mut self); // To be called after each change of the balance of power (night, elimination)
   fn prepare_new_turn(&mut self);
 
   fn get_player_id_from_key(&self, key: String) -> Option<PlayerId>;
+  fn get_login_guard(&mut self, terminal: Terminal) -> &mut LoginGuard;
 
   fn get_player(&self, id: PlayerId) -> &Player;
   fn get_mut_player(&mut self, id: PlayerId) -> &mut Player;
@@ -77,17 +196,43 @@ pub trait Game {
   fn broadcast (&mut self, message: Message);
   fn limited_broadcast(&mut self, message: Message, predicate: &dyn Fn(&&mut &mut Player) -> bool);
 
-  fn get_current_player_id(&self) -> Option<PlayerId>;
-  fn set_current_player_id(&mut self, player: Option<PlayerId>);
+  fn record_event(&mut self, cause: EventCause, kind: EventKind);
+  fn get_events(&self) -> &Vec<GameEvent>;
+
+  fn get_captain(&self) -> Option<PlayerId>;
+  fn set_captain(&mut self, captain: Option<PlayerId>);
+  fn get_captain_successor(&self) -> Option<PlayerId>;
+  fn set_captain_successor(&mut self, successor: Option<PlayerId>);
+
+  fn open_session(&mut self, player: PlayerId) -> SessionId;
+  fn close_session(&mut self, session: &SessionId);
+  fn get_session_player(&self, session: &SessionId) -> Option<PlayerId>;
+  fn get_session_game<'a> (&'a mut self, session: &SessionId) -> Option<PlayerTurn<'a>>; // none once the session is closed
   fn get_player_game<'a> (&'a mut self, current_player_id: PlayerId) -> PlayerTurn<'a>;
 }
 
-pub trait PlayerGame: Game {
+// The game as seen by one player: their own player, what the whole crew knows, and what their role tells them
+// The other players are only seen as crew members, a session cannot read nor change their data
+pub trait PlayerGame {
   fn get_current_player<'a>(&'a self) -> &'a Player;
   fn get_mut_current_player<'a>(&'a mut self) -> &'a mut Player;
-  fn get_current_target(&self, action: &ActionType) -> Option<&Player>;
+  fn get_current_target(&self, action: &ActionType) -> Option<CrewMember>;
   fn set_current_target(&mut self, action: &ActionType, target: Option<PlayerId>);
-  fn set_current_target_p(&mut self, action: &ActionType, target: &Option<&Player>);
+  fn log_out(&mut self); // only closes the session of this turn, the other sessions of the player stay open
+  fn autosave(&mut self) -> Result<(), Box<dyn error::Error>>;
+
+  fn debug(&self) -> bool;
+  fn get_date(&self) -> u32;
+  fn get_phase_of_day(&self) -> &PhaseOfDay;
+  fn get_rules(&self) -> &GameRules;
+  fn get_captain(&self) -> Option<PlayerId>;
+  fn get_crew_member(&self, id: PlayerId) -> CrewMember;
+  fn get_crew(&self) -> Vec<CrewMember>; // returns only alive players
+
+  fn get_medical_team(&self) -> Vec<CrewMember>; // the living physicians, only for a physician
+  fn get_hackable_roles(&self) -> Vec<Role>; // only for the hacker
+  fn get_captain_successor(&self) -> Option<PlayerId>; // only for the captain
+  fn set_captain_successor(&mut self, successor: Option<PlayerId>); // ignored unless the player is the captain
 }
 
 impl Game for GameStatus {
@@ -95,14 +240,71 @@ impl Game for GameStatus {
     return self.debug;
   }
 
-  fn backup(&self, path: &str) -> Result<(), Box<dyn error::Error>> {
-    return backup_game(self, format!("{path}sporz-{}-{}-day-{}", &self.name, &self.creation, &self.date));
+  fn backup(&self) -> Result<(), Box<dyn error::Error>> {
+    let Some(backup_dir) = &self.backup_dir else {
+      return Ok(());
+    };
+    fs::create_dir_all(backup_dir)?;
+    let path = Path::new(backup_dir).join(format!("{}day-{}", self.backup_prefix(), &self.date));
+    backup_game(self, path.to_string_lossy().to_string(), self.config.gm_password.as_deref())
+  }
+
+  fn autosave(&mut self) -> Result<(), Box<dyn error::Error>> {
+    let Some(backup_dir) = self.backup_dir.clone() else {
+      return Ok(());
+    };
+    fs::create_dir_all(&backup_dir)?;
+    self.autosaves += 1;
+    let path = Path::new(&backup_dir).join(format!("{}day-{}{AUTOSAVE_SEPARATOR}{}", self.backup_prefix(), &self.date, self.autosaves));
+    backup_game(self, path.to_string_lossy().to_string(), self.config.gm_password.as_deref())?;
+    prune_backups(Path::new(&backup_dir), &self.backup_prefix(), AUTOSAVE_SEPARATOR, self.config.autosaves_kept)
+  }
+
+  fn set_backup_dir(&mut self, backup_dir: Option<String>) {
+    // A game resumed from an older backup numbers its autosaves after the ones already written, so they are not pruned first
+    if let Some(backup_dir) = &backup_dir {
+      self.autosaves = self.autosaves.max(last_backup_number(Path::new(backup_dir), &self.backup_prefix(), AUTOSAVE_SEPARATOR));
+    }
+    self.backup_dir = backup_dir;
+  }
+
+  fn take_snapshot(&mut self, moment: SnapshotMoment) -> Result<(), Box<dyn error::Error>> {
+    let state = serde_json::to_value(&*self)?;
+    self.snapshots.push(Snapshot { date: self.date, moment, state });
+    Ok(())
+  }
+
+  fn get_snapshots(&self) -> &Vec<Snapshot> {
+    &self.snapshots
+  }
+
+  fn rollback(&mut self, snapshot: usize) -> Result<(), Box<dyn error::Error>> {
+    let mut game: GameStatus = serde_json::from_value(self.snapshots[snapshot].state.clone())?;
+    // What is not part of the game itself stays as it is
+    game.creation = self.creation;
+    game.autosaves = self.autosaves; // so the next autosaves are not mistaken for older ones
+    game.backup_dir = self.backup_dir.take();
+    self.snapshots.truncate(snapshot + 1);
+    game.snapshots = std::mem::take(&mut self.snapshots);
+    game.sessions = std::mem::take(&mut self.sessions); // the players stay logged in
+    game.login_guards = std::mem::take(&mut self.login_guards); // a rollback does not give new attempts
+    game.game_master_busy = self.game_master_busy;
+    *self = game;
+    Ok(())
   }
 
   fn get_name(&self) -> &str {
     return self.name.as_str();
   }
 
+  fn get_seed(&self) -> u64 {
+    self.seed
+  }
+
+  fn rng(&mut self) -> &mut GameRng {
+    &mut self.rng
+  }
+
   fn get_date(&self) -> u32 {
     return self.date;
   }
@@ -115,11 +317,20 @@ impl Game for GameStatus {
     self.phase = phase;
   }
 
+  fn get_config(&self) -> &GameConfig {
+    &self.config
+  }
+
+  fn get_rules(&self) -> &GameRules {
+    &self.config.rules
+  }
+
   fn get_player_id_from_key(&self, key: String) -> Option<PlayerId> {
-    match self.players.iter().find(|player| player.key == key) {
-      Some(player) => Some(player.id.clone()),
-      None => None,
-    }
+    self.players.iter().find(|player| player.check_key(&key)).map(|player| player.id)
+  }
+
+  fn get_login_guard(&mut self, terminal: Terminal) -> &mut LoginGuard {
+    self.login_guards.entry(terminal).or_default()
   }
 
   fn get_player(&self, id: PlayerId) -> &Player {
@@ -151,30 +362,23 @@ impl Game for GameStatus {
   fn send_message(&mut self, target: PlayerId, source: String, content: String) {
     let current_date = self.get_date();
 
-    let hackers = self.players.iter_mut().filter(|player| player.alive == true && player.role == Role::Hacker);
-    for hacker in hackers {
-      if hacker.hacker_target == Some(Role::ITEngineer) && source == "Système de diagnostique" {
-        hacker.send_message(Message {
-          date: current_date,
-          source: format!("Hacked {}", source),
-          content: content.clone(),
-        });
-        hacker.spy_info.woke_up = true; // Here and below, not great, only wake up if something happened
-      } else if hacker.hacker_target == Some(Role::Geneticist) && source == "GenoTech v0.17" {
-        hacker.send_message(Message {
-          date: current_date,
-          source: format!("Hacked {}", source),
-          content: content.clone(),
-        });
-        hacker.spy_info.woke_up = true;
-      } else if hacker.hacker_target == Some(Role::Spy) && source == "Stalker IV" {
-        hacker.send_message(Message {
-          date: current_date,
-          source: format!("Hacked {}", source),
-          content: content.clone(),
-        });
-        hacker.spy_info.woke_up = true;
-      }
+    // Hackers intercept the reports sent to the role they targeted, if that role can be hacked
+    let target_role = self.get_player(target).role.clone();
+    let hackable = target_role.hackable && target_role.actions.iter().any(|action| action.report_source() == Some(source.as_str()));
+    let mut hacks = Vec::new();
+    let hackers = self.players.iter_mut()
+      .filter(|player| player.alive && player.can(RoleAction::Hack) && player.hacker_target.as_ref().map(|role| &role.id) == Some(&target_role.id));
+    for hacker in hackers.filter(|_| hackable) {
+      hacker.send_message(Message {
+        date: current_date,
+        source: format!("Hacked {}", source),
+        content: content.clone(),
+      });
+      hacker.spy_info.woke_up = true; // not great, only wake up if something happened
+      hacks.push(hacker.id);
+    }
+    for hacker in hacks {
+      self.record_event(EventCause::Player(hacker), EventKind::Hacked { hacker, target });
     }
 
     let player = self.get_mut_player(target);
@@ -201,6 +405,14 @@ impl Game for GameStatus {
     return self.get_players().iter().filter(predicate).map(|player| player.id).collect();
   }
 
+  fn record_event(&mut self, cause: EventCause, kind: EventKind) {
+    self.events.push(GameEvent { date: self.date, cause, kind });
+  }
+
+  fn get_events(&self) -> &Vec<GameEvent> {
+    &self.events
+  }
+
   fn prepare_new_turn(&mut self) {
     self.players.iter_mut().for_each(|player| player.prepare_new_turn());
     self.date += 1;
@@ -208,128 +420,148 @@ impl Game for GameStatus {
   }
 
   fn ended(&self) -> bool {
-    let healthy_players = self.get_players().iter().filter(|player| !player.infected).count();
-    healthy_players == 0 || healthy_players == self.get_players().len()
+    self.end_reason.is_some()
   }
 
-  fn get_current_player_id(&self) -> Option<PlayerId> {
-    self.current_player_id
+  fn get_end_reason(&self) -> Option<EndReason> {
+    self.end_reason
   }
 
-  fn set_current_player_id(&mut self, player: Option<PlayerId>) {
-    self.current_player_id = player;
+  fn check_end_of_game(&mut self) {
+    let living_players = self.get_players();
+    let mutants = living_players.iter().filter(|player| player.infected).count();
+    let mutants_side = living_players.iter().filter(|player| player.sides_with_mutants()).count();
+    self.end_reason = if mutants == 0 {
+      Some(EndReason::MutantsEliminated)
+    } else if mutants_side == living_players.len() {
+      Some(EndReason::ShipTakenOver)
+    } else {
+      self.config.rules.early_end.iter()
+        .find(|rule| rule.applies(&living_players))
+        .map(|rule| EndReason::EarlyEnd(*rule))
+    };
   }
 
-  fn get_player_game<'a> (&'a mut self, current_player_id: PlayerId) -> PlayerTurn<'a> {
-    return PlayerTurn{ game: self, current_player_id };
+  fn get_captain(&self) -> Option<PlayerId> {
+    self.captain
   }
-}
 
-impl <'b> PlayerGame for PlayerTurn<'b> {
-  fn get_current_player<'a>(&'a self) -> &'a Player {
-    &self.current_player_id.get_player(&self.game.players)
+  fn set_captain(&mut self, captain: Option<PlayerId>) {
+    self.captain = captain;
+    self.captain_successor = None; // The new captain chooses their own successor
   }
 
-  fn get_mut_current_player<'a>(&'a mut self) -> &'a mut Player {
-    self.current_player_id.get_mut_player(&mut self.game.players)
+  fn get_captain_successor(&self) -> Option<PlayerId> {
+    self.captain_successor
   }
 
-  fn get_current_target(&self, action: &ActionType) -> Option<&Player> {
-    self.get_current_player().get_target(action).map(|player_id| player_id.get_player(&self.game.players))
+  fn set_captain_successor(&mut self, successor: Option<PlayerId>) {
+    self.captain_successor = successor;
   }
 
-  fn set_current_target(&mut self, action: &ActionType, target: Option<PlayerId>) {
-    self.get_mut_current_player().set_target(action, target);
+  fn open_session(&mut self, player: PlayerId) -> SessionId {
+    self.sessions.open(player)
   }
 
-  fn set_current_target_p(&mut self, action: &ActionType, target: &Option<&Player>) {
-    // This one is problematic, because it needs a mutable game, but the &player is borrowed from the game...
-    self.get_mut_current_player().set_target(action, target.map(|player| player.id));
+  fn close_session(&mut self, session: &SessionId) {
+    self.sessions.close(session)
   }
-}
 
-impl <'b> Game for PlayerTurn<'b> { // Proxy everything to self.game
-  fn debug(&self) -> bool {
-    self.game.debug()
+  fn get_session_player(&self, session: &SessionId) -> Option<PlayerId> {
+    self.sessions.player(session)
   }
 
-  fn backup(&self, path: &str) -> Result<(), Box<dyn error::Error>> {
-    self.game.backup(path)
+  fn get_session_game<'a> (&'a mut self, session: &SessionId) -> Option<PlayerTurn<'a>> {
+    let current_player_id = self.sessions.player(session)?;
+    Some(PlayerTurn{ game: self, current_player_id, session: Some(session.clone()) })
   }
 
-  fn get_name(&self) -> &str {
-    self.game.get_name()
-  }
-
-  fn get_date(&self) -> u32 {
-    self.game.get_date()
+  fn get_player_game<'a> (&'a mut self, current_player_id: PlayerId) -> PlayerTurn<'a> {
+    PlayerTurn{ game: self, current_player_id, session: None }
   }
+}
 
-  fn get_phase_of_day(&self) -> &PhaseOfDay {
-    &self.game.phase
+impl <'b> PlayerGame for PlayerTurn<'b> {
+  fn get_current_player<'a>(&'a self) -> &'a Player {
+    &self.current_player_id.get_player(&self.game.players)
   }
 
-  fn set_phase_of_day(&mut self, phase: PhaseOfDay) {
-    self.game.set_phase_of_day(phase);
+  fn get_mut_current_player<'a>(&'a mut self) -> &'a mut Player {
+    self.current_player_id.get_mut_player(&mut self.game.players)
   }
 
-  fn get_player_id_from_key(&self, key: String) -> Option<PlayerId> {
-    self.game.get_player_id_from_key(key)
+  fn get_current_target(&self, action: &ActionType) -> Option<CrewMember> {
+    self.get_current_player().get_target(action).map(|player_id| self.get_crew_member(*player_id))
   }
 
-  fn get_player(&self, id: PlayerId) -> &Player {
-    self.game.get_player(id)
+  fn set_current_target(&mut self, action: &ActionType, target: Option<PlayerId>) {
+    self.get_mut_current_player().set_target(action, target);
+    let voter = self.current_player_id;
+    self.game.record_event(EventCause::Player(voter), EventKind::Voted { voter, action: *action, target });
   }
 
-  fn get_mut_player(&mut self, id: PlayerId) -> &mut Player {
-    self.game.get_mut_player(id)
+  fn log_out(&mut self) {
+    if let Some(session) = self.session.take() {
+      self.game.close_session(&session);
+    }
   }
 
-  fn get_all_players(&self) -> Iter<'_, Player> {
-    self.game.get_all_players()
+  fn autosave(&mut self) -> Result<(), Box<dyn error::Error>> {
+    self.game.autosave()
   }
 
-  fn get_players(&self) -> Vec<&Player> {
-    self.game.get_players()
+  fn debug(&self) -> bool {
+    self.game.debug()
   }
 
-  fn get_mut_players(&mut self) -> Vec<&mut Player> {
-    self.game.get_mut_players()
+  fn get_date(&self) -> u32 {
+    self.game.get_date()
   }
 
-  fn send_message(&mut self, target: PlayerId, source: String, content: String) {
-    self.game.send_message(target, source, content)
+  fn get_phase_of_day(&self) -> &PhaseOfDay {
+    self.game.get_phase_of_day()
   }
 
-  fn broadcast (&mut self, message: Message) {
-    self.game.broadcast(message)
+  fn get_rules(&self) -> &GameRules {
+    self.game.get_rules()
   }
 
-  fn limited_broadcast(&mut self, message: Message, predicate: &dyn Fn(&&mut &mut Player) -> bool) {
-    self.game.limited_broadcast(message, predicate)
+  fn get_captain(&self) -> Option<PlayerId> {
+    self.game.get_captain()
   }
 
-  fn get_player_ids(&self, predicate: &dyn Fn(&&&Player) -> bool) -> Vec<PlayerId> {
-    self.game.get_player_ids(predicate)
+  fn get_crew_member(&self, id: PlayerId) -> CrewMember {
+    self.game.get_player(id).crew_member()
   }
 
-  fn prepare_new_turn(&mut self) {
-    self.game.prepare_new_turn()
+  fn get_crew(&self) -> Vec<CrewMember> {
+    self.game.get_players().into_iter().map(|player| player.crew_member()).collect()
   }
 
-  fn ended(&self) -> bool {
-    self.game.ended()
+  fn get_medical_team(&self) -> Vec<CrewMember> {
+    if !self.get_current_player().can(RoleAction::Cure) {
+      return Vec::new();
+    }
+    self.game.get_players().into_iter()
+      .filter(|player| player.can(RoleAction::Cure))
+      .map(|player| player.crew_member())
+      .collect()
   }
 
-  fn get_current_player_id(&self) -> Option<PlayerId> {
-    self.game.get_current_player_id()
+  fn get_hackable_roles(&self) -> Vec<Role> {
+    if !self.get_current_player().can(RoleAction::Hack) {
+      return Vec::new();
+    }
+    hackable_roles(self.game)
   }
 
-  fn set_current_player_id(&mut self, player: Option<PlayerId>) {
-    self.game.set_current_player_id(player)
+  fn get_captain_successor(&self) -> Option<PlayerId> {
+    self.game.get_captain_successor().filter(|_| self.game.get_captain() == Some(self.current_player_id))
   }
 
-  fn get_player_game<'a> (&'a mut self, current_player_id: PlayerId) -> PlayerTurn<'a> {
-    self.game.get_player_game(current_player_id)
+  fn set_captain_successor(&mut self, successor: Option<PlayerId>) {
+    if self.game.get_captain() == Some(self.current_player_id) {
+      self.game.set_captain_successor(successor);
+    }
   }
 }
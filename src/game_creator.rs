@@ -4,102 +4,131 @@ use std::cmp;
 use std::fmt;
 use std::error;
 
-use rand::thread_rng;
+use rand::SeedableRng;
 use rand::seq::SliceRandom;
 
-use crate::game::GameStatus;
-use crate::interface::Interface;
+use crate::config::{GameConfig, GenomeAssignment};
+use crate::debug::debug_key;
+use crate::game::{GameRng, GameStatus};
+use crate::interface::{Idle, Interface};
 use crate::player::Player;
-use crate::role::Role;
+use crate::role::{Role, RoleAction, RoleCatalog};
 
 struct GameCreator<'a> {
   debug: bool,
-  interface: &'a mut Interface,
-  id_keys: Vec<String>,
+  interface: &'a mut dyn Interface,
+  keys_given: usize,
   player_names: BTreeMap<String, String>, // want a sorted map for simpler debug
+  catalog: RoleCatalog,
+  config: GameConfig,
   custom_roles: Option<HashMap<Role, usize>>,
   ship_name: Option<String>,
+  seed: u64,
+  rng: GameRng,
 }
 
 impl <'a> GameCreator<'a> {
-  pub fn new (interface: &'a mut Interface, debug: bool) -> GameCreator<'a> {
+  pub fn new (interface: &'a mut dyn Interface, debug: bool, seed: u64, catalog: RoleCatalog, config: GameConfig) -> GameCreator<'a> {
+    let rng = GameRng::seed_from_u64(seed);
     GameCreator {
       debug,
       interface,
-      id_keys: generaye_keys(debug),
+      keys_given: 0,
       player_names: BTreeMap::new(),
+      catalog,
+      config,
       custom_roles: None,
       ship_name: None,
+      seed,
+      rng,
     }
   }
 
-  pub fn name_ship (&mut self) {
-    let name = self.interface.user_non_empty_input("Quel est le nom de votre vaisseau?");
+  pub fn name_ship (&mut self) -> Result<(), Idle> {
+    let name = self.interface.user_non_empty_input("Quel est le nom de votre vaisseau?")?;
     self.ship_name = Some(name);
+    Ok(())
   }
 
-  pub fn add_player (&mut self) {
+  pub fn add_player (&mut self) -> Result<(), Idle> {
     let name = loop {
-      let name = self.interface.user_non_empty_input("Sous quel dénominatif souhaitez-vous être identifié·e?");
+      let name = self.interface.user_non_empty_input("Sous quel dénominatif souhaitez-vous être identifié·e?")?;
       if self.player_names.contains_key(&name) {
-        println!("Désolé, ce dénominatif n'est pas disponible");
+        self.interface.println("Désolé, ce dénominatif n'est pas disponible");
         continue;
       }
       break name;
     };
-    let key = self.id_keys.pop().unwrap().to_string();
-    self.interface.user_validate(format!("{name}, votre code secret est: '{key}', ne l'oubliez pas! Vous en aurez besoin pour vous identifier.").as_str());
+    let key = self.new_key();
+    self.interface.user_validate(format!("{name}, votre code secret est: '{key}', ne l'oubliez pas! Vous en aurez besoin pour vous identifier.").as_str())?;
     self.player_names.insert(name, key);
+    Ok(())
   }
 
-  pub fn remove_player (&mut self) {
+  fn new_key (&mut self) -> String {
+    self.keys_given += 1;
+    if self.debug {
+      return debug_key(self.keys_given - 1);
+    }
+    loop {
+      let key = self.config.keys.generate(&mut rand::thread_rng()); // not the game's rng, the codes must not depend on the seed
+      if !self.player_names.values().any(|other| other == &key) {
+        return key;
+      }
+    }
+  }
+
+  pub fn remove_player (&mut self) -> Result<(), Idle> {
     if self.player_names.len() == 0 {
-      self.interface.user_validate("Désolé, il n'y a aucun membre d'équipage à supprimer");
+      self.interface.user_validate("Désolé, il n'y a aucun membre d'équipage à supprimer")?;
       self.interface.clear_terminal();
     } else {
-      let selected = self.interface.user_select_from(self.player_names.keys());
-      let key = self.player_names.remove(&selected.clone());
-      self.id_keys.push(key.unwrap()); // The key cannot not be there
+      let selected = self.interface.user_select_from(self.player_names.keys())?;
+      self.player_names.remove(&selected.clone());
     }
+    Ok(())
   }
 
-  pub fn update_roles (&mut self) {
-    let all_roles = vec![Role::Patient0, Role::Psychologist, Role::Physician, Role::Geneticist, Role::ITEngineer, Role::Spy, Role::Hacker, Role::Traitor, Role::Astronaut];
+  pub fn update_roles (&mut self) -> Result<(), Idle> {
+    let all_roles = self.catalog.roles().clone();
     match &mut self.custom_roles {
       None => {
-        println!("La partie est configurée pour utiliser les roles par défaut:");
+        self.interface.println("La partie est configurée pour utiliser les roles par défaut:");
       },
       Some(_) => {
-        println!("La partie est configurée pour utiliser des roles personalisés:");
+        self.interface.println("La partie est configurée pour utiliser des roles personalisés:");
       },
     }
 
     let default_roles = self.get_default_roles();
     let roles = self.custom_roles.as_ref().unwrap_or(&default_roles);
+    let count_roles = |predicate: &dyn Fn(&Role) -> bool| roles.iter()
+      .filter_map(|(role, count)| if predicate(role) { Some(count) } else { None })
+      .sum::<usize>();
+    let initially_infected = count_roles(&|role| role.infected);
+    let physicians = count_roles(&|role| role.can(RoleAction::Cure));
+    let hackable_roles = count_roles(&|role| role.hackable);
     for role in &all_roles {
       let count = roles.get(role).unwrap_or(&0);
-      if role == &Role::Patient0 && count < &1 {
-        println!("* {}: {} -- Attention, jouer sans {} risque de mener à une partie très courte", role, count, role);
-      } else if role == &Role::Physician && count < &2 {
-        println!("* {}: {} -- Attention, jouer avec moins de 2 {} est très difficile", role, count, role);
-      } else if role == &Role::Hacker && count >= &1 {
-        let hackable_roles = roles.get(&Role::Spy).unwrap_or(&0)
-          + roles.get(&Role::Psychologist).unwrap_or(&0)
-          + roles.get(&Role::Geneticist).unwrap_or(&0);
+      if role.infected && initially_infected < 1 {
+        self.interface.println(format!("* {}: {} -- Attention, jouer sans {} risque de mener à une partie très courte", role, count, role).as_str());
+      } else if role.can(RoleAction::Cure) && physicians < 2 {
+        self.interface.println(format!("* {}: {} -- Attention, jouer avec moins de 2 {} est très difficile", role, count, role).as_str());
+      } else if role.can(RoleAction::Hack) && count >= &1 {
         if hackable_roles < 2 {
-          println!("* {}: {} -- Attention, peu de cibles disponibles pour le {}: {}", role, count, role, hackable_roles);
+          self.interface.println(format!("* {}: {} -- Attention, peu de cibles disponibles pour le {}: {}", role, count, role, hackable_roles).as_str());
         }
       } else {
-        println!("* {}: {}", role, count);
+        self.interface.println(format!("* {}: {}", role, count).as_str());
       }
     }
-    println!();
+    self.interface.println("");
     
     let modify = "Modifier les roles à utiliser";
     let use_default = "Utiliser les roles par défaut";
     let ret = "Retour";
     let choices = vec![modify, use_default, ret];
-    let choice = self.interface.user_select_from(choices.iter());
+    let choice = self.interface.user_select_from(choices.iter())?;
     match *choice {
       ref x if x == &ret => (),
       ref x if x == &use_default => self.custom_roles = None,
@@ -107,22 +136,45 @@ impl <'a> GameCreator<'a> {
         if self.custom_roles.is_none() {
           self.custom_roles = Some(default_roles);
         }
-        println!("");
-        println!("Quel role voulez vous modifier?");
-        let role = self.interface.user_select_from(all_roles.iter());
+        self.interface.println("");
+        self.interface.println("Quel role voulez vous modifier?");
+        let role = self.interface.user_select_from(all_roles.iter())?;
         let count = loop {
-          let count = self.interface.user_non_empty_input(format!("Combient de {role} voulez vous?").as_str());
+          let count = self.interface.user_non_empty_input(format!("Combient de {role} voulez vous?").as_str())?;
           let count = count.parse::<usize>();
           if let Ok(count) = count {
             break count;
           }
-          println!("Avec un nombre ce serait pas mal!")
+          self.interface.println("Avec un nombre ce serait pas mal!");
         };
-        self.custom_roles.as_mut().unwrap().insert(*role, count);
-        self.update_roles();
+        self.custom_roles.as_mut().unwrap().insert(role.clone(), count);
+        self.update_roles()?;
       }
       _ => panic!(), // beurk
     }
+    Ok(())
+  }
+
+  // Only for this game: it protects the rollback and encrypts the backups
+  pub fn set_gm_password (&mut self) -> Result<(), Idle> {
+    let password = self.interface.user_non_empty_input("Quel mot de passe pour le maître du jeu? (il permettra de revenir en arrière pendant la partie, et de lire les sauvegardes qui seront chiffrées)")?;
+    self.config.gm_password = Some(password);
+    Ok(())
+  }
+
+  // Saves the current configuration, with the selected roles as the default ones
+  pub fn save_config (&mut self) -> Result<(), Idle> {
+    let mut config = self.config.clone();
+    config.gm_password = None; // it is specific to this game, and should not end up in a file
+    if let Some(custom_roles) = &self.custom_roles {
+      config.roles = custom_roles.iter().map(|(role, count)| (role.id.clone(), *count)).collect();
+      config.filler_role = None;
+    }
+    let path = self.interface.user_non_empty_input("Dans quel fichier enregistrer la configuration? (par exemple game.toml)")?;
+    match config.save(&path) {
+      Ok(()) => self.interface.user_validate(format!("Configuration enregistrée dans {path}, utilisez --config {path} pour la réutiliser").as_str()),
+      Err(error) => self.interface.user_validate(format!("Impossible d'enregistrer la configuration: {error}").as_str()),
+    }
   }
 
   fn get_roles (&self) -> Vec<Role> {
@@ -135,47 +187,60 @@ impl <'a> GameCreator<'a> {
         roles.push(role.clone());
       }
     }
-    if self.debug { // Keep the roles ordered when debugging
-      roles.sort();
-    } else {
-      roles.shuffle(&mut thread_rng());
-    }
+    roles.sort_by_key(|role| self.catalog.position(role)); // Shuffled only when creating the game, so the deal only depends on the seed
     return roles;
   }
 
   pub fn get_default_roles (&self) -> HashMap<Role, usize> {
     let mut roles = HashMap::new();
-    roles.insert(Role::Patient0, 1);
-    roles.insert(Role::Physician, 2);
-    roles.insert(Role::Psychologist, 1);
-    roles.insert(Role::ITEngineer, 1);
-    roles.insert(Role::Spy, 1);
-    // Add astronauts for a 7 players game if there are less registered
-    let astronauts = cmp::max(7, self.player_names.len()) - roles.values().sum::<usize>();
-    roles.insert(Role::Astronaut, astronauts);
+    for (id, count) in &self.config.roles {
+      if let Some(role) = self.catalog.get(id) { // checked when loading the configuration
+        roles.insert(role.clone(), *count);
+      }
+    }
+    // Add astronauts for a game with the minimum number of players if there are less registered
+    if let Some(filler_role) = self.config.filler_role.as_ref().and_then(|id| self.catalog.get(id)) {
+      let fillers = cmp::max(self.config.min_players, self.player_names.len()).saturating_sub(roles.values().sum::<usize>());
+      *roles.entry(filler_role.clone()).or_insert(0) += fillers;
+    }
     return roles;
   }
 
-  pub fn can_create_game (&mut self) -> bool {
+  pub fn can_create_game (&mut self) -> Result<bool, Idle> {
     if self.ship_name == None {
-      self.interface.user_validate("Vous devez donner un nom à votre vaisseau");
-      return false;
+      self.interface.user_validate("Vous devez donner un nom à votre vaisseau")?;
+      return Ok(false);
     }
-    if self.player_names.len() < 7 {
-      self.interface.user_validate("Désolé, il vous faut au moins 7 joueurs pour jouer");
-      return false;
+    if self.player_names.len() < self.config.min_players {
+      self.interface.user_validate(format!("Désolé, il vous faut au moins {} joueurs pour jouer", self.config.min_players).as_str())?;
+      return Ok(false);
     }
     let roles = self.get_roles();
     if self.player_names.len() != roles.len() {
-      self.interface.user_validate(format!("Le nombre de roles ({}) doit correspondre au nombre de joueurs ({})", roles.len(), self.player_names.len()).as_str());
-      return false;
+      self.interface.user_validate(format!("Le nombre de roles ({}) doit correspondre au nombre de joueurs ({})", roles.len(), self.player_names.len()).as_str())?;
+      return Ok(false);
+    }
+    if self.assigns_genomes(&roles) && roles.iter().filter(|role| can_carry_genome(role)).count() < 2 {
+      self.interface.user_validate("Il faut au moins 2 membres d'équipage ni infecté·e·s ni médecins pour recevoir les génomes hôte et résistant")?;
+      return Ok(false);
+    }
+    return Ok(true);
+  }
+
+  fn assigns_genomes (&self, roles: &[Role]) -> bool {
+    match self.config.genomes {
+      GenomeAssignment::WithGeneticist => roles.iter().any(|role| role.can(RoleAction::Genomyze)),
+      GenomeAssignment::Always => true,
+      GenomeAssignment::Never => false,
     }
-    return true;
   }
 
-  pub fn create_game (self) -> Result<GameStatus, Box<dyn error::Error>> {
+  pub fn create_game (mut self) -> Result<GameStatus, Box<dyn error::Error>> {
     let mut roles = self.get_roles();
-    let has_geneticist = roles.contains(&Role::Geneticist);
+    if !self.debug { // Keep the roles ordered when debugging
+      roles.shuffle(&mut self.rng);
+    }
+    let assign_genomes = self.assigns_genomes(&roles);
 
     let mut next_user_id = 0;
     let mut players: Vec<Player> = Vec::new();
@@ -186,29 +251,37 @@ impl <'a> GameCreator<'a> {
       next_user_id += 1;
     }
 
-    if has_geneticist {
+    if assign_genomes {
       let mut potential_host_and_resilient = players.iter_mut()
-        .filter(|player| player.role != Role::Patient0 && player.role != Role::Physician)
+        .filter(|player| can_carry_genome(&player.role))
         .collect::<Vec<&mut Player>>();
       if !self.debug { // No random when debugging
-        potential_host_and_resilient.shuffle(&mut thread_rng());
+        potential_host_and_resilient.shuffle(&mut self.rng);
       }
-      potential_host_and_resilient.pop().unwrap().host = true;
-      potential_host_and_resilient.pop().unwrap().resilient = true;
+      let not_enough = "Not enough players to carry the host and resilient genomes"; // checked by can_create_game
+      potential_host_and_resilient.pop().ok_or(not_enough)?.host = true;
+      potential_host_and_resilient.pop().ok_or(not_enough)?.resilient = true;
     }
 
-    Ok(GameStatus::new(self.ship_name.unwrap(), players, self.debug))
+    Ok(GameStatus::new(self.ship_name.unwrap(), players, self.debug, self.seed, self.rng, self.config))
   }
 }
 
-pub fn create_game (interface: &mut Interface, debug: bool) -> Result<GameStatus, Box<dyn error::Error>> {
-  let mut game_creator = GameCreator::new(interface, debug);
+// The host and resilient genomes go to the healthy crew members who cannot cure
+fn can_carry_genome (role: &Role) -> bool {
+  !role.infected && !role.can(RoleAction::Cure)
+}
+
+pub fn create_game (interface: &mut dyn Interface, debug: bool, seed: u64, catalog: RoleCatalog, config: GameConfig) -> Result<GameStatus, Box<dyn error::Error>> {
+  let mut game_creator = GameCreator::new(interface, debug, seed, catalog, config);
 
   enum Options {
     NameShip,
     AddPlayer,
     RemovePlayer,
     UpdateRoles,
+    SetGmPassword,
+    SaveConfig,
     StartGame,
   }
   impl fmt::Display for Options {
@@ -218,6 +291,8 @@ pub fn create_game (interface: &mut Interface, debug: bool) -> Result<GameStatus
         Options::AddPlayer => write!(f, "Ajouter un membre d'équipage")?,
         Options::RemovePlayer => write!(f, "Supprimer un membre d'équipage")?,
         Options::UpdateRoles => write!(f, "Selectionner la liste des roles")?,
+        Options::SetGmPassword => write!(f, "Choisir le mot de passe du maître du jeu")?,
+        Options::SaveConfig => write!(f, "Enregistrer la configuration")?,
         Options::StartGame => write!(f, "Commencer la partie")?,
       };
       return Ok(());
@@ -227,17 +302,22 @@ pub fn create_game (interface: &mut Interface, debug: bool) -> Result<GameStatus
   loop {
     game_creator.interface.clear_terminal();
     let names = game_creator.player_names.keys().map(|name| name.clone()).collect::<Vec<String>>().join(", ");
-    println!("Liste des membres d'équipage actifs: [{names}]");
-    println!("Que souhaitez vous faire?");
+    game_creator.interface.println(format!("Liste des membres d'équipage actifs: [{names}]").as_str());
+    if game_creator.config.gm_password.is_none() {
+      game_creator.interface.println("Sans mot de passe du maître du jeu, les sauvegardes pourront être lues par tout le monde");
+    }
+    game_creator.interface.println("Que souhaitez vous faire?");
 
-    let options_list = vec![Options::NameShip, Options::AddPlayer, Options::RemovePlayer, Options::UpdateRoles, Options::StartGame];
-    match game_creator.interface.user_select_from(options_list.iter()) {
-      Options::NameShip => game_creator.name_ship(),
-      Options::AddPlayer => game_creator.add_player(),
-      Options::RemovePlayer => game_creator.remove_player(),
-      Options::UpdateRoles => game_creator.update_roles(),
+    let options_list = [Options::NameShip, Options::AddPlayer, Options::RemovePlayer, Options::UpdateRoles, Options::SetGmPassword, Options::SaveConfig, Options::StartGame];
+    match game_creator.interface.user_select_from(options_list.iter())? {
+      Options::NameShip => game_creator.name_ship()?,
+      Options::AddPlayer => game_creator.add_player()?,
+      Options::RemovePlayer => game_creator.remove_player()?,
+      Options::UpdateRoles => game_creator.update_roles()?,
+      Options::SetGmPassword => game_creator.set_gm_password()?,
+      Options::SaveConfig => game_creator.save_config()?,
       Options::StartGame => {
-        if game_creator.can_create_game() {
+        if game_creator.can_create_game()? {
           return game_creator.create_game();
         }
       },
@@ -246,12 +326,45 @@ pub fn create_game (interface: &mut Interface, debug: bool) -> Result<GameStatus
 }
 
 
-fn generaye_keys (debug: bool) -> Vec<String> {
-  let mut keys: Vec<String> = (100..1000).map(|number| number.to_string()).collect();
-  if debug {
-      keys.reverse(); // Want ids to be 100 101 102 ... in debug
-  } else {
-      keys.shuffle(&mut thread_rng());
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use super::*;
+  use crate::game::Game;
+  use crate::interface::scripted::ScriptedInterface;
+
+  #[test]
+  fn games_without_enough_genome_carriers_are_not_created() {
+    let config = GameConfig {
+      roles: BTreeMap::from([(String::from("patient0"), 1), (String::from("physician"), 1)]),
+      min_players: 3,
+      genomes: GenomeAssignment::Always,
+      ..Default::default()
+    };
+    // The ship, three players, then the start of the game
+    let inputs = ["1", "Koursk", "2", "Alice", "", "2", "Bob", "", "2", "Carol", "", "7", ""];
+    let mut interface = ScriptedInterface::new(inputs.map(String::from).to_vec());
+    interface.set_idle_timeout(Some(Duration::ZERO)); // the script ends in the menu instead of starting the game
+    assert!(create_game(&mut interface, false, 1, RoleCatalog::default(), config).is_err());
+    assert!(interface.output().contains("Il faut au moins 2 membres d'équipage ni infecté·e·s ni médecins"));
+  }
+
+  #[test]
+  fn the_same_seed_deals_the_same_roles() {
+    let deal = |seed: u64| {
+      let mut inputs = vec!["1", "Koursk"];
+      for name in ["Alice", "Bob", "Carol", "Dave", "Erin", "Frank", "Grace"] {
+        inputs.append(&mut vec!["2", name, ""]);
+      }
+      inputs.push("7");
+      let mut interface = ScriptedInterface::new(inputs.into_iter().map(String::from).collect());
+      let game = create_game(&mut interface, false, seed, RoleCatalog::default(), GameConfig::default()).unwrap();
+      game.get_all_players()
+        .map(|player| (player.name.clone(), player.role.id.clone(), player.host, player.resilient))
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(deal(42), deal(42));
+    assert!((0..10).any(|seed| deal(seed) != deal(42)));
   }
-  return keys;
 }
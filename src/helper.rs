@@ -1,40 +1,35 @@
 use std::collections::HashMap;
 
-use crate::{action::ActionType, player::{PlayerId, Player}};
+use rand::seq::SliceRandom;
 
+use crate::{action::ActionType, game::GameRng, player::{PlayerId, Player}};
 
-// Returns the ID of the player who received the most votes for the given action among the voters, along the number of votes
+
+// Returns the ID of the player who received the most votes among the given results, along the number of votes
 // If several players received the highest number of votes, one is selected at random
-pub fn compute_votes_winner <'a, T> (voters: T, action: ActionType) -> Option<(PlayerId, usize)>
+pub fn select_votes_winner (results: HashMap<PlayerId, usize>, rng: &mut GameRng) -> Option<(PlayerId, usize)> {
+  let max_votes = *results.values().max()?;
+  let mut winners: Vec<PlayerId> = results.iter()
+    .filter_map(|(player, votes)| if *votes == max_votes { Some(*player) } else { None })
+    .collect();
+  winners.sort(); // HashMap iteration order is random, it must not leak in the choice
+  winners.choose(rng).map(|player| (*player, max_votes))
+}
+
+pub fn compute_votes_results <'a, T> (voters: T, action: ActionType) -> HashMap<PlayerId, usize>
 where T: IntoIterator<Item = &'a&'a Player>,
 {
-  let results = compute_votes_results(voters, action);
-  let mut winner: Option<PlayerId> = None;
-  let mut winner_votes = None;
-  for (player, votes) in results.iter() {
-    if winner_votes.is_none() || *votes > winner_votes.unwrap() {
-      winner = Some(*player);
-      winner_votes = Some(*votes);
-    } else if *votes == winner_votes.unwrap() {
-      if rand::random() { // Not great because if more than 2 "winners", the "first" one(s) is less likely to be selected
-        winner = Some(*player);
-        winner_votes = Some(*votes);
-      }
-    }
-  }
-  match winner {
-    Some(player) => Some((player, winner_votes.unwrap())),
-    None => None,
-  }
+  compute_weighted_votes_results(voters, action, |_| 1)
 }
 
-pub fn compute_votes_results <'a, T> (voters: T, action: ActionType) -> HashMap<PlayerId, usize>
+// Same as compute_votes_results, but some voters can count for more than one vote
+pub fn compute_weighted_votes_results <'a, T> (voters: T, action: ActionType, weight: impl Fn(&Player) -> usize) -> HashMap<PlayerId, usize>
 where T: IntoIterator<Item = &'a&'a Player>,
 {
-  let results = voters.into_iter().filter_map(|mutant| mutant.actions.get(&action))
-    .fold(HashMap::new(), |mut acc, target| {
-      *acc.entry(*target).or_insert(0) += 1;
+  let results = voters.into_iter().filter_map(|voter| voter.actions.get(&action).map(|target| (target, weight(voter))))
+    .fold(HashMap::new(), |mut acc, (target, weight)| {
+      *acc.entry(*target).or_insert(0) += weight;
       acc
   });
-  return results;
+  results
 }
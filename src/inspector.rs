@@ -0,0 +1,180 @@
+use std::error;
+
+use crate::game::{Game, GameStatus, PhaseOfDay};
+use crate::player::Player;
+
+// Readable views of the backups, for the game master to settle disputes during a party
+// Everything is displayed, including the secret keys and roles
+
+fn phase_name (phase: &PhaseOfDay) -> &'static str {
+  match phase {
+    PhaseOfDay::Day => "journée",
+    PhaseOfDay::Twilight => "crépuscule",
+  }
+}
+
+fn status (player: &Player) -> String {
+  let mut status = vec![
+    String::from(if player.alive { "vivant·e" } else { "mort·e" }),
+    String::from(if player.infected { "mutant·e" } else { "sain·e" }),
+  ];
+  if player.paralyzed {
+    status.push(String::from("paralysé·e"));
+  }
+  if player.host {
+    status.push(String::from("génome hôte"));
+  } else if player.resilient {
+    status.push(String::from("génome résistant"));
+  }
+  if player.has_connected_today {
+    status.push(String::from("connecté·e aujourd'hui"));
+  }
+  status.join(", ")
+}
+
+fn pending_actions (game: &GameStatus, player: &Player) -> Vec<String> {
+  let mut actions = player.actions.iter()
+    .map(|(action, target)| format!("{:?} -> {}", action, game.get_player(*target).name))
+    .collect::<Vec<String>>();
+  actions.sort(); // the actions are in a hash map
+  if player.infected && player.mutant_kill {
+    actions.push(String::from("les mutants doivent tuer leur cible"));
+  }
+  if player.physician_kill {
+    actions.push(String::from("tuer la cible au lieu de la soigner"));
+  }
+  if player.traitor_signal {
+    actions.push(String::from("faire signe aux mutants"));
+  }
+  if let Some(role) = &player.hacker_target {
+    actions.push(format!("pirater le role {role}"));
+  }
+  actions
+}
+
+pub fn describe_game (game: &GameStatus) -> String {
+  let mut lines = Vec::new();
+  lines.push(format!("===== {} =====", game.get_name()));
+  let captain = match game.get_captain() {
+    Some(captain) => game.get_player(captain).name.clone(),
+    None => String::from("aucun·e"),
+  };
+  lines.push(format!("Jour {} ({}) - graine {} - capitaine: {}", game.get_date(), phase_name(game.get_phase_of_day()), game.get_seed(), captain));
+  if let Some(reason) = game.get_end_reason() {
+    lines.push(format!("Partie terminée: {reason}"));
+  }
+
+  lines.push(String::new());
+  lines.push(String::from("Équipage:"));
+  for player in game.get_all_players() {
+    lines.push(format!("* {} - {} - {}", player.name, player.role, status(player)));
+    if !player.alive {
+      lines.push(format!("    mort·e le jour {}: {}", player.death_date.unwrap_or(0), player.get_death_cause()));
+    }
+  }
+
+  lines.push(String::new());
+  lines.push(String::from("Actions en attente:"));
+  for player in game.get_players() {
+    let actions = pending_actions(game, player);
+    if !actions.is_empty() {
+      lines.push(format!("* {}: {}", player.name, actions.join(", ")));
+    }
+  }
+
+  lines.push(String::new());
+  lines.push(String::from("Messages:"));
+  for player in game.get_all_players().filter(|player| !player.messages.is_empty()) {
+    lines.push(format!("{}:", player.name));
+    for message in &player.messages {
+      lines.push(format!("  {}", message.to_string()));
+    }
+  }
+  lines.join("\n")
+}
+
+// What changed between two backups of the same game, typically two consecutive days
+pub fn diff_games (before: &GameStatus, after: &GameStatus) -> Result<String, Box<dyn error::Error>> {
+  let same_crew = before.get_all_players().map(|player| &player.name).eq(after.get_all_players().map(|player| &player.name));
+  if before.get_name() != after.get_name() || before.get_seed() != after.get_seed() || !same_crew {
+    return Err("Both backups must come from the same game".into());
+  }
+
+  let mut lines = Vec::new();
+  if before.get_date() != after.get_date() || before.get_phase_of_day() != after.get_phase_of_day() {
+    lines.push(format!("* Jour {} ({}) -> jour {} ({})",
+      before.get_date(), phase_name(before.get_phase_of_day()),
+      after.get_date(), phase_name(after.get_phase_of_day())));
+  }
+  if before.get_captain() != after.get_captain() {
+    let name = |game: &GameStatus| game.get_captain().map(|captain| game.get_player(captain).name.clone()).unwrap_or(String::from("aucun·e"));
+    lines.push(format!("* Capitaine: {} -> {}", name(before), name(after)));
+  }
+  for (old, new) in before.get_all_players().zip(after.get_all_players()) {
+    let mut changes = Vec::new();
+    if old.alive != new.alive {
+      changes.push(if new.alive { String::from("ressuscité·e") } else { format!("mort·e ({})", new.get_death_cause()) });
+    }
+    if old.infected != new.infected {
+      changes.push(String::from(if new.infected { "infecté·e" } else { "guéri·e" }));
+    }
+    if old.paralyzed != new.paralyzed {
+      changes.push(String::from(if new.paralyzed { "paralysé·e" } else { "n'est plus paralysé·e" }));
+    }
+    if !changes.is_empty() {
+      lines.push(format!("* {}: {}", new.name, changes.join(", ")));
+    }
+  }
+  if lines.is_empty() {
+    lines.push(String::from("Aucun changement d'état"));
+  }
+
+  let new_events = after.get_events().iter().skip(before.get_events().len()).collect::<Vec<_>>();
+  if !new_events.is_empty() {
+    lines.push(String::new());
+    lines.push(String::from("Nouveaux évènements:"));
+    for event in new_events {
+      lines.push(event.describe(after));
+    }
+  }
+  Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::SeedableRng;
+
+  use super::*;
+  use crate::config::GameConfig;
+  use crate::game::GameRng;
+  use crate::role::RoleCatalog;
+
+  fn create_game() -> GameStatus {
+    let catalog = RoleCatalog::default();
+    let players = ["patient0", "physician", "astronaut"].iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, (100 + idx).to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    GameStatus::new(String::from("Test"), players, true, 42, GameRng::seed_from_u64(42), GameConfig::default())
+  }
+
+  #[test]
+  fn diff_shows_the_changes_of_state() {
+    let before = create_game();
+    let mut after = create_game();
+    let ids = after.get_player_ids(&|_| true);
+    after.get_mut_player(ids[1]).infected = true;
+    after.get_mut_player(ids[2]).die(1, String::from("Carbonisé·e sous la douche"));
+
+    let diff = diff_games(&before, &after).unwrap();
+    assert!(diff.contains("* player-1: infecté·e"));
+    assert!(diff.contains("* player-2: mort·e (Carbonisé·e sous la douche)"));
+    assert!(!diff.contains("player-0"));
+  }
+
+  #[test]
+  fn diff_refuses_backups_of_different_games() {
+    let before = create_game();
+    let after = GameStatus::new(String::from("Other"), Vec::new(), true, 42, GameRng::seed_from_u64(42), GameConfig::default());
+    assert!(diff_games(&before, &after).is_err());
+  }
+}
@@ -67,3 +67,17 @@ impl Color {
     }
   }
 }
+
+// For the outputs that are not displayed in a terminal
+pub fn strip_colors(text: &str) -> String {
+  let mut stripped = String::with_capacity(text.len());
+  let mut chars = text.chars();
+  while let Some(c) = chars.next() {
+    if c == '\x1b' {
+      chars.by_ref().find(|c| *c == 'm');
+    } else {
+      stripped.push(c);
+    }
+  }
+  stripped
+}
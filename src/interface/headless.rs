@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use rand::{SeedableRng, seq::SliceRandom};
+use rand::rngs::StdRng;
+
+use super::{Idle, Interface, Sound};
+
+// Displays nothing and never waits, meant for simulations
+// Mocked inputs are used first, then the choices are made at random
+pub struct HeadlessInterface {
+  input_mock: Vec<String>,
+  rng: StdRng,
+  generated_inputs: usize,
+}
+
+impl HeadlessInterface {
+  pub fn new (seed: u64) -> HeadlessInterface {
+    HeadlessInterface {
+      input_mock: Vec::new(),
+      rng: StdRng::seed_from_u64(seed),
+      generated_inputs: 0,
+    }
+  }
+}
+
+impl Interface for HeadlessInterface {
+  fn mock(&mut self, mut inputs: Vec<String>) {
+    inputs.reverse();
+    inputs.append(&mut self.input_mock); // new inputs are played after the ones already there
+    self.input_mock = inputs;
+  }
+
+  fn print(&mut self, _text: &str) {}
+
+  fn println(&mut self, _text: &str) {}
+
+  fn clear_terminal(&mut self) {}
+
+  fn user_choice(&mut self, _message: &str, accepted_answers: &[String]) -> Result<String, Idle> {
+    while let Some(input) = self.input_mock.pop() {
+      let input = input.trim().to_string();
+      if accepted_answers.contains(&input) {
+        return Ok(input);
+      }
+    }
+    Ok(accepted_answers.choose(&mut self.rng).expect("Nothing to choose from").clone())
+  }
+
+  fn user_validate(&mut self, _message: &str) -> Result<(), Idle> {
+    self.input_mock.pop();
+    Ok(())
+  }
+
+  fn user_non_empty_input(&mut self, _message: &str) -> Result<String, Idle> {
+    while let Some(input) = self.input_mock.pop() {
+      let input = input.trim().to_string();
+      if !input.is_empty() {
+        return Ok(input);
+      }
+    }
+    self.generated_inputs += 1;
+    Ok(format!("headless-{}", self.generated_inputs))
+  }
+
+  fn set_idle_timeout(&mut self, _timeout: Option<Duration>) {} // the random players never walk away
+
+  fn play_sound(&mut self, _sound: Sound) {}
+
+  fn stop_sound(&mut self) {}
+
+  fn wait_and_display(&mut self, _message: &str, _wait: Duration, _display_interval: Duration) {}
+}
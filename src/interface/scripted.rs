@@ -0,0 +1,122 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use super::{Idle, Interface, Sound};
+
+// Plays a pre-written list of inputs, and records everything that would have been displayed
+// Meant to drive the menus from code and make assertions on what the players saw
+pub struct ScriptedInterface {
+  inputs: VecDeque<String>,
+  screens: Vec<String>, // a new screen is started every time the terminal is cleared
+  sounds: usize,
+  idle_timeout: Option<Duration>,
+}
+
+impl ScriptedInterface {
+  pub fn new (inputs: Vec<String>) -> ScriptedInterface {
+    let mut interface = ScriptedInterface {
+      inputs: VecDeque::new(),
+      screens: vec![String::new()],
+      sounds: 0,
+      idle_timeout: None,
+    };
+    interface.mock(inputs);
+    interface
+  }
+
+  pub fn output(&self) -> String {
+    self.screens.join("")
+  }
+
+  pub fn screens(&self) -> &Vec<String> {
+    &self.screens
+  }
+
+  pub fn last_screen(&self) -> &str {
+    self.screens.last().unwrap() // there is always at least one screen
+  }
+
+  pub fn remaining_inputs(&self) -> usize {
+    self.inputs.len()
+  }
+
+  pub fn sounds_played(&self) -> usize {
+    self.sounds
+  }
+
+  fn record(&mut self, text: &str) {
+    self.screens.last_mut().unwrap().push_str(text);
+  }
+
+  // Once the script is over, the players have walked away: the inputs wait until the idle timeout, if there is one
+  fn next_input(&mut self, message: &str) -> Result<String, Idle> {
+    match self.inputs.pop_front() {
+      Some(input) => {
+        let input = input.trim().to_string();
+        self.record(format!("{message} {input}\n").as_str());
+        Ok(input)
+      },
+      None if self.idle_timeout.is_some() => {
+        self.clear_terminal();
+        Err(Idle)
+      },
+      None => panic!("The script has no more inputs, but the game is waiting for: {message}"),
+    }
+  }
+}
+
+impl Interface for ScriptedInterface {
+  fn mock(&mut self, inputs: Vec<String>) {
+    self.inputs.extend(inputs);
+  }
+
+  fn print(&mut self, text: &str) {
+    self.record(text);
+  }
+
+  fn println(&mut self, text: &str) {
+    self.record(text);
+    self.record("\n");
+  }
+
+  fn clear_terminal(&mut self) {
+    self.screens.push(String::new());
+  }
+
+  fn user_choice(&mut self, message: &str, accepted_answers: &[String]) -> Result<String, Idle> {
+    loop {
+      let input = self.next_input(message)?;
+      if accepted_answers.contains(&input) {
+        return Ok(input);
+      }
+    }
+  }
+
+  fn user_validate(&mut self, message: &str) -> Result<(), Idle> {
+    self.next_input(message)?;
+    Ok(())
+  }
+
+  fn user_non_empty_input(&mut self, message: &str) -> Result<String, Idle> {
+    loop {
+      let input = self.next_input(message)?;
+      if !input.is_empty() {
+        return Ok(input);
+      }
+    }
+  }
+
+  fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+    self.idle_timeout = timeout;
+  }
+
+  fn play_sound(&mut self, _sound: Sound) {
+    self.sounds += 1;
+  }
+
+  fn stop_sound(&mut self) {}
+
+  fn wait_and_display(&mut self, message: &str, _wait: Duration, _display_interval: Duration) {
+    self.println(message);
+  }
+}
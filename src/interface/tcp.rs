@@ -0,0 +1,178 @@
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+use std::thread;
+
+use super::{Idle, Interface, Sound};
+
+// A terminal at the other end of a network connection (nc, telnet...), one line per answer
+// The connection is never read while a menu runs, the game stays locked for that time: the menus are replayed from
+// their beginning with the answers read so far, until they need one more, like in the browsers (see WebInterface)
+// A closed connection gives up the inputs like an idle player, there is nobody left to answer
+pub struct TcpInterface {
+  reader: BufReader<TcpStream>,
+  writer: TcpStream,
+  answers: Vec<(String, String)>, // with what was displayed when they were asked
+  used: usize, // answers already replayed by the current run of the menu
+  output: String, // what the current run of the menu displayed so far
+  shown: String, // what the player saw until the last question
+  changed: bool, // the menu no longer displays what the player answered to
+  closed: bool,
+}
+
+impl TcpInterface {
+  pub fn new (stream: TcpStream) -> io::Result<TcpInterface> {
+    Ok(TcpInterface {
+      reader: BufReader::new(stream.try_clone()?),
+      writer: stream,
+      answers: Vec::new(),
+      used: 0,
+      output: String::new(),
+      shown: String::new(),
+      changed: false,
+      closed: false,
+    })
+  }
+
+  pub fn closed (&self) -> bool {
+    self.closed
+  }
+
+  pub fn start_replay (&mut self) {
+    self.used = 0;
+    self.output.clear();
+    self.changed = false;
+  }
+
+  // The menu needs one more answer: sends what the player has not seen yet
+  pub fn show_replay (&mut self) {
+    let output = std::mem::take(&mut self.output);
+    if self.changed {
+      self.answers.clear();
+      self.send("\nLe menu a changé depuis votre dernier choix, vérifiez-le avant de continuer.\n");
+      self.send(&output);
+    } else if let Some(unseen) = output.strip_prefix(self.shown.as_str()) {
+      self.send(unseen);
+    } else {
+      self.send(&output);
+    }
+    self.shown = output;
+  }
+
+  // The menu is done, the next one starts from scratch
+  pub fn end_replay (&mut self) {
+    self.answers.clear();
+    self.output.clear();
+    self.shown.clear();
+  }
+
+  // Waits for the next answer, outside of the menu
+  pub fn read_answer (&mut self) -> Result<(), Idle> {
+    let mut line = String::new();
+    match self.reader.read_line(&mut line) {
+      Ok(0) => {
+        self.closed = true;
+        Err(Idle)
+      },
+      Ok(_) => {
+        self.answers.push((line.trim().to_string(), self.shown.clone()));
+        Ok(())
+      },
+      Err(error) if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+        self.send(format!("{esc}[3J{esc}[2J{esc}[1;1H", esc = 27 as char).as_str());
+        Err(Idle)
+      },
+      Err(_) => {
+        self.closed = true;
+        Err(Idle)
+      },
+    }
+  }
+
+  // The answers are only given to the questions the player saw, they could mean something else now
+  fn next_answer (&mut self) -> Result<String, Idle> {
+    match self.answers.get(self.used) {
+      Some((answer, asked)) if *asked == self.output => {
+        self.used += 1;
+        Ok(answer.clone())
+      },
+      Some(_) => {
+        self.changed = true;
+        Err(Idle)
+      },
+      None => Err(Idle),
+    }
+  }
+
+  fn write (&mut self, text: &str) {
+    self.output.push_str(text);
+  }
+
+  fn send (&mut self, text: &str) {
+    if self.writer.write_all(text.as_bytes()).is_err() {
+      self.closed = true;
+    }
+  }
+}
+
+impl Interface for TcpInterface {
+  fn mock (&mut self, _inputs: Vec<String>) {} // only the debug mode mocks inputs, and it does it on the local terminal
+
+  fn print(&mut self, text: &str) {
+    self.write(text);
+  }
+
+  fn println(&mut self, text: &str) {
+    self.write(text);
+    self.write("\n");
+  }
+
+  fn clear_terminal(&mut self) {
+    self.write(format!("{esc}[2J{esc}[1;1H", esc = 27 as char).as_str());
+  }
+
+  fn user_choice(&mut self, message: &str, accepted_answers: &[String]) -> Result<String, Idle> {
+    self.write("\n");
+    loop {
+      self.write(format!("{message} ").as_str());
+      let answer = self.next_answer()?;
+      if accepted_answers.contains(&answer) {
+        return Ok(answer);
+      }
+    }
+  }
+
+  fn user_validate(&mut self, message: &str) -> Result<(), Idle> {
+    self.write(format!("{message} ").as_str());
+    self.next_answer()?;
+    Ok(())
+  }
+
+  fn user_non_empty_input(&mut self, message: &str) -> Result<String, Idle> {
+    loop {
+      self.write(format!("{message} ").as_str());
+      let answer = self.next_answer()?;
+      if !answer.is_empty() {
+        return Ok(answer);
+      }
+    }
+  }
+
+  fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+    if self.writer.set_read_timeout(timeout).is_err() {
+      self.closed = true;
+    }
+  }
+
+  fn play_sound(&mut self, _sound: Sound) {}
+
+  fn stop_sound(&mut self) {}
+
+  fn wait_and_display(&mut self, message: &str, wait: Duration, display_interval: Duration) {
+    let now = Instant::now();
+    while now.elapsed() < wait && !self.closed {
+      thread::sleep(display_interval);
+      self.println(message);
+    }
+  }
+}
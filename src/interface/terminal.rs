@@ -0,0 +1,185 @@
+use std::time::Duration;
+use std::io;
+use std::io::Write;
+use std::fs::File;
+use std::io::BufReader;
+use std::{thread, time};
+use std::sync::{Mutex, OnceLock, mpsc::{self, Receiver, RecvTimeoutError}};
+use rodio::{Sink, OutputStreamHandle};
+use rodio::{Decoder, OutputStream};
+
+use super::{Idle, Interface, Sound, colors::Color};
+
+// Stdin is read by a thread of its own so the inputs can time out. There is only one for the whole program,
+// several terminals are created (to pick the saved game, then to play) and must not race for the same lines
+fn stdin_lines () -> &'static Mutex<Receiver<String>> {
+  static LINES: OnceLock<Mutex<Receiver<String>>> = OnceLock::new();
+  LINES.get_or_init(|| {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || loop {
+      let mut line = String::new();
+      match io::stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => break, // the receiver then sees the end of the inputs
+        Ok(_) => if sender.send(line).is_err() {
+          break;
+        },
+      }
+    });
+    Mutex::new(receiver)
+  })
+}
+
+// The ANSI terminal the game was originally built for
+pub struct TerminalInterface {
+  debug: bool,
+  sound: bool,
+  input_mock: Vec<String>,
+  idle_timeout: Option<Duration>,
+
+  current_sink: Option<Sink>,
+  current_stream: Option<OutputStream>,
+  current_stream_handle: Option<OutputStreamHandle>,
+}
+
+impl TerminalInterface {
+  pub fn new (debug: bool) -> TerminalInterface {
+    TerminalInterface {
+      debug,
+      sound: true,
+      input_mock: Vec::new(),
+      idle_timeout: None,
+      current_sink: None,
+      current_stream: None,
+      current_stream_handle: None,
+    }
+  }
+
+  pub fn set_sound (&mut self, sound: bool) {
+    self.sound = sound;
+  }
+
+  fn read_line (&mut self) -> Result<String, Idle> {
+    if let Some(next_mock) = self.input_mock.pop() {
+      print!("{}", Color::FgCyan.color(&next_mock));
+      return Ok(next_mock);
+    }
+    let lines = stdin_lines().lock().unwrap();
+    match self.idle_timeout {
+      Some(timeout) => match lines.recv_timeout(timeout) {
+        Ok(line) => Ok(line),
+        Err(RecvTimeoutError::Timeout) => {
+          // The scrollback too, the secrets of the player must not be one scroll away
+          print!("{esc}[3J{esc}[2J{esc}[1;1H", esc = 27 as char);
+          io::stdout().flush().unwrap();
+          Err(Idle)
+        },
+        Err(RecvTimeoutError::Disconnected) => Ok(String::new()),
+      },
+      None => Ok(lines.recv().unwrap_or_default()),
+    }
+  }
+}
+
+impl Interface for TerminalInterface {
+  fn mock (&mut self, mut inputs: Vec<String>) {
+    if !self.input_mock.is_empty() {
+      panic!("Trying to add mock while there is still some moco")
+    }
+    inputs.reverse();
+    for input in inputs {
+      self.input_mock.push(input);
+    }
+  }
+
+  fn print(&mut self, text: &str) {
+    print!("{text}");
+  }
+
+  fn println(&mut self, text: &str) {
+    println!("{text}");
+  }
+
+  fn clear_terminal(&mut self) {
+    if self.debug {
+        print!("\n##############################\n\n");
+    } else {
+        print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+    }
+  }
+
+  fn user_choice(&mut self, message: &str, accepted_answers: &[String]) -> Result<String, Idle> {
+    println!();
+    loop {
+        print!("{message} ");
+        io::stdout().flush().unwrap();
+        let input = self.read_line()?.trim().to_string();
+        if accepted_answers.contains(&input) {
+            return Ok(input);
+        }
+    }
+  }
+
+  fn user_validate(&mut self, message: &str) -> Result<(), Idle> {
+    print!("{message} ");
+    io::stdout().flush().unwrap();
+    self.read_line()?;
+    Ok(())
+  }
+
+  fn user_non_empty_input(&mut self, message: &str) -> Result<String, Idle> {
+    loop {
+        print!("{message} ");
+        io::stdout().flush().unwrap();
+        let input = self.read_line()?.trim().to_string();
+        if !input.is_empty() {
+            return Ok(input);
+        }
+    }
+  }
+
+  fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+    self.idle_timeout = timeout;
+  }
+
+  fn play_sound (&mut self, sound: Sound) {
+    if !self.sound {
+      return;
+    }
+    let (filename, speed) = match sound {
+      Sound::Warning => ("sounds/Alarm_or_siren.mp3", 0.42),
+      Sound::Alarm => ("sounds/Alarm_or_siren.mp3", 0.84),
+      Sound::NoDeathGood => ("sounds/Cathedralofthedowns.mp3", 1.0),
+      Sound::Death => ("sounds/ChopinsFuneralMarch1906_2-short.mp3", 1.0),
+    };
+
+    self.stop_sound();
+    let (_stream, stream_handle) = OutputStream::try_default().unwrap();
+    let sink = Sink::try_new(&stream_handle).unwrap();
+    let file = BufReader::new(File::open(filename).unwrap());
+    let source = Decoder::new(file).unwrap();
+
+    sink.set_speed(speed);
+    sink.append(source);
+
+    self.current_sink = Some(sink);
+    self.current_stream = Some(_stream);
+    self.current_stream_handle = Some(stream_handle);
+  }
+
+  fn stop_sound (&mut self) {
+    if let Some(sink) = &self.current_sink {
+      sink.stop();
+      self.current_sink = None;
+      self.current_stream = None;
+      self.current_stream_handle = None;
+    }
+  }
+
+  fn wait_and_display (&mut self, message: &str, wait: Duration, display_interval: Duration) {
+    let now = time::Instant::now();
+    while now.elapsed() < wait {
+      thread::sleep(display_interval);
+      println!("{}", message);
+    }
+  }
+}
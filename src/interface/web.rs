@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+use super::{Idle, Interface, Sound};
+
+// What the player has to answer next, to be displayed as a form
+#[derive(Debug, PartialEq, Hash)]
+pub enum Prompt {
+  Choice { message: String, options: Vec<(String, String)> }, // the answers, with the text of the menu line
+  Validate { message: String },
+  Text { message: String },
+}
+
+// The menus are replayed from their beginning for each request of a browser, with all the answers given so far
+// When there are no answers left, the next input gives up (as if the player was idle) and is kept as the prompt to display
+// Each answer comes with the fingerprint of the screen it answered: if the game changed in the meantime (a new phase,
+// a death in the list of targets...), the same answer could mean something else, so the player is asked again
+pub struct WebInterface {
+  answers: VecDeque<(String, String)>, // with the fingerprint of their screen
+  mocked: VecDeque<String>,
+  given: Vec<(String, String)>, // the answers actually used, to be sent back with the next one
+  screen: String, // only the last screen, like a terminal
+  prompt: Option<Prompt>,
+  fingerprint: String, // of the prompt to display
+  changed: bool, // some answers were dropped because their screen changed
+}
+
+impl WebInterface {
+  pub fn new (answers: Vec<(String, String)>) -> WebInterface {
+    WebInterface {
+      answers: answers.into(),
+      mocked: VecDeque::new(),
+      given: Vec::new(),
+      screen: String::new(),
+      prompt: None,
+      fingerprint: String::new(),
+      changed: false,
+    }
+  }
+
+  pub fn screen(&self) -> &str {
+    &self.screen
+  }
+
+  pub fn prompt(&self) -> Option<&Prompt> {
+    self.prompt.as_ref()
+  }
+
+  pub fn given_answers(&self) -> &Vec<(String, String)> {
+    &self.given
+  }
+
+  pub fn fingerprint(&self) -> &str {
+    &self.fingerprint
+  }
+
+  pub fn changed(&self) -> bool {
+    self.changed
+  }
+
+  fn next_answer(&mut self, prompt: impl FnOnce(&mut WebInterface) -> Prompt) -> Result<String, Idle> {
+    if let Some(answer) = self.mocked.pop_front() {
+      return Ok(answer.trim().to_string());
+    }
+    let screen = self.screen.clone();
+    let prompt = prompt(self);
+    let mut hasher = DefaultHasher::new();
+    (&screen, &prompt).hash(&mut hasher);
+    let fingerprint = format!("{:016x}", hasher.finish());
+    match self.answers.pop_front() {
+      Some((answer, answered)) if answered == fingerprint => {
+        self.screen = screen; // the options were only taken out for the form
+        self.fingerprint = fingerprint;
+        Ok(answer.trim().to_string())
+      },
+      answer => {
+        self.changed = answer.is_some();
+        self.answers.clear();
+        self.prompt = Some(prompt);
+        self.fingerprint = fingerprint;
+        Err(Idle)
+      },
+    }
+  }
+
+  // Kept with the fingerprint of the screen it answered, see next_answer
+  fn give(&mut self, answer: &str) {
+    self.given.push((answer.to_string(), self.fingerprint.clone()));
+  }
+
+  // The options of the menus are printed as "<answer>) <text>" just before the choice, they become the buttons of the form
+  fn take_options(&mut self, accepted_answers: &[String]) -> Vec<(String, String)> {
+    let mut options = Vec::new();
+    let mut lines = Vec::new();
+    for line in self.screen.lines() {
+      let option = line.split_once(") ").filter(|(answer, _)| accepted_answers.iter().any(|accepted| accepted == answer));
+      match option {
+        Some((answer, text)) => options.push((answer.to_string(), text.to_string())),
+        None => lines.push(line),
+      }
+    }
+    for answer in accepted_answers {
+      if !options.iter().any(|(option, _)| option == answer) {
+        options.push((answer.clone(), answer.clone()));
+      }
+    }
+    self.screen = lines.join("\n");
+    options
+  }
+}
+
+impl Interface for WebInterface {
+  fn mock(&mut self, inputs: Vec<String>) {
+    self.mocked.extend(inputs);
+  }
+
+  fn print(&mut self, text: &str) {
+    self.screen.push_str(text);
+  }
+
+  fn println(&mut self, text: &str) {
+    self.screen.push_str(text);
+    self.screen.push('\n');
+  }
+
+  fn clear_terminal(&mut self) {
+    self.screen.clear();
+  }
+
+  fn user_choice(&mut self, message: &str, accepted_answers: &[String]) -> Result<String, Idle> {
+    loop {
+      let answer = self.next_answer(|web| Prompt::Choice { message: message.to_string(), options: web.take_options(accepted_answers) })?;
+      if accepted_answers.contains(&answer) { // an outdated page can send answers that are no longer possible
+        self.give(&answer);
+        return Ok(answer);
+      }
+    }
+  }
+
+  fn user_validate(&mut self, message: &str) -> Result<(), Idle> {
+    let answer = self.next_answer(|_| Prompt::Validate { message: message.to_string() })?;
+    self.give(&answer);
+    Ok(())
+  }
+
+  fn user_non_empty_input(&mut self, message: &str) -> Result<String, Idle> {
+    loop {
+      let answer = self.next_answer(|_| Prompt::Text { message: message.to_string() })?;
+      if !answer.is_empty() {
+        self.give(&answer);
+        return Ok(answer);
+      }
+    }
+  }
+
+  fn set_idle_timeout(&mut self, _timeout: Option<Duration>) {} // the browsers are not waited for
+
+  fn play_sound(&mut self, _sound: Sound) {}
+
+  fn stop_sound(&mut self) {}
+
+  fn wait_and_display(&mut self, _message: &str, _wait: Duration, _display_interval: Duration) {}
+}
@@ -0,0 +1,100 @@
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
+use argon2::password_hash::{SaltString, rand_core::OsRng};
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::{Serialize, Deserialize};
+
+// Codes must be impossible to guess by trying a few of them on the terminal
+pub const MIN_COMBINATIONS: f64 = 1000.0;
+
+const CONSONANTS: [char; 14] = ['b', 'd', 'f', 'g', 'k', 'l', 'm', 'n', 'p', 'r', 's', 't', 'v', 'z'];
+const VOWELS: [char; 5] = ['a', 'e', 'i', 'o', 'u'];
+const SYLLABLES_PER_WORD: usize = 3;
+
+// How the secret codes of the players are generated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "style")]
+pub enum KeyStyle {
+  Digits { length: usize },
+  Alphabet { alphabet: String, length: usize },
+  Words { count: usize }, // pronounceable made up words, like "kobale-timu"
+}
+
+impl Default for KeyStyle {
+  fn default() -> Self {
+    KeyStyle::Digits { length: 4 }
+  }
+}
+
+impl KeyStyle {
+  pub fn combinations (&self) -> f64 {
+    match self {
+      KeyStyle::Digits { length } => 10f64.powi(*length as i32),
+      KeyStyle::Alphabet { alphabet, length } => (alphabet.chars().count() as f64).powi(*length as i32),
+      KeyStyle::Words { count } => ((CONSONANTS.len() * VOWELS.len()) as f64).powi((SYLLABLES_PER_WORD * count) as i32),
+    }
+  }
+
+  // Never from the game's rng: the seed is in the backups, the codes could be computed back from it
+  pub fn generate (&self, rng: &mut impl Rng) -> String {
+    match self {
+      KeyStyle::Digits { length } => (0..*length).map(|_| char::from(b'0' + rng.gen_range(0..10))).collect(),
+      KeyStyle::Alphabet { alphabet, length } => {
+        let alphabet: Vec<char> = alphabet.chars().collect();
+        (0..*length).map(|_| *alphabet.choose(rng).unwrap()).collect()
+      },
+      KeyStyle::Words { count } => (0..*count)
+        .map(|_| (0..SYLLABLES_PER_WORD).map(|_| format!("{}{}", CONSONANTS.choose(rng).unwrap(), VOWELS.choose(rng).unwrap())).collect::<String>())
+        .collect::<Vec<String>>()
+        .join("-"),
+    }
+  }
+}
+
+// The codes are short and typed often, so the hash is much lighter than for real passwords,
+// it is only there so the codes cannot be read in the backups
+fn hasher () -> Argon2<'static> {
+  Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::new(4096, 2, 1, None).unwrap())
+}
+
+pub fn hash_key (key: &str) -> String {
+  let salt = SaltString::generate(&mut OsRng); // not the game's rng, the salt must not depend on the seed
+  hasher().hash_password(key.as_bytes(), &salt).unwrap().to_string() // only fails with invalid parameters
+}
+
+pub fn verify_key (hash: &str, key: &str) -> bool {
+  match PasswordHash::new(hash) {
+    Ok(hash) => hasher().verify_password(key.as_bytes(), &hash).is_ok(),
+    Err(_) => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::SeedableRng;
+
+  use super::*;
+  use crate::game::GameRng;
+
+  #[test]
+  fn keys_follow_their_style() {
+    let mut rng = GameRng::seed_from_u64(1);
+    let key = KeyStyle::Digits { length: 6 }.generate(&mut rng);
+    assert!(key.len() == 6 && key.chars().all(|c| c.is_ascii_digit()));
+    let key = KeyStyle::Alphabet { alphabet: String::from("XYZ"), length: 8 }.generate(&mut rng);
+    assert!(key.len() == 8 && key.chars().all(|c| "XYZ".contains(c)));
+    let key = KeyStyle::Words { count: 2 }.generate(&mut rng);
+    assert_eq!(key.split('-').map(|word| word.len()).collect::<Vec<_>>(), vec![6, 6]);
+    assert!(KeyStyle::Digits { length: 2 }.combinations() < MIN_COMBINATIONS);
+  }
+
+  #[test]
+  fn hashed_keys_are_salted() {
+    let first = hash_key("1234");
+    let second = hash_key("1234");
+    assert!(!first.contains("1234"));
+    assert_ne!(first, second);
+    assert!(verify_key(&first, "1234") && verify_key(&second, "1234"));
+    assert!(!verify_key(&first, "4321"));
+  }
+}
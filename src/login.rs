@@ -0,0 +1,181 @@
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use serde::{Serialize, Deserialize};
+
+use crate::event::{EventCause, EventKind};
+use crate::game::Game;
+use crate::message::Message;
+use crate::player::PlayerId;
+
+// What happens when wrong codes are typed on a terminal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoginRules {
+  pub attempts_before_lockout: u32,
+  pub lockout_seconds: u64, // doubled for each new failure once the terminal is locked
+  pub max_lockout_seconds: u64,
+  pub broadcast_intrusions: bool, // warn the whole crew when a terminal gets locked
+}
+
+impl Default for LoginRules {
+  fn default() -> Self {
+    LoginRules {
+      attempts_before_lockout: 3,
+      lockout_seconds: 30,
+      max_lockout_seconds: 600,
+      broadcast_intrusions: false,
+    }
+  }
+}
+
+// Where the codes are typed: each terminal has its own lockout, so a device on the network cannot lock the others
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Terminal {
+  Local,
+  Remote(IpAddr), // by address, so a new connection does not reset the lockout
+}
+
+// Failed logins of a terminal, forgotten as soon as someone logs in successfully
+#[derive(Debug, Default)]
+pub struct LoginGuard {
+  failures: u32,
+  locked_until: Option<Instant>,
+}
+
+impl LoginGuard {
+  pub fn locked_for (&self, now: Instant) -> Option<Duration> {
+    self.locked_until.filter(|until| *until > now).map(|until| until - now)
+  }
+
+  pub fn failures (&self) -> u32 {
+    self.failures
+  }
+
+  // Returns how long the terminal is locked, if this failure locks it
+  pub fn record_failure (&mut self, rules: &LoginRules, now: Instant) -> Option<Duration> {
+    self.failures += 1;
+    if self.failures < rules.attempts_before_lockout {
+      return None;
+    }
+    let doublings = (self.failures - rules.attempts_before_lockout).min(16);
+    let seconds = rules.lockout_seconds.saturating_mul(1 << doublings).min(rules.max_lockout_seconds);
+    let lockout = Duration::from_secs(seconds);
+    self.locked_until = Some(now + lockout);
+    Some(lockout)
+  }
+
+  pub fn record_success (&mut self) {
+    self.failures = 0;
+    self.locked_until = None;
+  }
+}
+
+pub enum LogIn {
+  Success(PlayerId),
+  Invalid,
+  Locked(Duration), // this failure locked the terminal, for that long
+}
+
+// Checks a code typed by a player on a terminal, and records the attempt for that terminal
+// The terminal must not be locked already, the code would not even be checked
+pub fn log_in (game: &mut dyn Game, terminal: Terminal, key: String) -> LogIn {
+  match game.get_player_id_from_key(key) {
+    Some(player_id) => log_in_success(game, terminal, player_id),
+    None => log_in_failure(game, terminal),
+  }
+}
+
+// Same, but only the code of this player is accepted (the captain settling a tie): any other code is a failure
+pub fn authenticate (game: &mut dyn Game, terminal: Terminal, player_id: PlayerId, key: String) -> LogIn {
+  match game.get_player_id_from_key(key) {
+    Some(id) if id == player_id => log_in_success(game, terminal, player_id),
+    _ => log_in_failure(game, terminal),
+  }
+}
+
+fn log_in_success (game: &mut dyn Game, terminal: Terminal, player_id: PlayerId) -> LogIn {
+  game.get_login_guard(terminal).record_success();
+  game.record_event(EventCause::Player(player_id), EventKind::LoggedIn { player: player_id });
+  LogIn::Success(player_id)
+}
+
+fn log_in_failure (game: &mut dyn Game, terminal: Terminal) -> LogIn {
+  let rules = game.get_config().login.clone();
+  let lockout = game.get_login_guard(terminal).record_failure(&rules, Instant::now());
+  let failures = game.get_login_guard(terminal).failures();
+  game.record_event(EventCause::Unknown, EventKind::LoginFailed { failures });
+  let Some(lockout) = lockout else {
+    return LogIn::Invalid;
+  };
+  game.record_event(EventCause::Unknown, EventKind::TerminalLocked { seconds: lockout.as_secs() });
+  if rules.broadcast_intrusions {
+    game.broadcast(Message {
+      date: game.get_date(),
+      source: String::from("Système de sécurité"),
+      content: String::from("Tentative d'intrusion détectée sur le terminal de control, plusieurs codes invalides ont été saisis."),
+    });
+  }
+  LogIn::Locked(lockout)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::Ipv4Addr;
+
+  use rand::SeedableRng;
+
+  use super::*;
+  use crate::game::{GameRng, GameStatus};
+  use crate::player::Player;
+  use crate::role::RoleCatalog;
+
+  #[test]
+  fn lockout_doubles_after_each_failure() {
+    let rules = LoginRules::default();
+    let mut guard = LoginGuard::default();
+    let now = Instant::now();
+    assert_eq!(guard.record_failure(&rules, now), None);
+    assert_eq!(guard.record_failure(&rules, now), None);
+    assert_eq!(guard.locked_for(now), None);
+    assert_eq!(guard.record_failure(&rules, now), Some(Duration::from_secs(30)));
+    assert_eq!(guard.locked_for(now + Duration::from_secs(10)), Some(Duration::from_secs(20)));
+    assert_eq!(guard.locked_for(now + Duration::from_secs(30)), None);
+    assert_eq!(guard.record_failure(&rules, now), Some(Duration::from_secs(60)));
+    for _ in 0..10 {
+      guard.record_failure(&rules, now);
+    }
+    assert_eq!(guard.locked_for(now), Some(Duration::from_secs(600)));
+  }
+
+  #[test]
+  fn each_terminal_is_locked_on_its_own() {
+    let catalog = RoleCatalog::default();
+    let players = ["patient0", "physician", "astronaut"].iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let mut game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), Default::default());
+    let intruder = Terminal::Remote(Ipv4Addr::new(192, 168, 1, 66).into());
+    for _ in 0..3 {
+      log_in(&mut game, intruder, String::from("wrong"));
+    }
+    assert!(game.get_login_guard(intruder).locked_for(Instant::now()).is_some());
+    assert!(game.get_login_guard(Terminal::Local).locked_for(Instant::now()).is_none());
+    let phone = Terminal::Remote(Ipv4Addr::new(192, 168, 1, 12).into());
+    assert!(matches!(log_in(&mut game, phone, String::from("wrong")), LogIn::Invalid));
+    assert!(matches!(log_in(&mut game, Terminal::Local, String::from("1")), LogIn::Success(_)));
+  }
+
+  #[test]
+  fn success_resets_the_failures() {
+    let rules = LoginRules::default();
+    let mut guard = LoginGuard::default();
+    let now = Instant::now();
+    for _ in 0..3 {
+      guard.record_failure(&rules, now);
+    }
+    guard.record_success();
+    assert_eq!(guard.locked_for(now), None);
+    assert_eq!(guard.record_failure(&rules, now), None);
+  }
+}
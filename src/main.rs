@@ -10,140 +10,320 @@ mod backup;
 mod message;
 mod interface;
 mod game_creator;
+mod event;
+mod rules;
+mod night;
+mod config;
+mod cli;
+mod simulation;
+mod inspector;
+mod keys;
+mod login;
+mod saved_games;
+mod rollback;
+mod server;
+mod session;
 use debug::{mock_game_creator, mock_game_vote_tie};
 use menu::{display_player_status_and_actions, display_home_menu};
-use phases::{run_elimination_phase, run_it_phase, run_mutants_phase, run_physicians_phase, run_psychologist_phase, run_spy_phase, run_geneticist_phase};
-use std::{env, time::Duration};
-use game::{ Game, GameStatus };
+use phases::{run_elimination_phase, run_captain_election_phase};
+use night::default_pipeline;
+use std::{env, path::Path, process, time::Duration};
+use game::{ Game, GameAccess, GameStatus };
 use std::error;
 
-use crate::interface::{Interface, colors::Color};
+use crate::interface::{Idle, Interface, colors::Color, terminal::TerminalInterface};
+use crate::action::ActionType;
+use crate::role::{RoleCatalog, Team};
+use crate::config::GameConfig;
+use crate::cli::{Cli, Command};
+use crate::rollback::SnapshotMoment;
+use crate::backup::encryption::EncryptionError;
 
-fn main() -> Result<(), Box<dyn error::Error>> {
-  let args: Vec<String> = env::args().collect();
-  let debug = args.contains(&String::from("--debug"));
+fn main() {
+  if let Err(error) = run(env::args().skip(1)) {
+    eprintln!("Error: {error}");
+    process::exit(1);
+  }
+}
 
-  let mut interface = Interface::new(debug);
+fn run (args: impl Iterator<Item = String>) -> Result<(), Box<dyn error::Error>> {
+  let cli = cli::parse(args)?;
+  let seed = cli.seed.unwrap_or_else(rand::random);
+  let catalog = match &cli.roles {
+    Some(path) => RoleCatalog::load(path)?,
+    None => RoleCatalog::default(),
+  };
+  let config = match &cli.config {
+    Some(path) => GameConfig::load(path)?,
+    None => GameConfig::default(),
+  };
+  config.check_roles(&catalog)?;
+  config.check_keys()?;
 
-  let mut game;
-  if args.contains(&String::from("--from-backup")) {
-    // Shitty but will do for now
-    let mut iter = args.iter();
-    while iter.next().unwrap() != &String::from("--from-backup") {}
-    let path = iter.next().unwrap();
-    game = GameStatus::restore_from_backup(path).unwrap();
-  } else {
-    if debug {
-      mock_game_creator(&mut interface);
-    }
+  let backup = match &cli.command {
+    Command::Help => {
+      println!("{}", cli::USAGE);
+      return Ok(());
+    },
+    Command::Inspect { backup, other } => {
+      let mut passphrase = None; // both backups most likely have the same
+      let game = load_game(backup, &mut passphrase)?;
+      match other {
+        Some(other) => println!("{}", inspector::diff_games(&game, &load_game(other, &mut passphrase)?)?),
+        None => println!("{}", inspector::describe_game(&game)),
+      }
+      return Ok(());
+    },
+    Command::Simulate { games } => {
+      simulate(catalog, config, cli.players, seed, *games)?;
+      return Ok(());
+    },
+    Command::Resume { backup: Some(backup) } => backup.clone(),
+    Command::Resume { backup: None } => {
+      choose_saved_game(&cli.backup_dir, false)?.ok_or(format!("No game to resume in {}", cli.backup_dir))?
+    },
+    Command::Start if !cli.debug => match choose_saved_game(&cli.backup_dir, true)? {
+      Some(backup) => backup,
+      None => return new_game(&cli, seed, catalog, config),
+    },
+    Command::Start | Command::New => return new_game(&cli, seed, catalog, config),
+  };
 
-    game = game_creator::create_game(&mut interface, debug)?;
+  let mut game = load_game(&backup, &mut None)?;
+  let mut interface = TerminalInterface::new(game.debug());
+  interface.set_sound(cli.sound && game.get_config().sound); // a restored game keeps its own configuration
+  game.set_backup_dir(Some(cli.backup_dir.clone()));
+  start_game(game, &mut interface, &cli)?;
+  Ok(())
+}
 
-    if debug {
-      mock_game_vote_tie(&mut interface, &mut game);
+fn new_game (cli: &Cli, seed: u64, catalog: RoleCatalog, config: GameConfig) -> Result<(), Box<dyn error::Error>> {
+  let mut interface = TerminalInterface::new(cli.debug);
+  interface.set_sound(cli.sound && config.sound);
+  if cli.debug {
+    mock_game_creator(&mut interface);
+  }
+  let mut game = game_creator::create_game(&mut interface, cli.debug, seed, catalog, config)?;
+  if cli.debug && cli.serve.is_none() && cli.serve_tcp.is_none() { // the votes are typed on the terminal
+    mock_game_vote_tie(&mut interface);
+  }
+  game.set_backup_dir(Some(cli.backup_dir.clone()));
+  start_game(game, &mut interface, cli)?;
+  Ok(())
+}
+
+// Encrypted backups need the game master password, which is asked until it is right (3 times at most)
+fn load_game (path: &String, passphrase: &mut Option<String>) -> Result<GameStatus, Box<dyn error::Error>> {
+  let mut attempts = 0;
+  loop {
+    match GameStatus::restore_from_backup(path, passphrase.as_deref()) {
+      Err(error) if error.is::<EncryptionError>() && attempts < 3 => {
+        let mut interface = TerminalInterface::new(false);
+        if attempts > 0 {
+          interface.println("Mot de passe invalide");
+        }
+        attempts += 1;
+        *passphrase = Some(interface.user_non_empty_input(format!("Mot de passe du maître du jeu pour {path}:").as_str())?);
+      },
+      result => return result.map_err(|error| format!("Cannot load {path}: {error}").into()),
     }
   }
+}
 
-  start_game(game, &mut interface);
+// None when there is no saved game, or when the host chose to create a new game
+fn choose_saved_game (backup_dir: &str, new_game: bool) -> Result<Option<String>, Box<dyn error::Error>> {
+  let saved_games = saved_games::find_saved_games(Path::new(backup_dir))?;
+  if saved_games.is_empty() {
+    return Ok(None);
+  }
+  let mut interface = TerminalInterface::new(false);
+  let choice = saved_games::pick_saved_game(&mut interface, &saved_games, new_game)?;
+  Ok(choice.map(|path| path.to_string_lossy().to_string()))
+}
 
-  return Ok(());
+fn simulate (catalog: RoleCatalog, config: GameConfig, players: usize, seed: u64, games: usize) -> Result<(), Box<dyn error::Error>> {
+  let mut mutants_victories = 0;
+  for idx in 0..games {
+    let game_seed = seed.wrapping_add(idx as u64);
+    let game = simulation::simulate_game(catalog.clone(), config.clone(), players, game_seed)?;
+    let result = match game.get_end_reason() {
+      Some(reason) if reason.mutants_won() => {
+        mutants_victories += 1;
+        format!("victoire des mutants ({reason})")
+      },
+      Some(reason) => format!("victoire de l'équipage ({reason})"),
+      None => String::from("partie interrompue"),
+    };
+    println!("Partie {} (graine {game_seed}): jour {}, {result}", idx + 1, game.get_date());
+  }
+  println!("Victoires des mutants: {mutants_victories}/{games}");
+  Ok(())
 }
 
-fn start_game (mut game: impl Game, interface: &mut Interface) {
+fn start_game (mut game: GameStatus, interface: &mut dyn Interface, cli: &Cli) -> Result<(), Box<dyn error::Error>> {
+  snapshot(&mut GameAccess::Owned(&mut game), interface, SnapshotMoment::Launch)?;
+  if cli.serve.is_some() || cli.serve_tcp.is_some() {
+    game = server::serve_game(game, interface, cli.serve.as_deref(), cli.serve_tcp.as_deref())?; // only returns once the game is over
+  }
+  let mut session = None; // the player logged in on the terminal
   while !game.ended() {
-    match game.get_current_player_id() {
-      Some(current_player_id) => {
+    session = match session {
+      Some(current_session) => {
         display_player_status_and_actions(
           &mut game,
           interface,
-          current_player_id,
-        );
+          &current_session,
+        )?;
+        game.get_session_player(&current_session).map(|_| current_session)
       }
-      None => display_home_menu(&mut game, interface),
+      None => display_home_menu(&mut game, interface)?,
     }
   }
   end_game(game, interface);
+  Ok(())
 }
 
-pub fn run_end_of_day (game: &mut dyn Game, interface: &mut Interface) {
+// The day starts with the election of the captain when the crew has none: on the first day, or after a captain died without successor
+pub fn run_captain_election (game: &mut GameAccess, interface: &mut dyn Interface) -> Result<(), Idle> {
+  let missing_players = missing_players(&*game.lock());
+  if !missing_players.is_empty() {
+    interface.user_validate(format!("J'exige le vote des membres d'équipages {:?} avant l'élection du capitaine", missing_players).as_str())?;
+    return Ok(());
+  }
+  run_captain_election_phase(interface, game)?;
+  let result = game.lock().autosave();
+  report_backup_error(result, interface)
+}
+
+pub fn run_end_of_day (game: &mut GameAccess, interface: &mut dyn Interface) -> Result<(), Idle> {
+  if game.lock().get_captain().is_none() { // not offered by the menus, the election opens the day
+    interface.user_validate("Le·a capitaine doit être élu·e avant l'extinction des feux")?;
+    return Ok(());
+  }
   // Check that everyone played
-  if !game.debug() {
-    let living_players = game.get_players();
-    let missing_players = living_players
-      .iter()
-      .filter_map(|player| if player.has_connected_today { None } else { Some(&player.name) })
-      .collect::<Vec<&String>>();
-    if missing_players.len() > 0 {
-      interface.user_validate(format!("J'exige la visite des membres d'équipages {:?} avant l'extinction des feux", missing_players).as_str());
-      return;
-    }
+  let missing_players = missing_players(&*game.lock());
+  if !missing_players.is_empty() {
+    interface.user_validate(format!("J'exige la visite des membres d'équipages {:?} avant l'extinction des feux", missing_players).as_str())?;
+    return Ok(());
   }
 
-  let victim = run_elimination_phase(interface, game);
-  game.set_phase_of_day(game::PhaseOfDay::Twilight); // not sure who should control this
+  snapshot(game, interface, SnapshotMoment::BeforeElimination)?;
+
+  let victim = run_elimination_phase(interface, game)?;
+  let result = {
+    let mut game = game.lock();
+    game.set_phase_of_day(game::PhaseOfDay::Twilight); // not sure who should control this
+    game.check_end_of_game();
+    game.autosave()
+  };
+  report_backup_error(result, interface)?;
+  if game.lock().ended() {
+    return Ok(());
+  }
   if victim.is_none() { // If no-one died we directly play the night
-    run_night(game, interface);
+    run_night(game, interface)?;
+  }
+  Ok(())
+}
+
+// The living players the votes wait for: everyone votes for the election, and comes by during the rest of the day
+pub fn missing_players (game: &dyn Game) -> Vec<String> {
+  if game.debug() {
+    return Vec::new();
   }
+  let electing = game.get_captain().is_none();
+  game.get_players().into_iter()
+    .filter(|player| if electing { player.get_target(&ActionType::ElectCaptain).is_none() } else { !player.has_connected_today })
+    .map(|player| player.name.clone())
+    .collect()
 }
 
-pub fn run_night(game: &mut dyn Game, interface: &mut Interface) {
+pub fn run_night(game: &mut GameAccess, interface: &mut dyn Interface) -> Result<(), Idle> {
 
-  let killed_by_mutants = run_mutants_phase(game);
-  let killed_by_physicians = run_physicians_phase(game);
-  run_it_phase(game);
-  run_psychologist_phase(game);
-  run_geneticist_phase(game);
-  run_spy_phase(game);
+  let (dead_players, pause) = {
+    let mut game = game.lock();
+    let report = default_pipeline().run(&mut *game);
+    let dead_players = report.killed.iter()
+      .map(|player| game.get_player(*player).name.clone())
+      .collect::<Vec<String>>();
+    (dead_players, game.get_config().pause())
+  };
 
   interface.clear_terminal();
-  interface.wait_and_display("La nuit passe...", Duration::from_secs(5), Duration::from_millis(700));
+  interface.wait_and_display("La nuit passe...", pause, Duration::from_millis(700));
 
-  if killed_by_mutants == None && killed_by_physicians == None {
+  if dead_players.is_empty() {
     interface.play_no_death_good_sound();
-    interface.user_validate("Rien à signaler pour cette nuit");
+    interface.user_validate("Rien à signaler pour cette nuit")?;
   } else {
-    let mut dead_players = Vec::new();
-    if let Some(player) = killed_by_mutants {
-      dead_players.push(game.get_player(player).name.clone());
-    }
-    if let Some(player) = killed_by_physicians {
-      dead_players.push(game.get_player(player).name.clone());
-    }
     interface.play_death_sound();
     interface.user_validate(format!("C'est avec tristesse et amertume que nous vous annonçons la perte accidentelle de {} cette nuit",
-      dead_players.join(" et ")).as_str());
+      dead_players.join(" et ")).as_str())?;
   }
 
-  game.prepare_new_turn();
+  let result = {
+    let mut game = game.lock();
+    game.prepare_new_turn();
+    game.check_end_of_game();
+    game.backup()
+  };
+  report_backup_error(result, interface)?;
+  snapshot(game, interface, SnapshotMoment::DayStart)
+}
+
+// Written after each log out and each change of phase, so a crash loses nothing of the day
+pub fn autosave (game: &mut dyn Game, interface: &mut dyn Interface) -> Result<(), Idle> {
+  let result = game.autosave();
+  report_backup_error(result, interface)
+}
 
-  backup(game, interface);
+fn snapshot (game: &mut GameAccess, interface: &mut dyn Interface, moment: SnapshotMoment) -> Result<(), Idle> {
+  let result = game.lock().take_snapshot(moment);
+  report_backup_error(result, interface)
 }
 
-fn backup (game: &mut dyn Game, interface: &mut Interface) {
-  if let Err(error) = game.backup("backups/") {
+pub fn report_backup_error (result: Result<(), Box<dyn error::Error>>, interface: &mut dyn Interface) -> Result<(), Idle> {
+  if let Err(error) = result {
     interface.clear_terminal();
-    println!("WARNING - Backup Error: details written to stderr");
+    interface.println("WARNING - Backup Error: details written to stderr");
     eprintln!("WARNING - Backup Error: {}", error);
-    interface.user_validate("Appuyez sur entrée pour continuer");
+    interface.user_validate("Appuyez sur entrée pour continuer")?;
     interface.clear_terminal();
   }
+  Ok(())
 }
 
-fn end_game(game: impl Game, interface: &mut Interface) {
+fn end_game(game: impl Game, interface: &mut dyn Interface) {
   interface.clear_terminal();
 
-  let healthy_players = game.get_players().iter().filter(|player| !player.infected).count();
-  if healthy_players == 0 {
-    println!("===== Victoire des mutants =====");
-    println!("Le {} est maintenant aux mains des mutants et, avec la coopération des centaines de passagers en sommeil, essaimera la mutation dans la galaxie.", Color::Bright.color(game.get_name()));
-    println!("Féliciations aux mutants");
-    println!("Vous êtes l'avenir de l'humanité");
-    println!("Mais il reste beaucoup à faire...");
+  let end_reason = game.get_end_reason().unwrap(); // the game only ends with a reason
+  if end_reason.mutants_won() {
+    interface.println("===== Victoire des mutants =====");
+    interface.println(end_reason.to_string().as_str());
+    interface.println(format!("Le {} est maintenant aux mains des mutants et, avec la coopération des centaines de passagers en sommeil, essaimera la mutation dans la galaxie.", Color::Bright.color(game.get_name())).as_str());
+    interface.println("Féliciations aux mutants");
+    let traitors = game.get_all_players()
+      .filter_map(|player| if player.role.team == Team::Mutants && !player.infected { Some(player.name.clone()) } else { None })
+      .collect::<Vec<String>>();
+    if !traitors.is_empty() {
+      interface.println(format!("Ainsi qu'à leurs fidèles allié·e·s: [{}]", traitors.join(", ")).as_str());
+    }
+    interface.println("Vous êtes l'avenir de l'humanité");
+    interface.println("Mais il reste beaucoup à faire...");
   } else {
-    println!("===== Victoire de l'humanité =====");
-    println!("L'équipage du {} est parvenu, au prix de grands sacrifices, à contenir et éliminer la mutation.", Color::Bright.color(game.get_name()));
-    println!("Féliciations aux survivants");
-    println!("Grâce à vous l'humanité est sauve");
-    println!("Pour le moment...");
+    interface.println("===== Victoire de l'humanité =====");
+    interface.println(end_reason.to_string().as_str());
+    interface.println(format!("L'équipage du {} est parvenu, au prix de grands sacrifices, à contenir et éliminer la mutation.", Color::Bright.color(game.get_name())).as_str());
+    interface.println("Féliciations aux survivants");
+    interface.println("Grâce à vous l'humanité est sauve");
+    interface.println("Pour le moment...");
+  }
+
+  interface.println("");
+  interface.println(format!("Partie générée avec la graine {} (--seed {} pour la rejouer)", game.get_seed(), game.get_seed()).as_str());
+  interface.println("");
+  interface.println("Chronique de la partie:");
+  for event in game.get_events().iter().filter(|event| event.is_notable()) {
+    interface.println(event.describe(&game).as_str());
   }
 }
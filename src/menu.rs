@@ -1,37 +1,87 @@
-use std::{collections::HashMap, time::Duration};
+use std::{collections::HashMap, time::{Duration, Instant}};
 
-use crate::{game::{Game, PlayerGame, PhaseOfDay}, interface::{Interface, colors::Color}, action::{Action, Action::{GeneralAction, UserAction}, ActionType, get_header_text, get_menu_text}, player::{Player, PlayerId}, role::Role, run_night, run_end_of_day};
+use crate::{game::{Game, GameAccess, PlayerGame, PhaseOfDay}, interface::{Idle, Interface, colors::Color}, action::{Action, Action::{GeneralAction, UserAction}, ActionType, get_header_text, get_menu_text}, player::{CrewMember, PlayerId}, role::{RoleAction, Team}, phases::ROLE_MODULES, run_night, run_end_of_day, run_captain_election, report_backup_error, rollback::run_action_rollback, login::{log_in, LogIn, Terminal}, session::SessionId};
 
-use rand::{Rng, seq::SliceRandom, thread_rng};
+use rand::{Rng, seq::SliceRandom};
 
-pub fn display_home_menu (game: &mut dyn Game, interface: &mut Interface) {
+// Returns the session of the player who logged in, if any: it is the cursor of this terminal
+pub fn display_home_menu (game: &mut dyn Game, interface: &mut dyn Interface) -> Result<Option<SessionId>, Idle> {
   interface.clear_terminal();
+  display_ship_status(game, interface);
+  let mut actions_list: Vec<Action> = Vec::new();
+  actions_list.push(GeneralAction(
+    String::from("Identification"),
+    |_, _| Ok(()), // the identification opens a session, see below
+  ));
+  actions_list.append(&mut game_master_actions(game));
+  let action = interface.user_select_action(&actions_list)?;
+  if std::ptr::eq(action, &actions_list[0]) {
+    return run_action_log_in(game, interface, Terminal::Local);
+  }
+  match action {
+    UserAction(_, _) => panic!(""), // Arghhhh, didn't expect to have to do this :/
+    GeneralAction(_, run) => run(&mut GameAccess::Owned(game), interface).map(|_| None),
+  }
+}
+
+// The home screen of the devices on the network: only the identification, the game master actions stay on the local terminal
+// There is nothing to validate after a wrong code, the outcome is given back to be displayed on the next home screen
+pub fn display_remote_home_menu (game: &mut dyn Game, interface: &mut dyn Interface, terminal: Terminal, notice: Option<&str>) -> Result<Result<SessionId, String>, Idle> {
+  interface.clear_terminal();
+  display_ship_status(game, interface);
+  if let Some(notice) = notice {
+    interface.println("");
+    interface.println(notice);
+  }
+  interface.println("");
+  let key = interface.user_non_empty_input("Entrez votre code d'identification:")?;
+  if let Some(remaining) = game.get_login_guard(terminal).locked_for(Instant::now()) {
+    return Ok(Err(format!("Terminal verrouillé suite à plusieurs codes invalides, réessayez dans {} secondes.", remaining.as_secs() + 1)));
+  }
+  match log_in(game, terminal, key) {
+    LogIn::Success(player_id) => Ok(Ok(game.open_session(player_id))),
+    LogIn::Invalid => Ok(Err(String::from("Code invalide"))),
+    LogIn::Locked(lockout) => Ok(Err(format!("Code invalide. Tentative d'intrusion détectée: terminal verrouillé pendant {} secondes.", lockout.as_secs()))),
+  }
+}
+
+pub fn display_ship_status (game: &dyn Game, interface: &mut dyn Interface) {
   let current_date = game.get_date();
-  println!("Bienvenue sur le terminal de control du {}", Color::Bright.color(game.get_name()));
+  interface.println(format!("Bienvenue sur le terminal de control du {}", Color::Bright.color(game.get_name())).as_str());
   match game.get_phase_of_day() {
     PhaseOfDay::Day => {
-      println!("Nous sommes le {}ème jour après détection de l'infection", game.get_date());
-      let subtext = format!("Phase de journée: Vous pouvez voter pour un joueur à éliminer et choisis vos actions");
-      println!("* {}", subtext.as_str());
+      interface.println(format!("Nous sommes le {}ème jour après détection de l'infection", game.get_date()).as_str());
+      let subtext = match game.get_captain() {
+        Some(_) => String::from("Phase de journée: Vous pouvez voter pour un joueur à éliminer et choisir vos actions"),
+        None => String::from("Phase d'élection: Vous pouvez voter pour élire le·a capitaine et choisir vos actions"),
+      };
+      interface.println(format!("* {}", subtext.as_str()).as_str());
     },
     PhaseOfDay::Twilight => {
-      println!("Nous sommes au crépuscule du {}ème jour après détection de l'infection", current_date);
+      interface.println(format!("Nous sommes au crépuscule du {}ème jour après détection de l'infection", current_date).as_str());
       if let Some(dead_player) = game.get_all_players().find(|player| player.death_date == Some(current_date)) {
         let subtext = format!("Phase crépusculaire: Suite à l'élimination de {} vous pouvez choisir de modifier vos actions", dead_player.name);
-        println!("* {}", Color::Bright.color(subtext.as_str()));
+        interface.println(format!("* {}", Color::Bright.color(subtext.as_str())).as_str());
       }
     }
 }
+  if let Some(captain) = game.get_captain() {
+    interface.println(format!("* Capitaine: {}", game.get_player(captain).name).as_str());
+  }
+}
+
+// Everything but the identification of the players, also offered when the players use their own devices
+pub fn game_master_actions (game: &dyn Game) -> Vec<Action> {
   let mut actions_list: Vec<Action> = Vec::new();
-  actions_list.push(GeneralAction(
-    String::from("Identification"),
-    run_action_log_in,
-  ));
   actions_list.push(GeneralAction(
     String::from("Status de l'équipage"),
     run_action_crew_status,
   ));
   match game.get_phase_of_day() {
+    PhaseOfDay::Day if game.get_captain().is_none() => actions_list.push(GeneralAction(
+      String::from("Élection du capitaine"),
+      run_captain_election,
+    )),
     PhaseOfDay::Day => actions_list.push(GeneralAction(
       String::from("Fin de la journée"),
       run_end_of_day,
@@ -41,31 +91,43 @@ pub fn display_home_menu (game: &mut dyn Game, interface: &mut Interface) {
       run_night,
     )),
   }
-  match interface.user_select_action(&actions_list) {
-    UserAction(_, _) => panic!(""), // Arghhhh, didn't expect to have to do this :/
-    GeneralAction(_, run) => run(game, interface),
+  if game.get_config().gm_password.is_some() {
+    actions_list.push(GeneralAction(
+      String::from("Revenir en arrière (maître du jeu)"),
+      run_action_rollback,
+    ));
   }
+  actions_list
 }
 
-pub fn run_action_log_in(game: &mut dyn Game, interface: &mut Interface) {
+pub fn run_action_log_in(game: &mut dyn Game, interface: &mut dyn Interface, terminal: Terminal) -> Result<Option<SessionId>, Idle> {
   interface.clear_terminal();
-  let key = interface.user_non_empty_input("Entrez votre code d'identification:");
-  let player_id = game.get_player_id_from_key(key);
-  match player_id {
-    Some(player_id) => {
-      game.set_current_player_id(Some(player_id));
-    }
-    None => interface.user_validate("Code invalide, appuyez sur ENTREE pour revenir a l'écran d'accueil."),
+  if let Some(remaining) = game.get_login_guard(terminal).locked_for(Instant::now()) {
+    interface.user_validate(format!("Terminal verrouillé suite à plusieurs codes invalides, réessayez dans {} secondes.", remaining.as_secs() + 1).as_str())?;
+    return Ok(None);
+  }
+  let key = interface.user_non_empty_input("Entrez votre code d'identification:")?;
+  match log_in(game, terminal, key) {
+    LogIn::Success(player_id) => return Ok(Some(game.open_session(player_id))),
+    LogIn::Invalid => interface.user_validate("Code invalide, appuyez sur ENTREE pour revenir a l'écran d'accueil.")?,
+    LogIn::Locked(lockout) => {
+      interface.play_alarm(format!("Code invalide. Tentative d'intrusion détectée: terminal verrouillé pendant {} secondes.", lockout.as_secs()).as_str())?;
+    },
   }
+  Ok(None)
 }
 
-pub fn run_action_crew_status(game: &mut dyn Game, interface: &mut Interface) {
-  let mut rng = rand::thread_rng(); // Used to generate random ids for display
-  println!("\nStatus de l'équipage:");
+pub fn run_action_crew_status(game: &mut GameAccess, interface: &mut dyn Interface) -> Result<(), Idle> {
+  let mut rng = rand::thread_rng(); // Used to generate random ids for display, not from the game's rng so it doesn't alter the game
+  interface.println("\nStatus de l'équipage:");
+  let mut lines = Vec::new();
+  let game = game.lock();
+  let captain = game.get_captain();
   for player in game.get_all_players() {
+    let name = if Some(player.id) == captain { format!("{} (capitaine)", player.name) } else { player.name.clone() };
     if game.debug() {
-      println!("* Membre d'équipage n°{} - {} {}{} {}: {}",
-        player.key,
+      lines.push(format!("* Membre d'équipage n°{} - {} {}{} {}: {}",
+        rng.gen_range(0..100), // the keys are secret, even when debugging
         player.role,
         if player.infected { Color::FgRed.color("mutant") } else { Color::FgGreen.color("saint") },
         if player.host {
@@ -75,32 +137,51 @@ pub fn run_action_crew_status(game: &mut dyn Game, interface: &mut Interface) {
         } else {
           String::from("")
         },
-        player.name,
+        name,
         if player.alive {
           String::from(Color::FgGreen.color("Actif"))
         } else {
           format!("{} ({})", Color::Blink.color(Color::FgRed.color("Décédé·e").as_str()), player.get_death_cause())
         },
-      )
+      ))
     } else {
-      println!("* Membre d'équipage n°{} - {}: {}",
+      lines.push(format!("* Membre d'équipage n°{} - {}: {}",
         rng.gen_range(0..100),
-        player.name,
+        name,
         if player.alive {
           String::from(Color::FgGreen.color("Actif"))
         } else {
           format!("{} ({})", Color::Blink.color(Color::FgRed.color("Décédé·e").as_str()), player.get_death_cause())
         },
-      )
-
+      ))
     }
   }
-  interface.user_validate("");
+  drop(game); // the list is written, the players can play while the game master reads it
+  for line in lines {
+    interface.println(line.as_str());
+  }
+  interface.user_validate("")
+}
+
+// The player is logged out if they stop answering, so their secrets do not stay on screen after they leave
+pub fn display_player_status_and_actions (game_status: &mut impl Game, interface: &mut dyn Interface, session: &SessionId) -> Result<(), Idle> {
+  interface.set_idle_timeout(game_status.get_config().idle_timeout());
+  let result = display_player_menu(game_status, interface, session);
+  interface.set_idle_timeout(None);
+  match (result, game_status.get_session_game(session)) {
+    (Err(Idle), Some(mut game)) => run_log_out(&mut game, interface),
+    (result, _) => result,
+  }
 }
 
-pub fn display_player_status_and_actions (game_status: &mut impl Game, interface: &mut Interface, current_player_id: PlayerId) {
+// One screen of a logged in player: their status, then one of their actions
+// The session is the only way to the player: a closed session has nothing left to display
+pub fn display_player_menu (game_status: &mut impl Game, interface: &mut dyn Interface, session: &SessionId) -> Result<(), Idle> {
   interface.clear_terminal();
-  let game: &mut dyn PlayerGame = &mut game_status.get_player_game(current_player_id);
+  let Some(mut turn) = game_status.get_session_game(session) else {
+    return Ok(());
+  };
+  let game: &mut dyn PlayerGame = &mut turn;
   game.get_mut_current_player().has_connected_today = true;
   let player = game.get_current_player();
   let mut actions_list = Vec::new();
@@ -109,22 +190,27 @@ pub fn display_player_status_and_actions (game_status: &mut impl Game, interface
   } else {
     Color::Blink.color(Color::FgRed.color("Mort").as_str())
   };
-  println!("Bienvenue {}, vous êtes un {} {}", player.name, player.role, status);
-  if player.role == Role::Physician { //Physicians know the list of other physicians
-    let physician_names: Vec<String> = game.get_players().iter()
-      .filter_map(|player| if player.role == Role::Physician { Some(player.name.clone()) } else { None }).collect();
-    println!("* Membres de l'équipe médicale: [{}]", physician_names.join(", "));
+  interface.println(format!("Bienvenue {}, vous êtes un {} {}", player.name, player.role, status).as_str());
+  if game.get_captain() == Some(player.id) {
+    interface.println(format!("* Vous êtes le·a capitaine du vaisseau: vous tranchez les égalités lors des éliminations, et votre vote compte pour {} voix", game.get_rules().captain_vote_weight).as_str());
+  }
+  if player.can(RoleAction::Cure) { //Physicians know the list of other physicians
+    let physician_names: Vec<String> = game.get_medical_team().into_iter().map(|physician| physician.name).collect();
+    interface.println(format!("* Membres de l'équipe médicale: [{}]", physician_names.join(", ")).as_str());
   }
   if player.infected {
-    println!("En tant que mutant, vous devez prendre le contrôle du vaisseau en infectant ou éliminant tous les membres d'équipage encore saints!");
+    interface.println("En tant que mutant, vous devez prendre le contrôle du vaisseau en infectant ou éliminant tous les membres d'équipage encore saints!");
+  } else if player.role.team == Team::Mutants {
+    interface.println("Vous êtes parfaitement sain·e, mais votre loyauté va aux mutants: aidez-les à prendre le contrôle du vaisseau sans vous faire démasquer!");
+    interface.println("Vous gagnez avec les mutants, même si vous n'êtes pas infecté·e.");
   } else {
-    println!("Vous devez nous aider à contenir la propagation et éliminer les mutants à bord avant qu'il ne soit trop tard!");
+    interface.println("Vous devez nous aider à contenir la propagation et éliminer les mutants à bord avant qu'il ne soit trop tard!");
   }
   if player.messages.len() > 0 {
-    println!("");
-    println!("Messages personnels:");
+    interface.println("");
+    interface.println("Messages personnels:");
     for message in &player.messages {
-      println!("{}", message.to_string());
+      interface.println(message.to_string().as_str());
     }
   }
 
@@ -132,35 +218,27 @@ pub fn display_player_status_and_actions (game_status: &mut impl Game, interface
 
   if player.alive {
     if game.get_phase_of_day() == &PhaseOfDay::Day {
-      // Players are only allowed to vote during the day
-      add_action_elimination(game, &mut actions_list);
+      // Players are only allowed to vote during the day, for the captain first when the crew has none
+      if game.get_captain().is_none() {
+        add_action_captain_election(game, &mut actions_list);
+      } else {
+        add_action_elimination(game, &mut actions_list);
+      }
     } else {
-      println!("");
-      print!("Le vote sur l'élimination d'un membre d'équipage à déjà au lieu pour aujoud'hui");
-      println!(" (revenez demain pour une autre chance d'assassiner un de vos amis!)");
+      interface.println("");
+      interface.print("Le vote sur l'élimination d'un membre d'équipage à déjà au lieu pour aujoud'hui");
+      interface.println(" (revenez demain pour une autre chance d'assassiner un de vos amis!)");
     }
 
-    match game.get_current_player().role {
-      Role::Patient0 => add_action_patient_0(game, &mut actions_list),
-      Role::Psychologist => add_action_psychologist(game, &mut actions_list),
-      Role::Physician => add_action_physician(game, &mut actions_list),
-      Role::Geneticist => add_action_geneticist(game, &mut actions_list),
-      Role::ITEngineer => add_action_it_engineer(game, &mut actions_list),
-      Role::Spy => add_action_spy(game, &mut actions_list),
-      Role::Hacker => add_action_hacker(game, &mut actions_list),
-      Role::Traitor => add_action_traitor(game, &mut actions_list),
-      Role::Astronaut => add_action_astronaut(game, &mut actions_list),
-    }
-
-    if game.get_current_player().infected {
-      add_action_mutant(game, &mut actions_list);
+    for module in ROLE_MODULES {
+      (module.add_actions)(game, &mut actions_list);
     }
   }
 
-  println!("");
-  match interface.user_select_action(&actions_list) {
+  interface.println("");
+  match interface.user_select_action(&actions_list)? {
     UserAction(_, run) => run(game, interface),
-    GeneralAction(_, run) => run(game_status, interface),
+    GeneralAction(_, run) => run(&mut GameAccess::Owned(game_status), interface),
   }
 }
 
@@ -171,142 +249,24 @@ pub fn add_action_elimination(game: &mut dyn PlayerGame, actions_list: &mut Vec<
     game,
     actions_list,
     ActionType::Eliminate,
-    |game: &mut dyn PlayerGame, interface: &mut Interface| run_target_action(game, interface, ActionType::Eliminate),
+    |game: &mut dyn PlayerGame, interface: &mut dyn Interface| run_target_action(game, interface, ActionType::Eliminate),
   );
 }
 
-// Actions for mutants
+// Actions for the captain
 
-pub fn add_action_mutant(game: &mut dyn PlayerGame, actions_list: &mut Vec<Action>) {
-  add_target_action(
-    game,
-    actions_list,
-    ActionType::Infect,
-    |game: &mut dyn PlayerGame, interface: &mut Interface| run_target_action(game, interface, ActionType::Infect),
-  );
+pub fn add_action_captain_election(game: &mut dyn PlayerGame, actions_list: &mut Vec<Action>) {
   add_target_action(
     game,
     actions_list,
-    ActionType::Paralyze,
-    |game: &mut dyn PlayerGame, interface: &mut Interface| run_target_action(game, interface, ActionType::Paralyze),
+    ActionType::ElectCaptain,
+    |game: &mut dyn PlayerGame, interface: &mut dyn Interface| run_target_action(game, interface, ActionType::ElectCaptain),
   );
-  actions_list.push(Action::UserAction( // Action to choose between mutating or killing
-    if game.get_current_player().mutant_kill {
-      format!("Choisir de muter la cible [{}]", Color::FgRed.color("Tuer"))
-    } else {
-      format!("Choisir de tuer la cible [{}]", Color::FgGreen.color("Muter"))
-    },
-    |game: &mut dyn PlayerGame, _interface: &mut Interface| {
-      let current_player = game.get_mut_current_player();
-      current_player.mutant_kill = !current_player.mutant_kill;
-    }
-  ));
 }
 
-// Actions for roles
-
-pub fn add_action_patient_0(_game: &mut dyn PlayerGame, _actions_list: &mut Vec<Action>) {}
-
-pub fn add_action_psychologist(game: &mut dyn PlayerGame, actions_list: &mut Vec<Action>) {
-  add_target_action(
-    game,
-    actions_list,
-    ActionType::Psychoanalyze,
-    |game: &mut dyn PlayerGame, interface: &mut Interface| run_target_action(game, interface, ActionType::Psychoanalyze),
-  );
-}
-
-pub fn add_action_physician(game: &mut dyn PlayerGame, actions_list: &mut Vec<Action>) {
-  if !game.get_current_player().infected { // An infected physician cannot cure
-    add_target_action( // Action to select some to cure
-      game,
-      actions_list,
-      ActionType::Cure,
-      |game: &mut dyn PlayerGame, interface: &mut Interface| run_target_action(game, interface, ActionType::Cure),
-    );
-    actions_list.push(Action::UserAction( // Action to toggle auto-cure of other physicians
-      if game.get_current_player().auto_cure_physician {
-        format!("Que faire des médecins inactifs: [{}, {}, {}]", Color::Underscore.color(Color::FgGreen.color("Soigner").as_str()), "Tuer", "Rien")
-      } else if game.get_current_player().auto_kill_physician {
-        format!("Que faire des médecins inactifs: [{}, {}, {}]", "Soigner", Color::Underscore.color(Color::FgRed.color("Tuer").as_str()), "Rien")
-      } else {
-        format!("Que faire des médecins inactifs: [{}, {}, {}]", "Soigner", "Tuer", Color::Underscore.color("Rien"))
-      },
-      |game: &mut dyn PlayerGame, _interface: &mut Interface| {
-        let current_player = game.get_mut_current_player();
-        if current_player.auto_cure_physician {
-          current_player.auto_cure_physician = false;
-          current_player.auto_kill_physician = true;
-        } else if current_player.auto_kill_physician {
-          current_player.auto_kill_physician = false
-        } else {
-          current_player.auto_cure_physician = true;
-        }
-      }
-    ));
-    actions_list.push(Action::UserAction( // Action to choose between curing or killing
-      if game.get_current_player().physician_kill {
-        format!("Que faire de la cible [{}, {}]", "Soigner", Color::Underscore.color(Color::FgRed.color("Tuer").as_str()))
-      } else {
-        format!("Que faire de la cible [{}, {}]", Color::Underscore.color(Color::FgGreen.color("Soigner").as_str()), "Tuer")
-      },
-      |game: &mut dyn PlayerGame, _interface: &mut Interface| {
-        let current_player = game.get_mut_current_player();
-        current_player.physician_kill = !current_player.physician_kill;
-      }
-    ));
-  }
-}
-
-pub fn add_action_geneticist(game: &mut dyn PlayerGame, actions_list: &mut Vec<Action>) {
-  add_target_action(
-    game,
-    actions_list,
-    ActionType::Genomyze,
-    |game: &mut dyn PlayerGame, interface: &mut Interface| run_target_action(game, interface, ActionType::Genomyze),
-  );
-}
-
-pub fn add_action_it_engineer(_game: &mut dyn PlayerGame, _actions_list: &mut Vec<Action>) {}
-
-pub fn add_action_spy(game: &mut dyn PlayerGame, actions_list: &mut Vec<Action>) {
-  add_target_action(
-    game,
-    actions_list,
-    ActionType::Spy,
-    |game: &mut dyn PlayerGame, interface: &mut Interface| run_target_action(game, interface, ActionType::Spy),
-  );
-}
-
-pub fn add_action_hacker(game: &mut dyn PlayerGame, actions_list: &mut Vec<Action>) {
-  actions_list.push(Action::UserAction( // Action to toggle auto-cure of other physicians
-    match game.get_current_player().hacker_target {
-      Some(target) => format!("Selectionner un role à pirater [{}]", target),
-      None => format!("Selectionner un role à pirater"),
-    },
-    |game: &mut dyn PlayerGame, interface: &mut Interface| {
-      let hackable_roles = vec![Role::Geneticist, Role::ITEngineer, Role::Spy];
-      let hackable_roles = game.get_players().iter()
-        .filter_map(|player| if hackable_roles.contains(&player.role) { Some(player.role) } else { None })
-        .collect::<Vec<Role>>();
-      if hackable_roles.len() == 0 {
-        interface.user_validate("Désolé, il n'y a personne que vous puissiez hacker");
-      } else {
-        game.get_mut_current_player().hacker_target = Some(*interface.user_select_from(hackable_roles.iter()));
-      }
-    }
-  ));
-}
-
-pub fn add_action_traitor(_game: &mut dyn PlayerGame, _actions_list: &mut Vec<Action>) {
-  todo!();
-}
-
-pub fn add_action_astronaut(_game: &mut dyn PlayerGame, _actions_list: &mut Vec<Action>) {}
-
 // Actions helpers
 
-pub fn add_target_action(game: &mut dyn PlayerGame, actions_list: &mut Vec<Action>, action: ActionType, run: fn(&mut dyn PlayerGame, interface: &mut Interface)) {
+pub fn add_target_action(game: &mut dyn PlayerGame, actions_list: &mut Vec<Action>, action: ActionType, run: fn(&mut dyn PlayerGame, interface: &mut dyn Interface) -> Result<(), Idle>) {
   // It's a bit annoying to have to take "run" here, but closures using the scope seem to be a bit trickier
   actions_list.push(Action::UserAction(
     match game.get_current_target(&action) {
@@ -317,55 +277,71 @@ pub fn add_target_action(game: &mut dyn PlayerGame, actions_list: &mut Vec<Actio
   ));
 }
 
-pub fn run_target_action(game: &mut dyn PlayerGame, interface: &mut Interface, action: ActionType) {
+pub fn run_target_action(game: &mut dyn PlayerGame, interface: &mut dyn Interface, action: ActionType) -> Result<(), Idle> {
   interface.clear_terminal();
   match game.get_current_target(&action) {
-    Some(target) => println!("{} [{}]", get_header_text(action), target.name),
-    None => println!("{}", get_header_text(action)),
+    Some(target) => interface.println(format!("{} [{}]", get_header_text(action), target.name).as_str()),
+    None => interface.println(get_header_text(action).as_str()),
   }
-  let targets: Vec<&Player> = game.get_players();
-  let selected = interface.user_select_target(&targets);
+  let targets: Vec<CrewMember> = game.get_crew();
+  let selected = interface.user_select_target(&targets)?;
   game.set_current_target(&action, selected.map(|player| player.id));
+  Ok(())
 }
 
 // Selection helpers
 
 pub fn add_log_out_action(actions_list: &mut Vec<Action>) {
-  actions_list.push(Action::GeneralAction(String::from("Déconnection"), run_log_out ));
+  actions_list.push(Action::UserAction(String::from("Déconnection"), run_log_out ));
 }
 
-pub fn run_log_out(game: &mut dyn Game, _interface: &mut Interface) {
-  game.set_current_player_id(None);
+pub fn run_log_out(game: &mut dyn PlayerGame, interface: &mut dyn Interface) -> Result<(), Idle> {
+  game.log_out();
+  let result = game.autosave();
+  report_backup_error(result, interface)
 }
 
 // Elimination result menu
 
-pub fn display_menu_for_eliminated_player (game: &mut dyn Game, interface: &mut Interface, player: PlayerId) {
+pub fn display_menu_for_eliminated_player (game: &mut GameAccess, interface: &mut dyn Interface, player_id: PlayerId) -> Result<(), Idle> {
+  let pause = game.lock().get_config().pause();
   interface.clear_terminal();
-  interface.wait_and_display("Évaluation des résultats en cours", Duration::from_secs(5), Duration::from_millis(700));
+  interface.wait_and_display("Évaluation des résultats en cours", pause, Duration::from_millis(700));
 
-  let player = game.get_player(player);
+  let (name, captain) = {
+    let game = game.lock();
+    (game.get_player(player_id).name.clone(), game.get_captain() == Some(player_id))
+  };
   interface.clear_terminal();
-  interface.play_alarm(format!("Merci de faire venir {} immédiatement!", player.name).as_str());
+  interface.play_alarm(format!("Merci de faire venir {} immédiatement!", name).as_str())?;
   // check code?
-  println!("");
-  println!("Retrouvez moi dans le sas tribord pour une communication urgente (et discrète)");
-  println!("");
-  interface.user_select_from(vec!["Aller dans le sas"].iter());
-  println!("");
-  println!("J'ai le plaisir de vous annoncer que le reste de l'équipage");
-  println!("à décider de vous libérer de vos responsabilités à bord");
-  println!("Merci de sortir du vaisseau par le sas.");
-  println!("");
-  interface.user_select_from(vec!["Mourir"].iter());
+  interface.println("");
+  interface.println("Retrouvez moi dans le sas tribord pour une communication urgente (et discrète)");
+  interface.println("");
+  interface.user_select_from(vec!["Aller dans le sas"].iter())?;
+  interface.println("");
+  interface.println("J'ai le plaisir de vous annoncer que le reste de l'équipage");
+  interface.println("à décider de vous libérer de vos responsabilités à bord");
+  if captain {
+    interface.println("Avant de partir, choisissez qui vous succèdera en tant que capitaine:");
+    let candidates: Vec<CrewMember> = game.lock().get_players().iter().map(|player| player.crew_member()).collect();
+    let successor = interface.user_select_from_with_custom_display(candidates.into_iter(), |member| member.name.clone())?;
+    game.lock().set_captain_successor(Some(successor.id));
+    interface.println("");
+  }
+  interface.println("Merci de sortir du vaisseau par le sas.");
+  interface.println("");
+  interface.user_select_from(vec!["Mourir"].iter())?;
 
   interface.clear_terminal();
   interface.play_death_sound();
+  Ok(())
 }
 
-pub fn display_menu_for_no_eliminated_player (game: &mut dyn Game, interface: &mut Interface, votes: HashMap<PlayerId, usize>) {
+pub fn display_menu_for_no_eliminated_player (game: &mut GameAccess, interface: &mut dyn Interface, votes: HashMap<PlayerId, usize>) -> Result<(), Idle> {
+  let pause = game.lock().get_config().pause();
   interface.clear_terminal();
-  interface.wait_and_display("Évaluation des résultats en cours", Duration::from_secs(5), Duration::from_millis(700));
+  interface.wait_and_display("Évaluation des résultats en cours", pause, Duration::from_millis(700));
 
   let mut max_number_of_votes = 0;
   let mut players_with_max_votes = Vec::new();
@@ -378,21 +354,73 @@ pub fn display_menu_for_no_eliminated_player (game: &mut dyn Game, interface: &m
       players_with_max_votes.push(player);
     }
   }
-  players_with_max_votes.shuffle(&mut thread_rng());
+  players_with_max_votes.sort(); // HashMap iteration order is random, it must not leak in the choice
+  players_with_max_votes.shuffle(game.lock().rng());
 
   if let Some(player) = players_with_max_votes.pop() {    
-    let player = game.get_player(player);
-    println!("");
-    interface.play_alarm(format!("Merci de faire venir {} immédiatement!", player.name).as_str());
+    let name = game.lock().get_player(player).name.clone();
+    interface.println("");
+    interface.play_alarm(format!("Merci de faire venir {} immédiatement!", name).as_str())?;
     // check code?
     interface.clear_terminal();
-    println!("Retrouvez moi dans le sas tribord pour une communication urgente (et discrète)");
-    println!("");
-    interface.user_select_from(vec!["Aller dans le sas"].iter());
-    println!("");
-    println!("Je suis au regret de vous informer que {} membre(s) d'équipage ont conspiré pour vous éliminer", max_number_of_votes);
-    println!("Heureusement pour vous, ils n'étaient pas assez nombreux, vous mourrez un autre jour.");
-    println!("");
-    interface.user_select_from(vec!["Rentrer dans le vaisseau"].iter());
+    interface.println("Retrouvez moi dans le sas tribord pour une communication urgente (et discrète)");
+    interface.println("");
+    interface.user_select_from(vec!["Aller dans le sas"].iter())?;
+    interface.println("");
+    interface.println(format!("Je suis au regret de vous informer que des membres d'équipage ont conspiré pour vous éliminer ({} voix)", max_number_of_votes).as_str());
+    interface.println("Heureusement pour vous, ils n'étaient pas assez nombreux, vous mourrez un autre jour.");
+    interface.println("");
+    interface.user_select_from(vec!["Rentrer dans le vaisseau"].iter())?;
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::SeedableRng;
+
+  use super::*;
+  use crate::game::{GameRng, GameStatus};
+  use crate::interface::scripted::ScriptedInterface;
+  use crate::player::Player;
+  use crate::role::RoleCatalog;
+
+  #[test]
+  fn idle_players_are_logged_out() {
+    let catalog = RoleCatalog::default();
+    let players = ["patient0", "physician", "astronaut"].iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let mut game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), Default::default());
+    let player = game.get_player_ids(&|_| true)[0];
+    let session = game.open_session(player);
+
+    // The player leaves without answering anything, the script being over stands for the timeout
+    let mut interface = ScriptedInterface::new(Vec::new());
+    display_player_status_and_actions(&mut game, &mut interface, &session).unwrap();
+    assert_eq!(game.get_session_player(&session), None);
+    assert!(!interface.last_screen().contains("mutant"));
+    assert!(interface.output().contains("mutant"));
+  }
+
+  #[test]
+  fn wrong_codes_sound_the_alarm() {
+    let catalog = RoleCatalog::default();
+    let players = ["patient0", "physician", "astronaut"].iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let mut game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), Default::default());
+    let mut interface = ScriptedInterface::new(["1234", "", "1234", "", "1234", "", "", "1"].map(String::from).to_vec());
+    for _ in 0..3 {
+      assert_eq!(run_action_log_in(&mut game, &mut interface, Terminal::Local).unwrap(), None);
+    }
+    assert!(interface.screens()[1].contains("Code invalide, appuyez sur ENTREE"));
+    assert!(interface.last_screen().contains("terminal verrouillé pendant 30 secondes"));
+    assert_eq!(interface.sounds_played(), 1);
+
+    // Even the right code waits for the end of the lockout
+    assert_eq!(run_action_log_in(&mut game, &mut interface, Terminal::Local).unwrap(), None);
+    assert!(interface.last_screen().contains("Terminal verrouillé suite à plusieurs codes invalides"));
+    assert_eq!(interface.remaining_inputs(), 1);
   }
 }
@@ -0,0 +1,116 @@
+use crate::game::Game;
+use crate::phases::ROLE_MODULES;
+use crate::player::{Player, PlayerId};
+
+// What happened during the night that has to be announced to the whole crew in the morning
+#[derive(Debug, Default)]
+pub struct NightReport {
+  pub killed: Vec<PlayerId>,
+}
+
+// A step of the night, usually the resolution of the actions of one role
+pub trait NightPhase {
+  fn name(&self) -> &'static str;
+  // Names of the phases that have to be resolved before this one, ignored if they are not in the pipeline
+  fn after(&self) -> Vec<&'static str> { Vec::new() }
+  // The living players who take part in the phase, they are handed to run
+  fn acts(&self, _player: &Player) -> bool { false }
+  fn run(&self, game: &mut dyn Game, actors: &[PlayerId], report: &mut NightReport);
+}
+
+pub struct NightPipeline {
+  phases: Vec<Box<dyn NightPhase>>, // in registration order
+}
+
+impl NightPipeline {
+  pub fn new () -> NightPipeline {
+    NightPipeline { phases: Vec::new() }
+  }
+
+  pub fn register (&mut self, phase: Box<dyn NightPhase>) {
+    self.phases.push(phase);
+  }
+
+  // Phases are run in registration order, unless they have to wait for another one
+  pub fn ordered (&self) -> Result<Vec<&dyn NightPhase>, String> {
+    let mut remaining: Vec<&dyn NightPhase> = self.phases.iter().map(|phase| phase.as_ref()).collect();
+    let mut ordered: Vec<&dyn NightPhase> = Vec::new();
+    while !remaining.is_empty() {
+      let ready = remaining.iter().position(|phase| phase.after().iter().all(|dependency| {
+        !remaining.iter().any(|other| other.name() == *dependency)
+      }));
+      match ready {
+        Some(idx) => ordered.push(remaining.remove(idx)),
+        None => {
+          let names = remaining.iter().map(|phase| phase.name()).collect::<Vec<&str>>().join(", ");
+          return Err(format!("Cyclic dependencies between the night phases: [{names}]"));
+        },
+      }
+    }
+    Ok(ordered)
+  }
+
+  pub fn run (&self, game: &mut dyn Game) -> NightReport {
+    let mut report = NightReport::default();
+    for phase in self.ordered().unwrap() { // the pipeline is built by the code, a cycle is a bug
+      let actors = game.get_player_ids(&|player| phase.acts(player)); // after the previous phases, which may have infected or killed some
+      phase.run(game, &actors, &mut report);
+    }
+    report
+  }
+}
+
+pub fn default_pipeline () -> NightPipeline {
+  let mut pipeline = NightPipeline::new();
+  for module in ROLE_MODULES {
+    (module.register)(&mut pipeline);
+  }
+  pipeline
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::phases::{captain, geneticist, it_engineer, mutants, physicians, psychologist, spy, traitor};
+
+  struct TestPhase {
+    name: &'static str,
+    after: Vec<&'static str>,
+  }
+
+  impl NightPhase for TestPhase {
+    fn name(&self) -> &'static str { self.name }
+    fn after(&self) -> Vec<&'static str> { self.after.clone() }
+    fn run(&self, _game: &mut dyn Game, _actors: &[PlayerId], _report: &mut NightReport) {}
+  }
+
+  fn pipeline(phases: Vec<(&'static str, Vec<&'static str>)>) -> NightPipeline {
+    let mut pipeline = NightPipeline::new();
+    for (name, after) in phases {
+      pipeline.register(Box::new(TestPhase { name, after }));
+    }
+    pipeline
+  }
+
+  fn names(pipeline: &NightPipeline) -> Vec<&'static str> {
+    pipeline.ordered().unwrap().iter().map(|phase| phase.name()).collect()
+  }
+
+  #[test]
+  fn phases_wait_for_their_dependencies() {
+    let pipeline = pipeline(vec![("spy", vec!["mutants", "unknown"]), ("it", vec![]), ("mutants", vec![])]);
+    assert_eq!(names(&pipeline), vec!["it", "mutants", "spy"]);
+  }
+
+  #[test]
+  fn default_pipeline_keeps_the_historical_order() {
+    assert_eq!(names(&default_pipeline()),
+      vec![mutants::NAME, traitor::NAME, physicians::NAME, it_engineer::NAME, psychologist::NAME, geneticist::NAME, spy::NAME, captain::NAME]);
+  }
+
+  #[test]
+  fn cycles_are_reported() {
+    let pipeline = pipeline(vec![("a", vec!["b"]), ("b", vec!["a"])]);
+    assert!(pipeline.ordered().is_err());
+  }
+}
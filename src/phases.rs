@@ -1,436 +1,308 @@
 use rand::seq::SliceRandom;
 
 use crate::{
-  game::Game,
-  role::Role,
+  game::{GameAccess, PlayerGame},
   message::Message,
-  action::ActionType,
-  helper::{compute_votes_results, compute_votes_winner},
-  player::{Player, PlayerId}, interface::{Interface, colors::Color}, menu::{display_menu_for_eliminated_player, display_menu_for_no_eliminated_player}};
-
-use std::time::Duration;
-use rand::thread_rng;
-
-pub fn run_elimination_phase(interface: &mut Interface, game: &mut dyn Game) -> Option<PlayerId> {
-  let current_date = game.get_date(); // do better
-
-  // Check votes to eliminate a player
-  let elimination_results = compute_votes_results(
-    game.get_players().iter(),
-    ActionType::Eliminate);
-  let mut number_of_votes: Vec<usize> = elimination_results.values().map(|count|*count).collect();
-
-  let white_votes: usize = game.get_players().len() - number_of_votes.iter().sum::<usize>();
-  number_of_votes.push(white_votes);
-  let max_number_of_votes = number_of_votes.iter().max().unwrap(); // cannot be empty
-
-  let mut players_with_max_number: Vec<Option<PlayerId>> = elimination_results.iter()
-    .filter_map(|(player, votes)| {
-      if votes == max_number_of_votes { Some(player) } else { None }
-    }).map(|player| Some(*player)) // So we can add None for the whites
-    .collect();
-  if *max_number_of_votes == white_votes {
-    players_with_max_number.push(None);
-  }
+  action::{Action, ActionType},
+  night::NightPipeline,
+  helper::{compute_votes_results, compute_weighted_votes_results, select_votes_winner},
+  event::{EventCause, EventKind},
+  login::{authenticate, LogIn, Terminal},
+  player::{Player, PlayerId}, interface::{Idle, Interface}, menu::{display_menu_for_eliminated_player, display_menu_for_no_eliminated_player}};
+
+use self::captain::run_captain_succession;
+
+use std::time::{Duration, Instant};
+
+// One module per role: its night phase and the actions of its menu
+pub mod mutants;
+pub mod traitor;
+pub mod physicians;
+pub mod it_engineer;
+pub mod psychologist;
+pub mod geneticist;
+pub mod spy;
+pub mod captain;
+pub mod hacker;
+
+// What a role brings to the game, on top of its definition in the catalog
+pub struct RoleModule {
+  pub register: fn(&mut NightPipeline),
+  pub add_actions: fn(&mut dyn PlayerGame, &mut Vec<Action>), // for the living players, nothing if the player does not have the role
+}
 
-  // Notify everyone of how many crew members attempted to kill you, if any
-  for (target, votes) in elimination_results.iter() {
-    game.send_message(*target, 
-      String::from("Ordinateur Central"),
-      format!("Cette nuit, {votes} membres d'équipages ont tenté de vous éliminer."));
-  }
+// The night and the menus of the players are built from this list, a new role only has to be added here
+pub const ROLE_MODULES: [RoleModule; 9] = [
+  mutants::MODULE,
+  traitor::MODULE,
+  physicians::MODULE,
+  it_engineer::MODULE,
+  psychologist::MODULE,
+  geneticist::MODULE,
+  spy::MODULE,
+  captain::MODULE,
+  hacker::MODULE,
+];
+
+pub fn run_elimination_phase(interface: &mut dyn Interface, game: &mut GameAccess) -> Result<Option<PlayerId>, Idle> {
+  let (elimination_results, players_with_max_number) = {
+    let mut game = game.lock();
+
+    // Check votes to eliminate a player, the captain's vote can count for more than one
+    let captain = game.get_captain();
+    let captain_vote_weight = game.get_rules().captain_vote_weight;
+    let vote_weight = |player: &Player| if Some(player.id) == captain { captain_vote_weight } else { 1 };
+    let elimination_results = compute_weighted_votes_results(
+      game.get_players().iter(),
+      ActionType::Eliminate,
+      vote_weight);
+    let mut number_of_votes: Vec<usize> = elimination_results.values().map(|count|*count).collect();
+
+    let total_votes: usize = game.get_players().iter().map(|player| vote_weight(player)).sum();
+    let white_votes: usize = total_votes - number_of_votes.iter().sum::<usize>();
+    number_of_votes.push(white_votes);
+    let max_number_of_votes = number_of_votes.iter().max().unwrap(); // cannot be empty
+
+    let mut players_with_max_number: Vec<Option<PlayerId>> = elimination_results.iter()
+      .filter_map(|(player, votes)| {
+        if votes == max_number_of_votes { Some(player) } else { None }
+      }).map(|player| Some(*player)) // So we can add None for the whites
+      .collect();
+    players_with_max_number.sort(); // So the options are always proposed in the same order
+    if *max_number_of_votes == white_votes {
+      players_with_max_number.push(None);
+    }
+
+    // Notify everyone of how many crew members attempted to kill you, if any
+    let voters_results = compute_votes_results(
+      game.get_players().iter(),
+      ActionType::Eliminate);
+    for (target, votes) in voters_results.iter() {
+      game.send_message(*target, 
+        String::from("Ordinateur Central"),
+        format!("Cette nuit, {votes} membres d'équipages ont tenté de vous éliminer."));
+    }
+    (elimination_results, players_with_max_number)
+  };
 
-  let dead_crew_member = select_who_dies(interface, game, players_with_max_number);
+  let dead_crew_member = select_who_dies(interface, game, players_with_max_number)?;
   match dead_crew_member {
     Some(player_id) => {
-      let player = game.get_mut_player(player_id);
-      player.die(current_date, String::from("Aspiré·e accidentellement par le sas tribord"));
-
-      let mut content = String::new();
-      content.push_str(format!("Conformément à la volonté populaire, {} à été retiré du service actif.", player.name).as_str());
-
-      if player.infected {
-        let role = if player.role == Role::Patient0 { &Role::Astronaut } else { &player.role }; // Patient0's is not revealed on death
-        content.push_str(format!(" L'autopsie a révélé que {} était en réalité un·e {} mutant·e!", player.name, role).as_str());
-      } else {
-        content.push_str(format!(" {} était un·e honnête {} dévoué à la mission.", player.name, player.role).as_str());
-      }
-      if player.host {
-        content.push_str(" L'analyse génomique a de plus révélé qu'iel était particulierement succeptible à la mutation. Aujourd'hui est donc un grand jour!");
-      } else if player.resilient {
-        content.push_str(" L'analyse génomique a de plus révélé qu'iel était résistant à la mutation. Une perte véritablement tragique...");
+      {
+        let mut game = game.lock();
+        let current_date = game.get_date(); // do better
+        game.record_event(EventCause::CrewVote, EventKind::Eliminated { target: player_id });
+        let player = game.get_mut_player(player_id);
+        player.die(current_date, String::from("Aspiré·e accidentellement par le sas tribord"));
+
+        let mut content = String::new();
+        content.push_str(format!("Conformément à la volonté populaire, {} à été retiré du service actif.", player.name).as_str());
+
+        if player.infected {
+          content.push_str(format!(" L'autopsie a révélé que {} était en réalité un·e {} mutant·e!", player.name, player.role.revealed_name()).as_str());
+        } else {
+          content.push_str(format!(" {} était un·e honnête {} dévoué à la mission.", player.name, player.role.revealed_name()).as_str());
+        }
+        if player.host {
+          content.push_str(" L'analyse génomique a de plus révélé qu'iel était particulierement succeptible à la mutation. Aujourd'hui est donc un grand jour!");
+        } else if player.resilient {
+          content.push_str(" L'analyse génomique a de plus révélé qu'iel était résistant à la mutation. Une perte véritablement tragique...");
+        }
+        content.push_str(" Vous pouvez lui dire adieu par le hublot tribord :-)");
+        game.broadcast(Message {
+          date: current_date,
+          source: String::from("Ordinateur Central"),
+          content,
+        });
       }
-      content.push_str(" Vous pouvez lui dire adieu par le hublot tribord :-)");
-      game.broadcast(Message {
-        date: current_date,
-        source: String::from("Ordinateur Central"),
-        content,
-      });
-
-      display_menu_for_eliminated_player(game, interface, player_id);
-      return Some(player_id);
+
+      display_menu_for_eliminated_player(game, interface, player_id)?;
+      run_captain_succession(&mut *game.lock());
+      return Ok(Some(player_id));
     },
     None => {
-      game.broadcast(Message {
-        date: current_date,
-        source: String::from("Ordinateur Central"),
-        content: String::from("Tout le monde a très bien dormi cette nuit."),
-      });      
-      display_menu_for_no_eliminated_player(game, interface, elimination_results);
-      return None;
+      {
+        let mut game = game.lock();
+        let current_date = game.get_date();
+        game.broadcast(Message {
+          date: current_date,
+          source: String::from("Ordinateur Central"),
+          content: String::from("Tout le monde a très bien dormi cette nuit."),
+        });
+      }
+      display_menu_for_no_eliminated_player(game, interface, elimination_results)?;
+      return Ok(None);
     }
   }
 }
 
-fn select_who_dies (interface: &mut Interface, game: &dyn Game, options: Vec<Option<PlayerId>>) -> Option<PlayerId> {
+fn select_who_dies (interface: &mut dyn Interface, game: &mut GameAccess, options: Vec<Option<PlayerId>>) -> Result<Option<PlayerId>, Idle> {
   if options.len() == 0 {
-    return None;
+    return Ok(None);
   }
   if options.len() == 1 {
-    return options[0];
+    return Ok(options[0]);
   }
-  let displayer = |player: &&Option<PlayerId>| match player {
-    Some(player) => game.get_player(*player).name.clone(),
-    None => String::from("Aucun"),
-  };
 
+  let pause = game.lock().get_config().pause();
   interface.clear_terminal();
-  interface.wait_and_display("Évaluation des résultats en cours", Duration::from_secs(5), Duration::from_millis(700));
-
-  // TODO: check the leader's code to validate
-  interface.play_warning("Merci de faire venir le représentant du personnel!");
-  println!("");
-  println!("Un des membres d'équipage suivant doit être éliminé:");
-  return *interface.user_select_from_with_custom_display(options.iter(), displayer);
-}
+  interface.wait_and_display("Évaluation des résultats en cours", pause, Duration::from_millis(700));
 
-pub fn run_mutants_phase(game: &mut dyn Game) -> Option<PlayerId> {
-  let current_date = game.get_date(); // do better
-
-  // Notify the mutants of who the other mutants are
-  // The newly converted mutant will only get that information at the next night
-  let mutants_names = game.get_players().iter()
-      .filter_map(|player| if player.infected { Some(player.name.clone())} else { None })
-      .collect::<Vec<String>>().join(" ");
-  game.limited_broadcast(Message {
-      date: current_date,
-      source: String::from("Overmind"),
-      content: String::from(format!("Lors du dernier crépuscule, les mutant·e·s étaient: [{mutants_names}]")),
-  }, & |player: &&mut &mut Player| player.infected);
-
-  for player in game.get_mut_players().iter_mut().filter(|player| player.infected) {
-    player.spy_info.woke_up = true;
-  }
-
-  // Mutate or kill one player
-  let kill = game.get_players().iter()
-    .filter(|player| player.infected)
-    .fold(0, |mut count, player| {
-      if player.mutant_kill {
-        count += 1;
-      } else {
-        count -= 1;
-      }
-      count
-    }) > 0;
-  let mutate_results = compute_votes_winner(
-      game.get_players().iter().filter(|player| player.infected),
-      ActionType::Infect);
-  if let Some((target_id, _)) = mutate_results {
-    let target_name = game.get_player(target_id).name.clone();
-    if kill {
-      game.get_mut_player(target_id).die(current_date, String::from("Carbonisé·e sous la douche"));
-      game.send_message(target_id,
-        String::from("Overmind"),
-        String::from("Nous avons décidé que vous n'étiez pas digne de rejoindre nos rangs, mais tout de même assez pour nous servir de repas"));
-      game.limited_broadcast(Message { // Notify mutants of who was killed
-          date: current_date,
-          source: String::from("Overmind"),
-          content: String::from(format!("Les arrangements ont été faits, {target_name} ne devrait pas se réveiller")),
-      }, & |player: &&mut &mut Player| player.infected);
-    } else {
-      game.limited_broadcast(Message { // Notify mutants of who was infected
-          date: current_date,
-          source: String::from("Overmind"),
-          content: String::from(format!("Nos spores ont été envoyées dans la cabine de {target_name}, iel devrait bientôt nous rejoindre...")),
-      }, & |player: &&mut &mut Player| player.infected);
-      if game.get_player(target_id).infected == false {
-        if game.get_player(target_id).resilient == false {
-          game.get_mut_player(target_id).infected = true;
-          game.get_mut_player(target_id).spy_info.was_infected = true;
-          game.send_message(target_id, // Notify the new mutant that he was infected
-            String::from("Overmind"),
-            format!("Bienvenue {}, nous sommes heureuxe de vous compter parmis nous.", target_name));
-        } else {
-          game.send_message(target_id,
-            String::from("Outil d'auto diagnostique"),
-            format!("Bonne nouvelle {}, les mutants ont essayé de vous infecter, mais votre genome vous a protégé!", target_name));
-        }
-      }
+  let captain = game.lock().get_captain();
+  let captain = match captain {
+    Some(captain) => captain,
+    None => {
+      interface.user_validate("Aucun·e capitaine n'est là pour trancher, le sort va décider")?;
+      return Ok(*options.choose(game.lock().rng()).unwrap()); // cannot be empty
     }
+  };
+  let captain_name = game.lock().get_player(captain).name.clone();
+  interface.play_warning(format!("Merci de faire venir le·a capitaine {captain_name}!").as_str())?;
+  if !authenticate_player(interface, game, captain)? {
+    interface.user_validate("Le·a capitaine n'a pas pu être identifié·e, le sort va décider")?;
+    return Ok(*options.choose(game.lock().rng()).unwrap()); // cannot be empty
   }
 
-  // Paralyze one player
-  let paralyze_result = compute_votes_winner(
-      game.get_players().iter().filter(|player| player.infected),
-      ActionType::Paralyze);
-  if let Some((player_id, _)) = paralyze_result {
-      let paralized_name = &game.get_player(player_id).name;
-      game.limited_broadcast(Message { // Notify mutants of who was paralysed
-          date: current_date,
-          source: String::from("Overmind"),
-          content: String::from(format!("Félicitations, cette nuit vous êtes parvenus à paralyser: {paralized_name}")),
-      }, & |player: &&mut &mut Player| player.infected);
-
-      let paralyzed_player = game.get_mut_player(player_id);
-      paralyzed_player.paralyzed = true;
-      paralyzed_player.spy_info.was_paralyzed = true;
-      paralyzed_player.messages.push(Message {
-          date: current_date,
-          source: String::from("Outil d'auto diagnostique"),
-          content: String::from("Vous avez été paralysé pendant la nuit, vous n'avez donc pas pu faire d'action spéciale"),
-      })
-  }
-
-  if kill {
-    if let Some((target_id, _)) = mutate_results {
-      return Some(target_id);
-    }
-  }
-  return None;
+  let options: Vec<(Option<PlayerId>, String)> = {
+    let game = game.lock();
+    options.into_iter().map(|player| match player {
+      Some(player) => (Some(player), game.get_player(player).name.clone()),
+      None => (None, String::from("Aucun")),
+    }).collect()
+  };
+  interface.println("");
+  interface.println("Un des membres d'équipage suivant doit être éliminé:");
+  let (dead_crew_member, _) = interface.user_select_from_with_custom_display(options.into_iter(), |(_, name)| name.clone())?;
+  return Ok(dead_crew_member);
 }
 
-// Warning: As of now, this doesn't work great if we have more than 2 physicians in game
-// for example, if someone has an auto_kill_physician, it will bypass all other votes
-// and if several have an auto_kill_physician, only the last one will be considered
-pub fn run_physicians_phase(game: &mut dyn Game) -> Option<PlayerId> {
-  // Cure one player
-  let alive_players = game.get_players();
-  let physicians = alive_players
-    .iter()
-    .filter(|player| player.role == Role::Physician);
-
-  let mut killed_player = None;
-  let mut cured_players = Vec::new();
-  let mut active_physicians = Vec::new();
-  let mut active_physician_names = Vec::new();
-  let mut disabled_physicians = Vec::new();
-
-  let mut kill = 0;
-
-  for physician in physicians {
-    if physician.infected || physician.paralyzed {
-      disabled_physicians.push(physician.id);
-    } else {
-      active_physicians.push(physician.id);
-      if physician.physician_kill {
-        kill += 1;
-      } else {
-        kill -= 1;
-      }
-    }
-  }
-  let mut kill = kill > 0; // majority
-
-  for disabled_physician in disabled_physicians.iter() {
-    let infected = game.get_player(*disabled_physician).infected;
-    game.send_message(*disabled_physician,
-      String::from("Outil d'auto diagnostique"),
-      if infected {
-        String::from("Vous êtes infecté·e, vous n'avez donc pas participé aux soins")
-      } else {
-        String::from("Vous avez été·e paralysé·e pendant la nuit, vous n'avez donc pu participer aux soins")
-      });
-  }
-
-  for active_physician in active_physicians.iter() {
-    let active_physician = game.get_mut_player(*active_physician);
-    active_physician.spy_info.woke_up = true;
-    active_physician_names.push(active_physician.name.clone());
-    if active_physician.auto_cure_physician {
-      if let Some(target) = disabled_physicians.pop() {
-        cured_players.push(target);
-        continue; // not great, but avoids is_empty() + pop().unwrap()
-      }
-    } else if active_physician.auto_kill_physician {
-      if let Some(target) = disabled_physicians.pop() {
-        killed_player = Some(target);
-        kill = true;
-        continue; // not great, but avoids is_empty() + pop().unwrap()
-      }
-    }
-    if let Some(target) = active_physician.actions.get(&ActionType::Cure) {
-      cured_players.push(*target);
-    }
-  }
-
-  let mut cured_players_names = Vec::new();
-  if !kill {
-    // Cure the players, and warn them
-    for cured_player in cured_players {
-      cured_players_names.push(game.get_player(cured_player).name.clone());
-      if game.get_player(cured_player).role == Role::Patient0 {
-        game.send_message(cured_player,
-          String::from("Équipe médicale"),
-          String::from("Vous avez soigné par un traitement par irradiation intense cette nuit, mais la mutation est trop avancée chez vous, cela a échoué"));
-      } else if !game.get_player(cured_player).infected {
-        game.send_message(cured_player,
-          String::from("Équipe médicale"),
-          String::from("Vous avez été soigné par un traitement anti-mutation cette nuit, bien qu'il n'y ait eu aucune trace de mutations dans votre corps"));
-      } else if game.get_player(cured_player).host {
-        game.send_message(cured_player,
-          String::from("Overmind"),
-          String::from("L'équipe médicale vous a administré un traitement anti-mutation cette nuit, mais votre génome semble résistant au traitement. Félicitations ;-)"));
-      } else { // infected and not host
-        game.get_mut_player(cured_player).infected = false;
-        game.get_mut_player(cured_player).spy_info.was_cured = true;
-        game.send_message(cured_player,
-          String::from("Équipe médicale"),
-          String::from("Vous avez été soigné par un traitement par irradiation intense cette nuit, qui vous à débarrassé de toute trace de mutation"));
-      }
+// The player has until the terminal locks to enter their code, like on the home screen
+fn authenticate_player (interface: &mut dyn Interface, game: &mut GameAccess, player: PlayerId) -> Result<bool, Idle> {
+  let name = game.lock().get_player(player).name.clone();
+  loop {
+    let locked = game.lock().get_login_guard(Terminal::Local).locked_for(Instant::now());
+    if let Some(remaining) = locked {
+      interface.println(format!("Terminal verrouillé suite à plusieurs codes invalides pendant encore {} secondes", remaining.as_secs() + 1).as_str());
+      return Ok(false);
     }
-  } else {
-    if killed_player.is_none() {
-      cured_players.shuffle(&mut thread_rng());
-      killed_player = cured_players.pop();
+    let key = interface.user_non_empty_input(format!("{name}, entrez votre code d'identification:").as_str())?;
+    let result = authenticate(&mut *game.lock(), Terminal::Local, player, key);
+    match result {
+      LogIn::Success(_) => return Ok(true),
+      LogIn::Invalid => interface.println("Code invalide"),
+      LogIn::Locked(lockout) => {
+        interface.println(format!("Code invalide. Tentative d'intrusion détectée: terminal verrouillé pendant {} secondes.", lockout.as_secs()).as_str());
+        return Ok(false);
+      },
     }
   }
+}
 
-  if let Some(target) = killed_player {
+pub fn run_captain_election_phase(interface: &mut dyn Interface, game: &mut GameAccess) -> Result<(), Idle> {
+  let content = {
+    let mut game = game.lock();
     let current_date = game.get_date();
-    game.get_mut_player(target).die(current_date, String::from("Carbonisé·e sous la douche"));
-    game.send_message(target,
-      String::from("Équipe médicale"),
-      String::from("Le CHSCT à décidé que votre hygiène corporelle n'était pas compatible avec la survie du vaisseau, désolé."));
-  }
-
-  // Send messages to the active medical team about who was cured
-  let active_physician_names = active_physician_names.join(" ");
-  let cured_players_names = cured_players_names.join(" ");
-  for active_physician in active_physicians {
-    if let Some(killed) = killed_player {
-      let killed_player_name = game.get_player(killed).name.clone();
-      game.send_message(active_physician, 
-        String::from("Équipe médicale"),
-        String::from(format!("L'équipe médicale opérationelle de la nuit précédente ({}) est parvenue à tuer: [{}]", active_physician_names, killed_player_name)));
-    } else {
-      game.send_message(active_physician, 
-        String::from("Équipe médicale"),
-        String::from(format!("L'équipe médicale opérationelle de la nuit précédente ({}) est parvenue à soigner: [{}]", active_physician_names, cured_players_names)));
+    let election_results = compute_votes_results(
+      game.get_players().iter(),
+      ActionType::ElectCaptain);
+    let captain = match select_votes_winner(election_results, game.rng()) {
+      Some((captain, _)) => captain,
+      None => { // Nobody voted, the captain is drawn by lot
+        let candidates = game.get_player_ids(&|_| true);
+        match candidates.choose(game.rng()) {
+          Some(captain) => *captain,
+          None => return Ok(()),
+        }
+      },
+    };
+    game.set_captain(Some(captain));
+    game.record_event(EventCause::CrewVote, EventKind::CaptainAppointed { captain });
+
+    let captain_name = game.get_player(captain).name.clone();
+    let mut content = format!("{captain_name} a été élu·e capitaine du vaisseau, et tranchera les égalités lors des éliminations.");
+    if game.get_rules().captain_vote_weight > 1 {
+      content.push_str(format!(" Son vote compte pour {} voix.", game.get_rules().captain_vote_weight).as_str());
     }
-  }
+    game.broadcast(Message {
+      date: current_date,
+      source: String::from("Ordinateur Central"),
+      content: content.clone(),
+    });
+    content
+  };
 
-  return killed_player;
+  interface.clear_terminal();
+  interface.user_validate(content.as_str())
 }
 
-pub fn run_it_phase(game: &mut dyn Game) {
-  // Tell the IT guy how many mutants are in play
-  let infected_players = game.get_players().iter().filter(|player| player.infected).count();
-  let it_guys_ids = game.get_players().iter()
-    .filter_map(|player| if player.role == Role::ITEngineer { Some(player.id) } else { None }).collect::<Vec<PlayerId>>();
-  for player_id in it_guys_ids {
-    if !game.get_player(player_id).paralyzed {
-      game.get_mut_player(player_id).spy_info.woke_up = true;
-      game.send_message(player_id,
-        String::from("Système de diagnostique"),
-        format!("L'analyse quantique de cette nuit a révélé la présence de {infected_players} membres d'équipage infectés à bord."),
-      )
-    } // See if we want to display something in else
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use rand::SeedableRng;
+
+  use super::*;
+  use crate::game::{Game, GameRng, GameStatus};
+  use crate::interface::scripted::ScriptedInterface;
+  use crate::role::RoleCatalog;
+
+  fn new_game (roles: &[&str]) -> GameStatus {
+    let catalog = RoleCatalog::default();
+    let players = roles.iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), Default::default())
   }
-}
 
-pub fn run_psychologist_phase(game: &mut dyn Game) {
-  let psychologists_ids = game.get_player_ids(&|player| player.role == Role::Psychologist);
-  for psychologists_id in psychologists_ids {
-    if !game.get_player(psychologists_id).paralyzed {
-      game.get_mut_player(psychologists_id).spy_info.woke_up = true;
-      if let Some(analyzed_id) = game.get_player(psychologists_id).get_target(&ActionType::Psychoanalyze).copied() {
-        game.get_mut_player(analyzed_id).spy_info.was_psychoanalyzed = true;
-        let name = game.get_player(analyzed_id).name.clone();
-        if game.get_player(analyzed_id).infected {
-          game.send_message(psychologists_id,
-            String::from("Freud GPT"),
-            format!("D'après l'analyse, il semblerait que le comportement déviant de {} ne découle pas d'un trauma d'enfance, mais d'un changement récent. C'est un·e mutant·e!", name));
-        } else {
-          game.send_message(psychologists_id,
-            String::from("Freud GPT"),
-            format!("D'après l'analyse, il semblerait que le comportement déviant de {} découle simplement d'un rapport difficile à la mère, et pas d'une mutation génétique", name));
-        }
-      }
-    } // See if we want to display something in else
+  #[test]
+  fn the_captain_settles_a_tie_with_their_code() {
+    let mut game = new_game(&["patient0", "physician", "astronaut", "astronaut"]);
+    let players = game.get_player_ids(&|_| true);
+    game.set_captain(Some(players[3]));
+    let mut interface = ScriptedInterface::new(["", "3", "2"].map(String::from).to_vec());
+    let dead = select_who_dies(&mut interface, &mut GameAccess::Owned(&mut game), vec![Some(players[0]), Some(players[1])]).unwrap();
+    assert_eq!(dead, Some(players[1]));
+    assert_eq!(interface.remaining_inputs(), 0);
   }
-}
 
-pub fn run_geneticist_phase(game: &mut dyn Game) {
-  for geneticist_id in game.get_player_ids(&|player| player.role == Role::Geneticist) {
-    let geneticist = game.get_player(geneticist_id);
-    if geneticist.paralyzed {
-      game.send_message(geneticist_id,
-        String::from("Outil d'auto diagnostique"),
-        String::from("Vous avez été paralysé·e pendant la nuit, vous n'avez donc pu étudier le genome de vos camarades"));
-    } else {
-      if let Some(target) = geneticist.get_target(&ActionType::Genomyze).copied() {
-        game.get_mut_player(geneticist_id).spy_info.woke_up = true;
-        let target_name = game.get_player(target).name.clone();
-        let host = game.get_player(target).host;
-        let resilient = game.get_player(target).resilient;
-        if host {
-          game.send_message(geneticist_id,
-            String::from("GenoTech v0.17"),
-            format!("Votre analyse du génome de {target_name} révèle qu'il est particulièrement sensible à l'infection. {}",
-              Color::FgRed.color("S'il venait à muter, il ne pourrait être soigné")));
-        } else if resilient {
-          game.send_message(geneticist_id,
-            String::from("GenoTech v0.17"),
-            format!("Votre analyse du génome de {target_name} révèle qu'il est résistant à l'infection. {}",
-              Color::FgGreen.color("Il ne deviendra jamais un mutant")));
-        } else {
-          game.send_message(geneticist_id,
-            String::from("GenoTech v0.17"),
-            format!("Votre analyse du génome de {target_name} révèle qu'il est d'une banalité affligeante. Réponse standard à la mutation"));
-        }
-      }
-    }
+  #[test]
+  fn wrong_captain_codes_leave_the_tie_to_the_lot() {
+    let mut game = new_game(&["patient0", "physician", "astronaut", "astronaut"]);
+    let players = game.get_player_ids(&|_| true);
+    game.set_captain(Some(players[3]));
+
+    // The code of another crew member is not enough, and the terminal locks as on the home screen
+    let mut interface = ScriptedInterface::new(["", "1234", "1", "2", ""].map(String::from).to_vec());
+    let options = vec![Some(players[0]), Some(players[1])];
+    let dead = select_who_dies(&mut interface, &mut GameAccess::Owned(&mut game), options.clone()).unwrap();
+    assert!(options.contains(&dead));
+    assert!(interface.last_screen().contains("terminal verrouillé pendant 30 secondes"));
+    assert!(interface.last_screen().contains("le sort va décider"));
+    assert_eq!(interface.remaining_inputs(), 0);
+    assert!(game.get_events().iter().any(|event| matches!(event.kind, EventKind::TerminalLocked { .. })));
+
+    // Even the right code waits for the end of the lockout
+    let mut interface = ScriptedInterface::new(["", ""].map(String::from).to_vec());
+    select_who_dies(&mut interface, &mut GameAccess::Owned(&mut game), options).unwrap();
+    assert!(interface.last_screen().contains("Terminal verrouillé suite à plusieurs codes invalides"));
+    assert_eq!(interface.remaining_inputs(), 0);
   }
-}
 
-pub fn run_spy_phase(game: &mut dyn Game) {
-  for spy_id in game.get_player_ids(&|player| player.role == Role::Spy) {
-    let spy = game.get_player(spy_id);
-    if spy.paralyzed {
-      game.send_message(spy_id,
-        String::from("Outil d'auto diagnostique"),
-        String::from("Vous avez été paralysé·e pendant la nuit, vous n'avez donc pu espioner vos camarades"));
-    } else {
-      if let Some(target) = spy.get_target(&ActionType::Spy).copied() {
-        let target_name = game.get_player(target).name.clone();
-        let spy_info = game.get_player(target).spy_info.clone();
-        if spy_info.woke_up {
-          game.send_message(spy_id,
-            String::from("Stalker IV"),
-            format!("Durant votre surveillance, vous avez vu {target_name} se reveiller et sortir de son dortoir"));
-        }
-        if spy_info.was_infected {
-          game.send_message(spy_id,
-            String::from("Stalker IV"),
-            format!("Durant votre surveillance, vous avez vu {target_name} se transformer en mutant·e"));
-        }
-        if spy_info.was_paralyzed {
-          game.send_message(spy_id,
-            String::from("Stalker IV"),
-            format!("Durant votre surveillance, vous avez vu {target_name} être paralysé·e"));
-        }
-        if spy_info.was_cured {
-          game.send_message(spy_id,
-            String::from("Stalker IV"),
-            format!("Durant votre surveillance, vous avez vu {target_name} guérir de sa mutation"));
-        }
-        if spy_info.was_psychoanalyzed {
-          game.send_message(spy_id,
-            String::from("Stalker IV"),
-            format!("Durant votre surveillance, vous avez vu {target_name} être analysé·e par le psychologue"));
-        }
-      }
+  #[test]
+  fn the_vote_of_the_captain_can_count_more() {
+    let mut game = new_game(&["patient0", "physician", "astronaut", "astronaut"]);
+    let players = game.get_player_ids(&|_| true);
+    for (voter, target) in [(0, 1), (1, 2), (2, 1), (3, 2)] {
+      game.get_mut_player(players[voter]).set_target(&ActionType::Eliminate, Some(players[target]));
     }
+    let weight = |player: &Player| if player.id == players[3] { 3 } else { 1 };
+    let results = compute_weighted_votes_results(game.get_players().iter(), ActionType::Eliminate, weight);
+    assert_eq!(results, HashMap::from([(players[1], 2), (players[2], 4)]));
+    assert_eq!(compute_votes_results(game.get_players().iter(), ActionType::Eliminate), HashMap::from([(players[1], 2), (players[2], 2)]));
+
+    // Only the living players vote
+    game.get_mut_player(players[3]).die(1, String::from("Aspiré·e accidentellement par le sas tribord"));
+    let results = compute_weighted_votes_results(game.get_players().iter(), ActionType::Eliminate, weight);
+    assert_eq!(results, HashMap::from([(players[1], 2), (players[2], 1)]));
   }
 }
@@ -0,0 +1,132 @@
+use crate::{
+  game::{Game, PlayerGame},
+  message::Message,
+  action::Action,
+  event::{EventCause, EventKind},
+  interface::Interface,
+  night::{NightPhase, NightPipeline, NightReport},
+  player::{CrewMember, PlayerId}};
+use super::{mutants, physicians, RoleModule};
+
+pub const NAME: &str = "captain_succession";
+
+struct CaptainSuccessionPhase;
+
+impl NightPhase for CaptainSuccessionPhase {
+  fn name(&self) -> &'static str {
+    NAME
+  }
+
+  fn after(&self) -> Vec<&'static str> {
+    vec![mutants::NAME, physicians::NAME]
+  }
+
+  fn run(&self, game: &mut dyn Game, _actors: &[PlayerId], _report: &mut NightReport) {
+    run_captain_succession(game);
+  }
+}
+
+pub const MODULE: RoleModule = RoleModule { register, add_actions };
+
+pub fn register(pipeline: &mut NightPipeline) {
+  pipeline.register(Box::new(CaptainSuccessionPhase));
+}
+
+pub fn add_actions(game: &mut dyn PlayerGame, actions_list: &mut Vec<Action>) {
+  if game.get_captain() != Some(game.get_current_player().id) {
+    return;
+  }
+  actions_list.push(Action::UserAction( // Action to choose who becomes captain if you die
+    match game.get_captain_successor() {
+      Some(successor) => format!("Désigner votre successeur·e [{}]", game.get_crew_member(successor).name),
+      None => String::from("Désigner votre successeur·e"),
+    },
+    |game: &mut dyn PlayerGame, interface: &mut dyn Interface| {
+      interface.clear_terminal();
+      interface.println("Qui doit devenir capitaine si vous disparaissez?");
+      let current_player_id = game.get_current_player().id;
+      let candidates: Vec<CrewMember> = game.get_crew().into_iter().filter(|member| member.id != current_player_id).collect();
+      let selected = interface.user_select_target(&candidates)?.map(|member| member.id);
+      game.set_captain_successor(selected);
+      Ok(())
+    }
+  ));
+}
+
+// Hands the captaincy over to the successor chosen by the captain, if the captain died
+// If there is no successor left, a new captain will be elected at the start of the next day
+pub fn run_captain_succession(game: &mut dyn Game) {
+  let current_date = game.get_date();
+  let captain = match game.get_captain() {
+    Some(captain) if !game.get_player(captain).alive => captain,
+    _ => return,
+  };
+  let captain_name = game.get_player(captain).name.clone();
+  let successor = game.get_captain_successor().filter(|successor| game.get_player(*successor).alive);
+  game.set_captain(successor);
+
+  let content = match successor {
+    Some(successor) => {
+      game.record_event(EventCause::Player(captain), EventKind::CaptainAppointed { captain: successor });
+      format!("Avant de disparaître, le·a capitaine {captain_name} a désigné {} pour lui succéder.", game.get_player(successor).name)
+    },
+    None => format!("Le·a capitaine {captain_name} a disparu sans successeur·e, un·e nouveau·elle capitaine sera élu·e au début de la prochaine journée."),
+  };
+  game.broadcast(Message {
+    date: current_date,
+    source: String::from("Ordinateur Central"),
+    content,
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::SeedableRng;
+
+  use super::*;
+  use crate::game::{GameRng, GameStatus};
+  use crate::action::ActionType;
+  use crate::player::Player;
+  use crate::role::RoleCatalog;
+  use crate::night::default_pipeline;
+  use crate::missing_players;
+
+  // The patient 0 kills the captain during the night
+  fn create_game() -> (GameStatus, Vec<PlayerId>) {
+    let catalog = RoleCatalog::default();
+    let players = ["patient0", "physician", "astronaut", "astronaut"].iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let mut game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), Default::default());
+    let ids = game.get_player_ids(&|_| true);
+    game.set_captain(Some(ids[2]));
+    game.get_mut_player(ids[0]).set_target(&ActionType::Infect, Some(ids[2]));
+    game.get_mut_player(ids[0]).mutant_kill = true;
+    (game, ids)
+  }
+
+  #[test]
+  fn the_successor_takes_over_when_the_captain_dies_at_night() {
+    let (mut game, ids) = create_game();
+    game.set_captain_successor(Some(ids[3]));
+    let report = default_pipeline().run(&mut game);
+    assert_eq!(report.killed, vec![ids[2]]);
+    assert_eq!(game.get_captain(), Some(ids[3]));
+    assert!(game.get_events().iter().any(|event| event.kind == EventKind::CaptainAppointed { captain: ids[3] }));
+    assert!(game.get_player(ids[1]).messages.iter().any(|message| message.content.contains("a désigné player-3 pour lui succéder")));
+  }
+
+  #[test]
+  fn the_crew_elects_a_new_captain_when_the_successor_is_dead_too() {
+    let (mut game, ids) = create_game();
+    game.set_captain_successor(Some(ids[3]));
+    game.get_mut_player(ids[3]).die(1, String::from("Aspiré·e accidentellement par le sas tribord"));
+    default_pipeline().run(&mut game);
+    assert_eq!(game.get_captain(), None);
+    assert!(game.get_player(ids[1]).messages.iter().any(|message| message.content.contains("sans successeur·e")));
+
+    // The next day starts with the election, the survivors have to vote for their captain first
+    game.prepare_new_turn();
+    assert_eq!(missing_players(&game), vec![String::from("player-0"), String::from("player-1")]);
+  }
+}
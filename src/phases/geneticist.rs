@@ -0,0 +1,86 @@
+use crate::{
+  game::{Game, PlayerGame},
+  role::{GeneticistView, RoleAction},
+  action::{Action, ActionType},
+  interface::{Interface, colors::Color},
+  menu::{add_target_action, run_target_action},
+  night::{NightPhase, NightPipeline, NightReport},
+  player::{Player, PlayerId}};
+use super::{mutants, RoleModule};
+
+pub const NAME: &str = "geneticist";
+
+struct GeneticistPhase;
+
+impl NightPhase for GeneticistPhase {
+  fn name(&self) -> &'static str {
+    NAME
+  }
+
+  fn after(&self) -> Vec<&'static str> {
+    vec![mutants::NAME]
+  }
+
+  fn acts(&self, player: &Player) -> bool {
+    player.can(RoleAction::Genomyze)
+  }
+
+  fn run(&self, game: &mut dyn Game, geneticists: &[PlayerId], _report: &mut NightReport) {
+    run_geneticist_phase(game, geneticists);
+  }
+}
+
+pub const MODULE: RoleModule = RoleModule { register, add_actions };
+
+pub fn register(pipeline: &mut NightPipeline) {
+  pipeline.register(Box::new(GeneticistPhase));
+}
+
+pub fn add_actions(game: &mut dyn PlayerGame, actions_list: &mut Vec<Action>) {
+  if game.get_current_player().can(RoleAction::Genomyze) {
+    add_target_action(
+      game,
+      actions_list,
+      ActionType::Genomyze,
+      |game: &mut dyn PlayerGame, interface: &mut dyn Interface| run_target_action(game, interface, ActionType::Genomyze),
+    );
+  }
+}
+
+pub fn run_geneticist_phase(game: &mut dyn Game, geneticists: &[PlayerId]) {
+  for geneticist_id in geneticists.iter().copied() {
+    let geneticist = game.get_player(geneticist_id);
+    if geneticist.paralyzed {
+      game.send_message(geneticist_id,
+        String::from("Outil d'auto diagnostique"),
+        String::from("Vous avez été paralysé·e pendant la nuit, vous n'avez donc pu étudier le genome de vos camarades"));
+    } else {
+      if let Some(target) = geneticist.get_target(&ActionType::Genomyze).copied() {
+        game.get_mut_player(geneticist_id).spy_info.woke_up = true;
+        let target_name = game.get_player(target).name.clone();
+        let target = game.get_player(target);
+        let (host, resilient) = match target.role.geneticist_sees { // some roles can fool the geneticist
+          GeneticistView::Genome => (target.host, target.resilient),
+          GeneticistView::Standard => (false, false),
+          GeneticistView::Host => (true, false),
+          GeneticistView::Resilient => (false, true),
+        };
+        if host {
+          game.send_message(geneticist_id,
+            String::from("GenoTech v0.17"),
+            format!("Votre analyse du génome de {target_name} révèle qu'il est particulièrement sensible à l'infection. {}",
+              Color::FgRed.color("S'il venait à muter, il ne pourrait être soigné")));
+        } else if resilient {
+          game.send_message(geneticist_id,
+            String::from("GenoTech v0.17"),
+            format!("Votre analyse du génome de {target_name} révèle qu'il est résistant à l'infection. {}",
+              Color::FgGreen.color("Il ne deviendra jamais un mutant")));
+        } else {
+          game.send_message(geneticist_id,
+            String::from("GenoTech v0.17"),
+            format!("Votre analyse du génome de {target_name} révèle qu'il est d'une banalité affligeante. Réponse standard à la mutation"));
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,45 @@
+use crate::{
+  game::{Game, PlayerGame},
+  role::{Role, RoleAction},
+  action::Action,
+  interface::Interface,
+  night::NightPipeline};
+use super::RoleModule;
+
+// No night phase: the hacker receives a copy of the reports of the hacked role, see Game::send_message
+pub const MODULE: RoleModule = RoleModule { register, add_actions };
+
+pub fn register(_pipeline: &mut NightPipeline) {}
+
+pub fn add_actions(game: &mut dyn PlayerGame, actions_list: &mut Vec<Action>) {
+  if !game.get_current_player().can(RoleAction::Hack) {
+    return;
+  }
+  actions_list.push(Action::UserAction(
+    match &game.get_current_player().hacker_target {
+      Some(target) => format!("Selectionner un role à pirater [{}]", target),
+      None => String::from("Selectionner un role à pirater"),
+    },
+    |game: &mut dyn PlayerGame, interface: &mut dyn Interface| {
+      let hackable_roles = game.get_hackable_roles();
+      if hackable_roles.is_empty() {
+        interface.user_validate("Désolé, il n'y a personne que vous puissiez hacker")?;
+      } else {
+        game.get_mut_current_player().hacker_target = Some(interface.user_select_from(hackable_roles.iter())?.clone());
+      }
+      Ok(())
+    }
+  ));
+}
+
+// The roles of the living crew that send reports, and can be hacked
+pub fn hackable_roles(game: &dyn Game) -> Vec<Role> {
+  let mut hackable_roles: Vec<Role> = Vec::new();
+  for player in game.get_players() {
+    let reports = player.role.actions.iter().any(|action| action.report_source().is_some());
+    if player.role.hackable && reports && !hackable_roles.contains(&player.role) {
+      hackable_roles.push(player.role.clone());
+    }
+  }
+  hackable_roles
+}
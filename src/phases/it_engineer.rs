@@ -0,0 +1,51 @@
+use crate::{
+  game::{Game, PlayerGame},
+  role::RoleAction,
+  action::Action,
+  night::{NightPhase, NightPipeline, NightReport},
+  player::{Player, PlayerId}};
+use super::{mutants, physicians, RoleModule};
+
+pub const NAME: &str = "it_engineer";
+
+struct ITEngineerPhase;
+
+impl NightPhase for ITEngineerPhase {
+  fn name(&self) -> &'static str {
+    NAME
+  }
+
+  fn after(&self) -> Vec<&'static str> {
+    vec![mutants::NAME, physicians::NAME]
+  }
+
+  fn acts(&self, player: &Player) -> bool {
+    player.can(RoleAction::CountMutants)
+  }
+
+  fn run(&self, game: &mut dyn Game, it_guys: &[PlayerId], _report: &mut NightReport) {
+    run_it_phase(game, it_guys);
+  }
+}
+
+pub const MODULE: RoleModule = RoleModule { register, add_actions };
+
+pub fn register(pipeline: &mut NightPipeline) {
+  pipeline.register(Box::new(ITEngineerPhase));
+}
+
+pub fn add_actions(_game: &mut dyn PlayerGame, _actions_list: &mut Vec<Action>) {} // the count is automatic
+
+pub fn run_it_phase(game: &mut dyn Game, it_guys: &[PlayerId]) {
+  // Tell the IT guy how many mutants are in play
+  let infected_players = game.get_players().iter().filter(|player| player.infected).count();
+  for player_id in it_guys.iter().copied() {
+    if !game.get_player(player_id).paralyzed {
+      game.get_mut_player(player_id).spy_info.woke_up = true;
+      game.send_message(player_id,
+        String::from("Système de diagnostique"),
+        format!("L'analyse quantique de cette nuit a révélé la présence de {infected_players} membres d'équipage infectés à bord."),
+      )
+    } // See if we want to display something in else
+  }
+}
@@ -0,0 +1,169 @@
+use crate::{
+  game::{Game, PlayerGame},
+  message::Message,
+  action::{Action, ActionType},
+  helper::{compute_votes_results, select_votes_winner},
+  event::{EventCause, EventKind},
+  interface::{Interface, colors::Color},
+  menu::{add_target_action, run_target_action},
+  night::{NightPhase, NightPipeline, NightReport},
+  player::{Player, PlayerId}};
+use super::RoleModule;
+
+pub const NAME: &str = "mutants";
+
+struct MutantsPhase;
+
+impl NightPhase for MutantsPhase {
+  fn name(&self) -> &'static str {
+    NAME
+  }
+
+  fn acts(&self, player: &Player) -> bool {
+    player.infected
+  }
+
+  fn run(&self, game: &mut dyn Game, mutants: &[PlayerId], report: &mut NightReport) {
+    if let Some(killed) = run_mutants_phase(game, mutants) {
+      report.killed.push(killed);
+    }
+  }
+}
+
+pub const MODULE: RoleModule = RoleModule { register, add_actions };
+
+pub fn register(pipeline: &mut NightPipeline) {
+  pipeline.register(Box::new(MutantsPhase));
+}
+
+pub fn add_actions(game: &mut dyn PlayerGame, actions_list: &mut Vec<Action>) {
+  if !game.get_current_player().infected {
+    return;
+  }
+  add_target_action(
+    game,
+    actions_list,
+    ActionType::Infect,
+    |game: &mut dyn PlayerGame, interface: &mut dyn Interface| run_target_action(game, interface, ActionType::Infect),
+  );
+  add_target_action(
+    game,
+    actions_list,
+    ActionType::Paralyze,
+    |game: &mut dyn PlayerGame, interface: &mut dyn Interface| run_target_action(game, interface, ActionType::Paralyze),
+  );
+  actions_list.push(Action::UserAction( // Action to choose between mutating or killing
+    if game.get_current_player().mutant_kill {
+      format!("Choisir de muter la cible [{}]", Color::FgRed.color("Tuer"))
+    } else {
+      format!("Choisir de tuer la cible [{}]", Color::FgGreen.color("Muter"))
+    },
+    |game: &mut dyn PlayerGame, _interface: &mut dyn Interface| {
+      let current_player = game.get_mut_current_player();
+      current_player.mutant_kill = !current_player.mutant_kill;
+      Ok(())
+    }
+  ));
+}
+
+pub fn run_mutants_phase(game: &mut dyn Game, mutants: &[PlayerId]) -> Option<PlayerId> {
+  let current_date = game.get_date(); // do better
+
+  // Notify the mutants of who the other mutants are
+  // The newly converted mutant will only get that information at the next night
+  let mutants_names = game.get_players().iter()
+      .filter_map(|player| if player.infected { Some(player.name.clone())} else { None })
+      .collect::<Vec<String>>().join(" ");
+  game.limited_broadcast(Message {
+      date: current_date,
+      source: String::from("Overmind"),
+      content: String::from(format!("Lors du dernier crépuscule, les mutant·e·s étaient: [{mutants_names}]")),
+  }, & |player: &&mut &mut Player| player.infected);
+
+  for mutant in mutants {
+    game.get_mut_player(*mutant).spy_info.woke_up = true;
+  }
+
+  // Mutate or kill one player
+  let kill = mutants.iter()
+    .map(|mutant| game.get_player(*mutant))
+    .fold(0, |mut count, player| {
+      if player.mutant_kill {
+        count += 1;
+      } else {
+        count -= 1;
+      }
+      count
+    }) > 0;
+  let mutate_votes = compute_votes_results(
+      mutants.iter().map(|mutant| game.get_player(*mutant)).collect::<Vec<&Player>>().iter(),
+      ActionType::Infect);
+  let mutate_results = select_votes_winner(mutate_votes, game.rng());
+  if let Some((target_id, _)) = mutate_results {
+    let target_name = game.get_player(target_id).name.clone();
+    if kill {
+      game.get_mut_player(target_id).die(current_date, String::from("Carbonisé·e sous la douche"));
+      game.record_event(EventCause::Mutants, EventKind::Killed { target: target_id });
+      game.send_message(target_id,
+        String::from("Overmind"),
+        String::from("Nous avons décidé que vous n'étiez pas digne de rejoindre nos rangs, mais tout de même assez pour nous servir de repas"));
+      game.limited_broadcast(Message { // Notify mutants of who was killed
+          date: current_date,
+          source: String::from("Overmind"),
+          content: String::from(format!("Les arrangements ont été faits, {target_name} ne devrait pas se réveiller")),
+      }, & |player: &&mut &mut Player| player.infected);
+    } else {
+      game.limited_broadcast(Message { // Notify mutants of who was infected
+          date: current_date,
+          source: String::from("Overmind"),
+          content: String::from(format!("Nos spores ont été envoyées dans la cabine de {target_name}, iel devrait bientôt nous rejoindre...")),
+      }, & |player: &&mut &mut Player| player.infected);
+      if game.get_player(target_id).infected == false {
+        if game.get_player(target_id).resilient == false {
+          game.get_mut_player(target_id).infected = true;
+          game.get_mut_player(target_id).spy_info.was_infected = true;
+          game.record_event(EventCause::Mutants, EventKind::Infected { target: target_id });
+          game.send_message(target_id, // Notify the new mutant that he was infected
+            String::from("Overmind"),
+            format!("Bienvenue {}, nous sommes heureuxe de vous compter parmis nous.", target_name));
+        } else {
+          game.record_event(EventCause::Mutants, EventKind::InfectionResisted { target: target_id });
+          game.send_message(target_id,
+            String::from("Outil d'auto diagnostique"),
+            format!("Bonne nouvelle {}, les mutants ont essayé de vous infecter, mais votre genome vous a protégé!", target_name));
+        }
+      }
+    }
+  }
+
+  // Paralyze one player
+  let paralyze_votes = compute_votes_results(
+      mutants.iter().map(|mutant| game.get_player(*mutant)).collect::<Vec<&Player>>().iter(),
+      ActionType::Paralyze);
+  let paralyze_result = select_votes_winner(paralyze_votes, game.rng());
+  if let Some((player_id, _)) = paralyze_result {
+      let paralized_name = &game.get_player(player_id).name;
+      game.limited_broadcast(Message { // Notify mutants of who was paralysed
+          date: current_date,
+          source: String::from("Overmind"),
+          content: String::from(format!("Félicitations, cette nuit vous êtes parvenus à paralyser: {paralized_name}")),
+      }, & |player: &&mut &mut Player| player.infected);
+
+      game.record_event(EventCause::Mutants, EventKind::Paralyzed { target: player_id });
+      let paralyzed_player = game.get_mut_player(player_id);
+      paralyzed_player.paralyzed = true;
+      paralyzed_player.spy_info.was_paralyzed = true;
+      paralyzed_player.messages.push(Message {
+          date: current_date,
+          source: String::from("Outil d'auto diagnostique"),
+          content: String::from("Vous avez été paralysé pendant la nuit, vous n'avez donc pas pu faire d'action spéciale"),
+      })
+  }
+
+  if kill {
+    if let Some((target_id, _)) = mutate_results {
+      return Some(target_id);
+    }
+  }
+  return None;
+}
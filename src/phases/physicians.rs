@@ -0,0 +1,365 @@
+use crate::{
+  game::{Game, PlayerGame},
+  role::RoleAction,
+  action::{Action, ActionType},
+  helper::select_votes_winner,
+  event::{EventCause, EventKind},
+  interface::{Interface, colors::Color},
+  menu::{add_target_action, run_target_action},
+  night::{NightPhase, NightPipeline, NightReport},
+  player::{Player, PlayerId}};
+use super::{mutants, RoleModule};
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+pub const NAME: &str = "physicians";
+
+struct PhysiciansPhase;
+
+impl NightPhase for PhysiciansPhase {
+  fn name(&self) -> &'static str {
+    NAME
+  }
+
+  fn after(&self) -> Vec<&'static str> {
+    vec![mutants::NAME]
+  }
+
+  fn acts(&self, player: &Player) -> bool {
+    player.can(RoleAction::Cure)
+  }
+
+  fn run(&self, game: &mut dyn Game, physicians: &[PlayerId], report: &mut NightReport) {
+    if let Some(killed) = run_physicians_phase(game, physicians) {
+      report.killed.push(killed);
+    }
+  }
+}
+
+pub const MODULE: RoleModule = RoleModule { register, add_actions };
+
+pub fn register(pipeline: &mut NightPipeline) {
+  pipeline.register(Box::new(PhysiciansPhase));
+}
+
+pub fn add_actions(game: &mut dyn PlayerGame, actions_list: &mut Vec<Action>) {
+  let player = game.get_current_player();
+  if !player.can(RoleAction::Cure) || player.infected { // An infected physician cannot cure
+    return;
+  }
+  add_target_action( // Action to select some to cure
+    game,
+    actions_list,
+    ActionType::Cure,
+    |game: &mut dyn PlayerGame, interface: &mut dyn Interface| run_target_action(game, interface, ActionType::Cure),
+  );
+  actions_list.push(Action::UserAction( // Action to toggle auto-cure of other physicians
+    if game.get_current_player().auto_cure_physician {
+      format!("Que faire des médecins inactifs: [{}, {}, {}]", Color::Underscore.color(Color::FgGreen.color("Soigner").as_str()), "Tuer", "Rien")
+    } else if game.get_current_player().auto_kill_physician {
+      format!("Que faire des médecins inactifs: [{}, {}, {}]", "Soigner", Color::Underscore.color(Color::FgRed.color("Tuer").as_str()), "Rien")
+    } else {
+      format!("Que faire des médecins inactifs: [{}, {}, {}]", "Soigner", "Tuer", Color::Underscore.color("Rien"))
+    },
+    |game: &mut dyn PlayerGame, _interface: &mut dyn Interface| {
+      let current_player = game.get_mut_current_player();
+      if current_player.auto_cure_physician {
+        current_player.auto_cure_physician = false;
+        current_player.auto_kill_physician = true;
+      } else if current_player.auto_kill_physician {
+        current_player.auto_kill_physician = false
+      } else {
+        current_player.auto_cure_physician = true;
+      }
+      Ok(())
+    }
+  ));
+  actions_list.push(Action::UserAction( // Action to choose between curing or killing
+    if game.get_current_player().physician_kill {
+      format!("Que faire de la cible [{}, {}]", "Soigner", Color::Underscore.color(Color::FgRed.color("Tuer").as_str()))
+    } else {
+      format!("Que faire de la cible [{}, {}]", Color::Underscore.color(Color::FgGreen.color("Soigner").as_str()), "Tuer")
+    },
+    |game: &mut dyn PlayerGame, _interface: &mut dyn Interface| {
+      let current_player = game.get_mut_current_player();
+      current_player.physician_kill = !current_player.physician_kill;
+      Ok(())
+    }
+  ));
+}
+
+// Each active physician (healthy and not paralyzed) spends the night on one thing, in the order of the crew list:
+// * if they chose to take care of the inactive physicians, they cure or kill the next one in the queue
+// * otherwise they treat the target they selected
+// The team kills instead of curing if a strict majority of the active physicians voted for it, in which case
+// the most targeted patient dies (ties are drawn by lot). At most one patient is killed per night, and the
+// inactive physicians designated to be killed come first. A patient targeted several times is only treated once.
+pub fn run_physicians_phase(game: &mut dyn Game, physicians: &[PlayerId]) -> Option<PlayerId> {
+  let (disabled_physicians, active_physicians): (Vec<PlayerId>, Vec<PlayerId>) = physicians
+    .iter()
+    .copied()
+    .partition(|physician| game.get_player(*physician).infected || game.get_player(*physician).paralyzed);
+
+  for disabled_physician in disabled_physicians.iter() {
+    let infected = game.get_player(*disabled_physician).infected;
+    game.send_message(*disabled_physician,
+      String::from("Outil d'auto diagnostique"),
+      if infected {
+        String::from("Vous êtes infecté·e, vous n'avez donc pas participé aux soins")
+      } else {
+        String::from("Vous avez été·e paralysé·e pendant la nuit, vous n'avez donc pu participer aux soins")
+      });
+  }
+
+  let kill_votes = active_physicians.iter().filter(|physician| game.get_player(**physician).physician_kill).count();
+  let kill = kill_votes * 2 > active_physicians.len(); // strict majority
+
+  let mut disabled_queue: VecDeque<PlayerId> = disabled_physicians.into_iter().collect();
+  let mut cured_players = Vec::new();
+  let mut killed_colleagues = Vec::new();
+  let mut selected_targets = Vec::new();
+  let mut active_physician_names = Vec::new();
+  for active_physician in active_physicians.iter() {
+    let active_physician = game.get_mut_player(*active_physician);
+    active_physician.spy_info.woke_up = true;
+    active_physician_names.push(active_physician.name.clone());
+    let colleague = if active_physician.auto_cure_physician || active_physician.auto_kill_physician {
+      disabled_queue.pop_front()
+    } else {
+      None
+    };
+    match colleague {
+      Some(colleague) if active_physician.auto_cure_physician => cured_players.push(colleague),
+      Some(colleague) => killed_colleagues.push(colleague),
+      None => if let Some(target) = active_physician.get_target(&ActionType::Cure) {
+        selected_targets.push(*target);
+      },
+    }
+  }
+
+  let killed_player = if let Some(colleague) = killed_colleagues.first() {
+    Some(*colleague)
+  } else if kill {
+    let kill_results = selected_targets.iter().fold(HashMap::new(), |mut acc, target| {
+      *acc.entry(*target).or_insert(0) += 1;
+      acc
+    });
+    select_votes_winner(kill_results, game.rng()).map(|(target, _)| target)
+  } else {
+    None
+  };
+  if !kill {
+    cured_players.append(&mut selected_targets);
+  }
+  let mut treated_players = HashSet::new();
+  cured_players.retain(|player| Some(*player) != killed_player && treated_players.insert(*player));
+
+  // Cure the players, and warn them
+  let mut cured_players_names = Vec::new();
+  for cured_player in cured_players {
+    cured_players_names.push(game.get_player(cured_player).name.clone());
+    if !game.get_player(cured_player).role.curable {
+      game.record_event(EventCause::Physicians, EventKind::CureFailed { target: cured_player });
+      game.send_message(cured_player,
+        String::from("Équipe médicale"),
+        String::from("Vous avez soigné par un traitement par irradiation intense cette nuit, mais la mutation est trop avancée chez vous, cela a échoué"));
+    } else if !game.get_player(cured_player).infected {
+      game.record_event(EventCause::Physicians, EventKind::CureUnneeded { target: cured_player });
+      game.send_message(cured_player,
+        String::from("Équipe médicale"),
+        String::from("Vous avez été soigné par un traitement anti-mutation cette nuit, bien qu'il n'y ait eu aucune trace de mutations dans votre corps"));
+    } else if game.get_player(cured_player).host {
+      game.record_event(EventCause::Physicians, EventKind::CureFailed { target: cured_player });
+      game.send_message(cured_player,
+        String::from("Overmind"),
+        String::from("L'équipe médicale vous a administré un traitement anti-mutation cette nuit, mais votre génome semble résistant au traitement. Félicitations ;-)"));
+    } else { // infected and not host
+      game.get_mut_player(cured_player).infected = false;
+      game.get_mut_player(cured_player).spy_info.was_cured = true;
+      game.record_event(EventCause::Physicians, EventKind::Cured { target: cured_player });
+      game.send_message(cured_player,
+        String::from("Équipe médicale"),
+        String::from("Vous avez été soigné par un traitement par irradiation intense cette nuit, qui vous à débarrassé de toute trace de mutation"));
+    }
+  }
+
+  if let Some(target) = killed_player {
+    let current_date = game.get_date();
+    game.get_mut_player(target).die(current_date, String::from("Carbonisé·e sous la douche"));
+    game.record_event(EventCause::Physicians, EventKind::Killed { target });
+    game.send_message(target,
+      String::from("Équipe médicale"),
+      String::from("Le CHSCT à décidé que votre hygiène corporelle n'était pas compatible avec la survie du vaisseau, désolé."));
+  }
+
+  // Send messages to the active medical team about who was cured
+  let active_physician_names = active_physician_names.join(" ");
+  let cured_players_names = cured_players_names.join(" ");
+  let report = match killed_player {
+    Some(killed) if cured_players_names.is_empty() => format!("est parvenue à tuer: [{}]", game.get_player(killed).name),
+    Some(killed) => format!("est parvenue à tuer: [{}], et à soigner: [{}]", game.get_player(killed).name, cured_players_names),
+    None => format!("est parvenue à soigner: [{}]", cured_players_names),
+  };
+  for active_physician in active_physicians {
+    game.send_message(active_physician,
+      String::from("Équipe médicale"),
+      format!("L'équipe médicale opérationelle de la nuit précédente ({}) {}", active_physician_names, report));
+  }
+
+  killed_player
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::SeedableRng;
+
+  use super::*;
+  use crate::game::{GameRng, GameStatus};
+  use crate::event::GameEvent;
+  use crate::player::Player;
+  use crate::role::RoleCatalog;
+  use crate::config::GameConfig;
+
+  // Physicians first, then the patient 0, then astronauts to be cured or killed
+  fn create_game(physicians: usize) -> GameStatus {
+    let catalog = RoleCatalog::default();
+    let role = |id: &str| catalog.get(id).unwrap().clone();
+    let mut roles = vec![role("physician"); physicians];
+    roles.push(role("patient0"));
+    roles.append(&mut vec![role("astronaut"); 4]);
+    let players = roles.into_iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, (100 + idx).to_string(), format!("player-{idx}"), role))
+      .collect();
+    GameStatus::new(String::from("Test"), players, true, 42, GameRng::seed_from_u64(42), GameConfig::default())
+  }
+
+  fn ids(game: &GameStatus) -> Vec<PlayerId> {
+    game.get_player_ids(&|_| true)
+  }
+
+  fn cure(game: &mut GameStatus, physician: PlayerId, target: PlayerId, kill: bool) {
+    game.get_mut_player(physician).set_target(&ActionType::Cure, Some(target));
+    game.get_mut_player(physician).physician_kill = kill;
+  }
+
+  fn run(game: &mut GameStatus) -> Option<PlayerId> {
+    let physicians = game.get_player_ids(&|player| PhysiciansPhase.acts(player));
+    run_physicians_phase(game, &physicians)
+  }
+
+  fn count_events(game: &GameStatus, predicate: impl Fn(&GameEvent) -> bool) -> usize {
+    game.get_events().iter().filter(|event| predicate(event)).count()
+  }
+
+  #[test]
+  fn one_physician_cures_their_target() {
+    let mut game = create_game(1);
+    let ids = ids(&game);
+    game.get_mut_player(ids[2]).infected = true;
+    cure(&mut game, ids[0], ids[2], false);
+
+    assert_eq!(run(&mut game), None);
+    assert!(!game.get_player(ids[2]).infected);
+  }
+
+  #[test]
+  fn one_physician_kills_their_target() {
+    let mut game = create_game(1);
+    let ids = ids(&game);
+    cure(&mut game, ids[0], ids[1], true);
+
+    assert_eq!(run(&mut game), Some(ids[1]));
+    assert!(!game.get_player(ids[1]).alive);
+  }
+
+  #[test]
+  fn two_physicians_on_the_same_patient_treat_them_once() {
+    let mut game = create_game(2);
+    let ids = ids(&game);
+    game.get_mut_player(ids[3]).infected = true;
+    cure(&mut game, ids[0], ids[3], false);
+    cure(&mut game, ids[1], ids[3], false);
+
+    assert_eq!(run(&mut game), None);
+    assert!(!game.get_player(ids[3]).infected);
+    assert_eq!(count_events(&game, |event| event.kind == EventKind::Cured { target: ids[3] }), 1);
+    assert_eq!(game.get_player(ids[3]).messages.len(), 1);
+  }
+
+  #[test]
+  fn two_physicians_without_majority_cure() {
+    let mut game = create_game(2);
+    let ids = ids(&game);
+    cure(&mut game, ids[0], ids[3], true);
+    cure(&mut game, ids[1], ids[4], false);
+
+    assert_eq!(run(&mut game), None);
+    assert_eq!(count_events(&game, |event| matches!(event.kind, EventKind::CureUnneeded { .. })), 2);
+  }
+
+  #[test]
+  fn two_physicians_auto_cure_their_infected_colleague() {
+    let mut game = create_game(2);
+    let ids = ids(&game);
+    game.get_mut_player(ids[0]).infected = true;
+    cure(&mut game, ids[1], ids[4], false);
+
+    assert_eq!(run(&mut game), None);
+    assert!(!game.get_player(ids[0]).infected);
+    assert_eq!(count_events(&game, |event| event.kind == EventKind::CureUnneeded { target: ids[4] }), 0); // busy with the colleague
+  }
+
+  #[test]
+  fn three_physicians_majority_kills_the_most_targeted() {
+    let mut game = create_game(3);
+    let ids = ids(&game);
+    cure(&mut game, ids[0], ids[4], true);
+    cure(&mut game, ids[1], ids[4], true);
+    cure(&mut game, ids[2], ids[5], false);
+
+    assert_eq!(run(&mut game), Some(ids[4]));
+    assert!(game.get_player(ids[5]).alive);
+    assert_eq!(count_events(&game, |event| matches!(event.kind, EventKind::CureUnneeded { .. })), 0);
+  }
+
+  #[test]
+  fn three_physicians_auto_kill_does_not_override_the_cures() {
+    let mut game = create_game(3);
+    let ids = ids(&game);
+    game.get_mut_player(ids[0]).infected = true;
+    game.get_mut_player(ids[1]).auto_cure_physician = false;
+    game.get_mut_player(ids[1]).auto_kill_physician = true;
+    game.get_mut_player(ids[4]).infected = true;
+    cure(&mut game, ids[2], ids[4], false);
+
+    assert_eq!(run(&mut game), Some(ids[0]));
+    assert!(!game.get_player(ids[4]).infected);
+  }
+
+  #[test]
+  fn four_physicians_share_the_disabled_colleagues() {
+    let mut game = create_game(4);
+    let ids = ids(&game);
+    game.get_mut_player(ids[0]).infected = true;
+    game.get_mut_player(ids[1]).paralyzed = true;
+    game.get_mut_player(ids[2]).auto_cure_physician = false;
+    game.get_mut_player(ids[2]).auto_kill_physician = true;
+
+    // The first active physician takes care of the first disabled one
+    assert_eq!(run(&mut game), Some(ids[0]));
+    assert_eq!(count_events(&game, |event| event.kind == EventKind::CureUnneeded { target: ids[1] }), 1);
+  }
+
+  #[test]
+  fn four_physicians_tie_does_not_kill() {
+    let mut game = create_game(4);
+    let ids = ids(&game);
+    cure(&mut game, ids[0], ids[5], true);
+    cure(&mut game, ids[1], ids[5], true);
+    cure(&mut game, ids[2], ids[6], false);
+    cure(&mut game, ids[3], ids[7], false);
+
+    assert_eq!(run(&mut game), None);
+    assert_eq!(count_events(&game, |event| matches!(event.kind, EventKind::CureUnneeded { .. })), 3);
+  }
+}
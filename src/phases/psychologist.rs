@@ -0,0 +1,79 @@
+use crate::{
+  game::{Game, PlayerGame},
+  role::{PsychologistView, RoleAction},
+  player::{Player, PlayerId},
+  action::{Action, ActionType},
+  interface::Interface,
+  menu::{add_target_action, run_target_action},
+  night::{NightPhase, NightPipeline, NightReport}};
+use super::{mutants, physicians, RoleModule};
+
+pub const NAME: &str = "psychologist";
+
+struct PsychologistPhase;
+
+impl NightPhase for PsychologistPhase {
+  fn name(&self) -> &'static str {
+    NAME
+  }
+
+  fn after(&self) -> Vec<&'static str> {
+    vec![mutants::NAME, physicians::NAME]
+  }
+
+  fn acts(&self, player: &Player) -> bool {
+    player.can(RoleAction::Psychoanalyze)
+  }
+
+  fn run(&self, game: &mut dyn Game, psychologists: &[PlayerId], _report: &mut NightReport) {
+    run_psychologist_phase(game, psychologists);
+  }
+}
+
+pub const MODULE: RoleModule = RoleModule { register, add_actions };
+
+pub fn register(pipeline: &mut NightPipeline) {
+  pipeline.register(Box::new(PsychologistPhase));
+}
+
+pub fn add_actions(game: &mut dyn PlayerGame, actions_list: &mut Vec<Action>) {
+  if game.get_current_player().can(RoleAction::Psychoanalyze) {
+    add_target_action(
+      game,
+      actions_list,
+      ActionType::Psychoanalyze,
+      |game: &mut dyn PlayerGame, interface: &mut dyn Interface| run_target_action(game, interface, ActionType::Psychoanalyze),
+    );
+  }
+}
+
+pub fn run_psychologist_phase(game: &mut dyn Game, psychologists: &[PlayerId]) {
+  for psychologists_id in psychologists.iter().copied() {
+    if !game.get_player(psychologists_id).paralyzed {
+      game.get_mut_player(psychologists_id).spy_info.woke_up = true;
+      if let Some(analyzed_id) = game.get_player(psychologists_id).get_target(&ActionType::Psychoanalyze).copied() {
+        game.get_mut_player(analyzed_id).spy_info.was_psychoanalyzed = true;
+        let name = game.get_player(analyzed_id).name.clone();
+        if looks_like_mutant(game.get_player(analyzed_id)) {
+          game.send_message(psychologists_id,
+            String::from("Freud GPT"),
+            format!("D'après l'analyse, il semblerait que le comportement déviant de {} ne découle pas d'un trauma d'enfance, mais d'un changement récent. C'est un·e mutant·e!", name));
+        } else {
+          game.send_message(psychologists_id,
+            String::from("Freud GPT"),
+            format!("D'après l'analyse, il semblerait que le comportement déviant de {} découle simplement d'un rapport difficile à la mère, et pas d'une mutation génétique", name));
+        }
+      }
+    } // See if we want to display something in else
+  }
+}
+
+// By default the analysis reveals loyalties, so traitors look like mutants, but some roles can fool the psychologist
+fn looks_like_mutant(player: &Player) -> bool {
+  match player.role.psychologist_sees {
+    PsychologistView::Loyalty => player.sides_with_mutants(),
+    PsychologistView::Infection => player.infected,
+    PsychologistView::Mutant => true,
+    PsychologistView::Human => false,
+  }
+}
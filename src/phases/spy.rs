@@ -0,0 +1,89 @@
+use crate::{
+  game::{Game, PlayerGame},
+  role::RoleAction,
+  action::{Action, ActionType},
+  interface::Interface,
+  menu::{add_target_action, run_target_action},
+  night::{NightPhase, NightPipeline, NightReport},
+  player::{Player, PlayerId}};
+use super::{geneticist, it_engineer, mutants, physicians, psychologist, traitor, RoleModule};
+
+pub const NAME: &str = "spy";
+
+struct SpyPhase;
+
+impl NightPhase for SpyPhase {
+  fn name(&self) -> &'static str {
+    NAME
+  }
+
+  fn after(&self) -> Vec<&'static str> {
+    vec![mutants::NAME, traitor::NAME, physicians::NAME, it_engineer::NAME, psychologist::NAME, geneticist::NAME]
+  }
+
+  fn acts(&self, player: &Player) -> bool {
+    player.can(RoleAction::Spy)
+  }
+
+  fn run(&self, game: &mut dyn Game, spies: &[PlayerId], _report: &mut NightReport) {
+    run_spy_phase(game, spies);
+  }
+}
+
+pub const MODULE: RoleModule = RoleModule { register, add_actions };
+
+pub fn register(pipeline: &mut NightPipeline) {
+  pipeline.register(Box::new(SpyPhase));
+}
+
+pub fn add_actions(game: &mut dyn PlayerGame, actions_list: &mut Vec<Action>) {
+  if game.get_current_player().can(RoleAction::Spy) {
+    add_target_action(
+      game,
+      actions_list,
+      ActionType::Spy,
+      |game: &mut dyn PlayerGame, interface: &mut dyn Interface| run_target_action(game, interface, ActionType::Spy),
+    );
+  }
+}
+
+pub fn run_spy_phase(game: &mut dyn Game, spies: &[PlayerId]) {
+  for spy_id in spies.iter().copied() {
+    let spy = game.get_player(spy_id);
+    if spy.paralyzed {
+      game.send_message(spy_id,
+        String::from("Outil d'auto diagnostique"),
+        String::from("Vous avez été paralysé·e pendant la nuit, vous n'avez donc pu espioner vos camarades"));
+    } else {
+      if let Some(target) = spy.get_target(&ActionType::Spy).copied() {
+        let target_name = game.get_player(target).name.clone();
+        let spy_info = game.get_player(target).spy_info.clone();
+        if spy_info.woke_up {
+          game.send_message(spy_id,
+            String::from("Stalker IV"),
+            format!("Durant votre surveillance, vous avez vu {target_name} se reveiller et sortir de son dortoir"));
+        }
+        if spy_info.was_infected {
+          game.send_message(spy_id,
+            String::from("Stalker IV"),
+            format!("Durant votre surveillance, vous avez vu {target_name} se transformer en mutant·e"));
+        }
+        if spy_info.was_paralyzed {
+          game.send_message(spy_id,
+            String::from("Stalker IV"),
+            format!("Durant votre surveillance, vous avez vu {target_name} être paralysé·e"));
+        }
+        if spy_info.was_cured {
+          game.send_message(spy_id,
+            String::from("Stalker IV"),
+            format!("Durant votre surveillance, vous avez vu {target_name} guérir de sa mutation"));
+        }
+        if spy_info.was_psychoanalyzed {
+          game.send_message(spy_id,
+            String::from("Stalker IV"),
+            format!("Durant votre surveillance, vous avez vu {target_name} être analysé·e par le psychologue"));
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,165 @@
+use crate::{
+  game::{Game, PlayerGame},
+  role::RoleAction,
+  message::Message,
+  action::Action,
+  interface::{Interface, colors::Color},
+  night::{NightPhase, NightPipeline, NightReport},
+  player::{Player, PlayerId}};
+use super::{mutants, RoleModule};
+
+pub const NAME: &str = "traitor";
+
+struct TraitorPhase;
+
+impl NightPhase for TraitorPhase {
+  fn name(&self) -> &'static str {
+    NAME
+  }
+
+  fn after(&self) -> Vec<&'static str> {
+    vec![mutants::NAME] // the traitor can be paralyzed
+  }
+
+  fn acts(&self, player: &Player) -> bool {
+    player.can(RoleAction::SignalMutants) && !player.infected && player.traitor_signal
+  }
+
+  fn run(&self, game: &mut dyn Game, traitors: &[PlayerId], _report: &mut NightReport) {
+    run_traitor_phase(game, traitors);
+  }
+}
+
+pub const MODULE: RoleModule = RoleModule { register, add_actions };
+
+pub fn register(pipeline: &mut NightPipeline) {
+  pipeline.register(Box::new(TraitorPhase));
+}
+
+pub fn add_actions(game: &mut dyn PlayerGame, actions_list: &mut Vec<Action>) {
+  let player = game.get_current_player();
+  if !player.can(RoleAction::SignalMutants) || player.infected { // Once infected, the traitor is a regular mutant
+    return;
+  }
+  actions_list.push(Action::UserAction( // Action to reveal yourself to the mutants during the night
+    if player.traitor_signal {
+      format!("Faire signe aux mutants cette nuit [{}]", Color::FgRed.color("Oui"))
+    } else {
+      format!("Faire signe aux mutants cette nuit [{}]", Color::FgGreen.color("Non"))
+    },
+    |game: &mut dyn PlayerGame, _interface: &mut dyn Interface| {
+      let current_player = game.get_mut_current_player();
+      current_player.traitor_signal = !current_player.traitor_signal;
+      Ok(())
+    }
+  ));
+}
+
+// Traitors who signaled themselves meet the mutants
+pub fn run_traitor_phase(game: &mut dyn Game, traitors: &[PlayerId]) {
+  let current_date = game.get_date();
+
+  // The traitor meets the mutants as they were at dusk, whatever happened to them since then
+  let mutants_names = game.get_players().iter()
+      .filter_map(|player| {
+        let was_mutant = !player.spy_info.was_infected && (player.infected || player.spy_info.was_cured);
+        if was_mutant { Some(player.name.clone()) } else { None }
+      })
+      .collect::<Vec<String>>().join(" ");
+
+  for traitor_id in traitors.iter().copied() {
+    if game.get_player(traitor_id).paralyzed {
+      game.send_message(traitor_id,
+        String::from("Outil d'auto diagnostique"),
+        String::from("Vous avez été paralysé·e pendant la nuit, vous n'avez donc pas pu faire signe aux mutants"));
+      continue;
+    }
+    game.get_mut_player(traitor_id).spy_info.woke_up = true;
+    let traitor_name = game.get_player(traitor_id).name.clone();
+    game.limited_broadcast(Message {
+        date: current_date,
+        source: String::from("Overmind"),
+        content: format!("{traitor_name} nous a fait signe cette nuit: iel n'est pas infecté·e, mais sert notre cause"),
+    }, & |player: &&mut &mut Player| player.infected);
+    game.send_message(traitor_id,
+      String::from("Overmind"),
+      format!("Votre signal a été reçu. Les mutant·e·s sont: [{mutants_names}]"));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::SeedableRng;
+
+  use super::*;
+  use crate::action::{Action::UserAction, ActionType};
+  use crate::game::{GameRng, GameStatus};
+  use crate::interface::scripted::ScriptedInterface;
+  use crate::night::default_pipeline;
+  use crate::role::RoleCatalog;
+  use crate::rules::EndReason;
+
+  fn create_game(roles: &[&str]) -> (GameStatus, Vec<PlayerId>) {
+    let catalog = RoleCatalog::default();
+    let players = roles.iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), Default::default());
+    let ids = game.get_player_ids(&|_| true);
+    (game, ids)
+  }
+
+  fn has_message(game: &GameStatus, player: PlayerId, text: &str) -> bool {
+    game.get_player(player).messages.iter().any(|message| message.content.contains(text))
+  }
+
+  #[test]
+  fn the_signal_reaches_the_mutants() {
+    let (mut game, ids) = create_game(&["patient0", "traitor", "physician", "astronaut"]);
+    { // The traitor toggles the signal from their menu
+      let mut turn = game.get_player_game(ids[1]);
+      let mut actions = Vec::new();
+      add_actions(&mut turn, &mut actions);
+      let UserAction(_, run) = &actions[0] else { panic!("The traitor should have their signal") };
+      run(&mut turn, &mut ScriptedInterface::new(Vec::new())).unwrap();
+    }
+    assert!(game.get_player(ids[1]).traitor_signal);
+    default_pipeline().run(&mut game);
+    assert!(has_message(&game, ids[0], "player-1 nous a fait signe cette nuit"));
+    assert!(!has_message(&game, ids[2], "nous a fait signe"));
+    assert!(has_message(&game, ids[1], "Les mutant·e·s sont: [player-0]"));
+    assert!(game.get_player(ids[1]).spy_info.woke_up);
+  }
+
+  #[test]
+  fn a_paralyzed_traitor_cannot_signal() {
+    let (mut game, ids) = create_game(&["patient0", "traitor", "physician", "astronaut"]);
+    game.get_mut_player(ids[1]).traitor_signal = true;
+    game.get_mut_player(ids[0]).set_target(&ActionType::Paralyze, Some(ids[1]));
+    default_pipeline().run(&mut game);
+    assert!(!has_message(&game, ids[0], "nous a fait signe"));
+    assert!(has_message(&game, ids[1], "vous n'avez donc pas pu faire signe aux mutants"));
+  }
+
+  #[test]
+  fn the_psychologist_sees_the_traitor_as_a_mutant() {
+    let (mut game, ids) = create_game(&["patient0", "traitor", "psychologist", "astronaut"]);
+    game.get_mut_player(ids[2]).set_target(&ActionType::Psychoanalyze, Some(ids[1]));
+    default_pipeline().run(&mut game);
+    assert!(!game.get_player(ids[1]).infected);
+    assert!(has_message(&game, ids[2], "de player-1 ne découle pas d'un trauma d'enfance, mais d'un changement récent. C'est un·e mutant·e!"));
+  }
+
+  #[test]
+  fn a_living_traitor_wins_with_the_mutants() {
+    let (mut game, ids) = create_game(&["patient0", "traitor", "physician", "astronaut"]);
+    game.check_end_of_game();
+    assert!(!game.ended());
+    for id in [ids[2], ids[3]] {
+      game.get_mut_player(id).die(1, String::from("Aspiré·e accidentellement par le sas tribord"));
+    }
+    game.check_end_of_game();
+    assert_eq!(game.get_end_reason(), Some(EndReason::ShipTakenOver));
+    assert!(game.get_end_reason().unwrap().mutants_won());
+  }
+}
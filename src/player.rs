@@ -2,9 +2,10 @@ use std::collections::HashMap;
 
 use serde::{Serialize, Deserialize};
 
-use crate::role::Role;
+use crate::role::{Role, RoleAction, Team};
 use crate::message::Message;
 use crate::action::ActionType;
+use crate::keys::{hash_key, verify_key};
 
 #[derive(Debug, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
 pub struct PlayerId {
@@ -17,6 +18,18 @@ impl PartialEq for PlayerId {
     }
 }
 
+impl PartialOrd for PlayerId {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PlayerId { // Used to iterate over players in a stable order, so the random choices are reproducible
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
 impl PlayerId {
   pub fn get_player<'a>(&self, players: &'a Vec<Player>) -> &'a Player {
     return &players[self.id];
@@ -24,12 +37,22 @@ impl PlayerId {
   pub fn get_mut_player<'a>(&self, players: &'a mut Vec<Player>) -> &'a mut Player {
     return &mut players[self.id];
   }
+  pub fn index(&self) -> usize { // how the players are numbered outside of the game (the json api)
+    self.id
+  }
+}
+
+// What the whole crew knows about a crew member: all a player sees of the others (see PlayerGame)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrewMember {
+  pub id: PlayerId,
+  pub name: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Player {
   pub id: PlayerId,
-  pub key: String,
+  key_hash: String, // the key is only known by the player, see check_key
   pub name: String,
   pub role: Role,
   pub messages: Vec<Message>,
@@ -46,6 +69,7 @@ pub struct Player {
   pub auto_kill_physician: bool,
   pub physician_kill: bool,
   pub mutant_kill: bool,
+  pub traitor_signal: bool,
 
   // daily data
   pub has_connected_today: bool,
@@ -65,10 +89,10 @@ pub struct SpyData {
 
 impl Player {
   pub fn new(id: usize, key: String, name: String, role: Role) -> Player {
-    let infected = role == Role::Patient0;
+    let infected = role.infected;
     Player {
       id: PlayerId { id: id },
-      key,
+      key_hash: hash_key(&key),
       name,
       role,
       host: false,
@@ -82,6 +106,7 @@ impl Player {
       auto_kill_physician: false,
       physician_kill: false,
       mutant_kill: false,
+      traitor_signal: false,
       messages: Vec::new(),
       has_connected_today: false,
       actions: HashMap::new(),
@@ -97,6 +122,11 @@ impl Player {
     self.hacker_target = None;
     self.physician_kill = false;
     self.mutant_kill = false;
+    self.traitor_signal = false;
+  }
+
+  pub fn check_key(&self, key: &str) -> bool {
+    verify_key(&self.key_hash, key)
   }
 
   pub fn die(&mut self, date: u32, death_cause: String) {
@@ -105,6 +135,19 @@ impl Player {
     self.death_cause = Some(death_cause);
   }
 
+  // Traitors are healthy humans, but they win (and lose) with the mutants
+  pub fn sides_with_mutants(&self) -> bool {
+    self.infected || self.role.team == Team::Mutants
+  }
+
+  pub fn can(&self, action: RoleAction) -> bool {
+    self.role.can(action)
+  }
+
+  pub fn crew_member(&self) -> CrewMember {
+    CrewMember { id: self.id, name: self.name.clone() }
+  }
+
   pub fn get_target(&self, action: &ActionType) -> Option<&PlayerId> {
     return self.actions.get(action);
   }
@@ -1,31 +1,183 @@
 use std::fmt;
+use std::fs;
+use std::error;
+use std::collections::HashSet;
+
 use serde::{Serialize, Deserialize};
 
-#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone, Copy)]
-pub enum Role {
-  Patient0,
-  Physician,
-  Psychologist,
-  ITEngineer,
+// The roles shipped with the game, any other file with the same format can be used with --roles
+pub static DEFAULT_ROLES: &str = include_str!("../roles.toml");
+
+// Who a role wins with
+#[derive(PartialEq, Eq, Hash, Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub enum Team {
+  #[default]
+  Crew,
+  Mutants,
+}
+
+// Special actions given by a role, each of them is implemented by a role module (see phases::ROLE_MODULES)
+#[derive(PartialEq, Eq, Hash, Debug, Serialize, Deserialize, Clone, Copy)]
+pub enum RoleAction {
+  Psychoanalyze,
+  Cure,
+  Genomyze,
+  CountMutants,
   Spy,
-  Geneticist,
-  Hacker,
-  Traitor,
-  Astronaut,
+  Hack,
+  SignalMutants,
+}
+
+impl RoleAction {
+  // Source of the reports sent at night, which is what the hackers intercept
+  pub fn report_source(&self) -> Option<&'static str> {
+    match self {
+      RoleAction::Psychoanalyze => Some("Freud GPT"),
+      RoleAction::Genomyze => Some("GenoTech v0.17"),
+      RoleAction::CountMutants => Some("Système de diagnostique"),
+      RoleAction::Spy => Some("Stalker IV"),
+      RoleAction::Cure | RoleAction::Hack | RoleAction::SignalMutants => None,
+    }
+  }
+}
+
+// What the psychologist learns when analyzing someone with this role
+#[derive(PartialEq, Eq, Hash, Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub enum PsychologistView {
+  #[default]
+  Loyalty, // mutant if infected or on the mutants' team
+  Infection, // mutant only if infected
+  Mutant,
+  Human,
+}
+
+// What the geneticist learns when analyzing someone with this role
+#[derive(PartialEq, Eq, Hash, Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub enum GeneticistView {
+  #[default]
+  Genome, // the real genome of the player
+  Standard,
+  Host,
+  Resilient,
+}
+
+fn default_true() -> bool {
+  true
+}
+
+#[derive(PartialEq, Eq, Hash, Debug, Serialize, Deserialize, Clone)]
+pub struct Role {
+  pub id: String,
+  pub name: String,
+  #[serde(default)]
+  pub team: Team,
+  #[serde(default)]
+  pub infected: bool, // starts the game infected
+  #[serde(default = "default_true")]
+  pub curable: bool,
+  #[serde(default)]
+  pub actions: Vec<RoleAction>,
+  #[serde(default)]
+  pub psychologist_sees: PsychologistView,
+  #[serde(default)]
+  pub geneticist_sees: GeneticistView,
+  #[serde(default)]
+  pub hackable: bool, // the reports sent to this role can be intercepted by the hackers
+  #[serde(default)]
+  pub revealed_as: Option<String>, // name displayed instead of the real one when eliminated
+}
+
+impl Role {
+  pub fn can(&self, action: RoleAction) -> bool {
+    self.actions.contains(&action)
+  }
+
+  pub fn revealed_name(&self) -> &str {
+    self.revealed_as.as_ref().unwrap_or(&self.name)
+  }
 }
 
 impl fmt::Display for Role {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    match *self {
-        Role::Patient0 => write!(f, "Patient·e 0"),
-        Role::Psychologist => write!(f, "Psychologue"),
-        Role::Physician => write!(f, "Médecin"),
-        Role::Geneticist => write!(f, "Geneticien·ne"),
-        Role::ITEngineer => write!(f, "Informaticien·ne"),
-        Role::Spy => write!(f, "Espion·ne"),
-        Role::Hacker => write!(f, "Hacker·euse"),
-        Role::Traitor => write!(f, "Traitre·sse"),
-        Role::Astronaut => write!(f, "Astronaute"),
+    write!(f, "{}", self.name)
+  }
+}
+
+// All the roles that can be dealt in a game, in the order they are displayed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoleCatalog {
+  roles: Vec<Role>,
+}
+
+impl RoleCatalog {
+  pub fn from_toml (content: &str) -> Result<RoleCatalog, Box<dyn error::Error>> {
+    let catalog: RoleCatalog = toml::from_str(content)?;
+    let mut ids = HashSet::new();
+    for role in &catalog.roles {
+      if !ids.insert(role.id.as_str()) {
+        return Err(format!("Role '{}' is defined more than once", role.id).into());
+      }
     }
+    if catalog.roles.is_empty() {
+      return Err("No role defined".into());
+    }
+    Ok(catalog)
+  }
+
+  pub fn load (path: &str) -> Result<RoleCatalog, Box<dyn error::Error>> {
+    let content = fs::read_to_string(path).map_err(|error| format!("Cannot read {path}: {error}"))?;
+    RoleCatalog::from_toml(&content).map_err(|error| format!("Invalid roles in {path}: {error}").into())
+  }
+
+  pub fn roles (&self) -> &Vec<Role> {
+    &self.roles
+  }
+
+  pub fn get (&self, id: &str) -> Option<&Role> {
+    self.roles.iter().find(|role| role.id == id)
+  }
+
+  // Position of the role in the catalog, roles which are not in it come last
+  pub fn position (&self, role: &Role) -> usize {
+    self.roles.iter().position(|other| other.id == role.id).unwrap_or(self.roles.len())
+  }
+}
+
+impl Default for RoleCatalog {
+  fn default() -> Self {
+    RoleCatalog::from_toml(DEFAULT_ROLES).expect("The default roles are invalid")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn default_roles_are_valid() {
+    let catalog = RoleCatalog::default();
+    assert_eq!(catalog.get("patient0").unwrap().revealed_name(), "Astronaute");
+    assert!(catalog.get("traitor").unwrap().can(RoleAction::SignalMutants));
+  }
+
+  #[test]
+  fn house_roles_use_defaults_for_missing_fields() {
+    let catalog = RoleCatalog::from_toml(r#"
+      [[roles]]
+      id = "double_agent"
+      name = "Agent double"
+      team = "Mutants"
+      actions = ["Spy"]
+      psychologist_sees = "Human"
+    "#).unwrap();
+    let role = catalog.get("double_agent").unwrap();
+    assert!(role.curable && !role.infected && !role.hackable);
+    assert_eq!(role.geneticist_sees, GeneticistView::Genome);
+  }
+
+  #[test]
+  fn duplicated_roles_are_rejected() {
+    let roles = "[[roles]]\nid = \"a\"\nname = \"A\"\n[[roles]]\nid = \"a\"\nname = \"B\"\n";
+    assert!(RoleCatalog::from_toml(roles).is_err());
   }
 }
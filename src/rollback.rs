@@ -0,0 +1,137 @@
+use std::fmt;
+use std::time::Instant;
+
+use serde_json::Value;
+
+use crate::game::GameAccess;
+use crate::interface::{Idle, Interface};
+use crate::login::Terminal;
+
+// Copies of the game kept in memory, for the game master to go back in time after a wrong input
+// (a mis-typed vote, the wrong player designated in select_who_dies...)
+pub struct Snapshot {
+  pub date: u32,
+  pub moment: SnapshotMoment,
+  pub state: Value, // the whole GameStatus, as it is written in the backups
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnapshotMoment {
+  Launch, // when the program starts, for a new game as well as a restored one
+  DayStart,
+  BeforeElimination,
+}
+
+impl fmt::Display for Snapshot {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.moment {
+      SnapshotMoment::Launch => write!(f, "Jour {}: lancement du terminal", self.date),
+      SnapshotMoment::DayStart => write!(f, "Jour {}: début de la journée", self.date),
+      SnapshotMoment::BeforeElimination => write!(f, "Jour {}: avant l'élimination", self.date),
+    }
+  }
+}
+
+// Offered on the shared terminal: the wrong passwords count toward its lockout, like the wrong codes
+pub fn run_action_rollback (game: &mut GameAccess, interface: &mut dyn Interface) -> Result<(), Idle> {
+  interface.clear_terminal();
+  let locked = game.lock().get_login_guard(Terminal::Local).locked_for(Instant::now());
+  if let Some(remaining) = locked {
+    return interface.user_validate(format!("Terminal verrouillé suite à plusieurs codes invalides, réessayez dans {} secondes.", remaining.as_secs() + 1).as_str());
+  }
+  let password = interface.user_non_empty_input("Mot de passe du maître du jeu:")?;
+  let failure = {
+    let mut game = game.lock();
+    if game.get_config().gm_password.as_ref() != Some(&password) {
+      let rules = game.get_config().login.clone();
+      Some(game.get_login_guard(Terminal::Local).record_failure(&rules, Instant::now()))
+    } else {
+      game.get_login_guard(Terminal::Local).record_success();
+      None
+    }
+  };
+  match failure {
+    Some(Some(lockout)) => return interface.play_alarm(format!("Mot de passe invalide. Tentative d'intrusion détectée: terminal verrouillé pendant {} secondes.", lockout.as_secs()).as_str()),
+    Some(None) => return interface.user_validate("Mot de passe invalide, appuyez sur ENTREE pour revenir a l'écran d'accueil."),
+    None => (),
+  }
+
+  interface.println("À quel moment souhaitez-vous revenir? Tout ce qui s'est passé depuis sera oublié.");
+  let snapshots: Vec<String> = game.lock().get_snapshots().iter().map(|snapshot| snapshot.to_string()).collect();
+  let mut choices: Vec<Option<usize>> = (0..snapshots.len()).rev().map(Some).collect();
+  choices.push(None);
+  let choice = interface.user_select_from_with_custom_display(choices.into_iter(), |choice| match choice {
+    Some(idx) => snapshots[*idx].clone(),
+    None => String::from("Annuler"),
+  })?;
+  let Some(idx) = choice else {
+    return Ok(());
+  };
+  let result = {
+    let mut game = game.lock();
+    game.rollback(idx).map(|()| game.autosave()) // a crash right after should not bring back what was undone
+  };
+  match result {
+    Ok(saved) => {
+      crate::report_backup_error(saved, interface)?;
+      interface.user_validate(format!("Retour effectué: {}", snapshots[idx]).as_str())
+    },
+    Err(error) => interface.user_validate(format!("Impossible de revenir en arrière: {error}").as_str()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::SeedableRng;
+
+  use super::*;
+  use crate::config::GameConfig;
+  use crate::game::{Game, GameRng, GameStatus};
+  use crate::interface::scripted::ScriptedInterface;
+  use crate::player::Player;
+  use crate::role::RoleCatalog;
+
+  #[test]
+  fn rollback_restores_the_game_in_place() {
+    let catalog = RoleCatalog::default();
+    let players = ["patient0", "physician", "astronaut"].iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let mut game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), Default::default());
+    let victim = game.get_player_ids(&|_| true)[2];
+    game.take_snapshot(SnapshotMoment::DayStart).unwrap();
+    game.get_mut_player(victim).has_connected_today = true;
+    game.take_snapshot(SnapshotMoment::BeforeElimination).unwrap();
+    game.get_mut_player(victim).die(1, String::from("Éliminé·e par erreur"));
+    game.prepare_new_turn();
+    game.take_snapshot(SnapshotMoment::DayStart).unwrap();
+
+    game.rollback(1).unwrap();
+    assert_eq!(game.get_date(), 1);
+    assert!(game.get_player(victim).alive);
+    assert!(game.get_player(victim).has_connected_today);
+    assert_eq!(game.get_snapshots().iter().map(|snapshot| snapshot.moment).collect::<Vec<_>>(), vec![SnapshotMoment::DayStart, SnapshotMoment::BeforeElimination]);
+  }
+
+  #[test]
+  fn wrong_passwords_lock_the_terminal() {
+    let catalog = RoleCatalog::default();
+    let players = ["patient0", "physician", "astronaut"].iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let config = GameConfig { gm_password: Some(String::from("mj")), ..Default::default() };
+    let mut game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), config);
+    game.take_snapshot(SnapshotMoment::Launch).unwrap();
+    let mut interface = ScriptedInterface::new(["1234", "", "1234", "", "1234", "", "", "mj"].map(String::from).to_vec());
+    for _ in 0..4 {
+      run_action_rollback(&mut GameAccess::Owned(&mut game), &mut interface).unwrap();
+    }
+    assert!(interface.screens()[3].contains("terminal verrouillé pendant 30 secondes"));
+    assert!(interface.last_screen().contains("Terminal verrouillé"));
+    assert_eq!(interface.remaining_inputs(), 1); // the right password is not even asked for
+
+    // Going back in time does not unlock the terminal
+    game.rollback(0).unwrap();
+    assert!(game.get_login_guard(Terminal::Local).locked_for(Instant::now()).is_some());
+  }
+}
@@ -0,0 +1,147 @@
+use std::fmt;
+
+use serde::{Serialize, Deserialize};
+
+use crate::player::Player;
+use crate::role::RoleAction;
+
+// Variations of the rules that can change from one game to another
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameRules {
+  pub captain_vote_weight: usize, // number of votes the captain's ballot counts for in the elimination vote
+  pub early_end: Vec<EarlyEndRule>, // situations in which the mutants are declared winners before taking over the whole ship
+}
+
+impl Default for GameRules {
+  fn default() -> Self {
+    GameRules {
+      captain_vote_weight: 2,
+      early_end: vec![EarlyEndRule::NoHealthyPhysician { min_mutants: 2 }],
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum EarlyEndRule {
+  NoHealthyPhysician { min_mutants: usize }, // nobody can cure the mutants anymore
+  MutantsMajority, // the mutants' side holds the majority of the votes
+}
+
+impl EarlyEndRule {
+  pub fn applies(&self, living_players: &[&Player]) -> bool {
+    let mutants = living_players.iter().filter(|player| player.infected).count();
+    match self {
+      EarlyEndRule::NoHealthyPhysician { min_mutants } => {
+        let healthy_physicians = living_players.iter().filter(|player| player.can(RoleAction::Cure) && !player.infected).count();
+        healthy_physicians == 0 && mutants >= *min_mutants
+      },
+      EarlyEndRule::MutantsMajority => {
+        let mutants_side = living_players.iter().filter(|player| player.sides_with_mutants()).count();
+        mutants > 0 && mutants_side * 2 > living_players.len()
+      },
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum EndReason {
+  MutantsEliminated,
+  ShipTakenOver, // all the living players are on the mutants' side
+  EarlyEnd(EarlyEndRule),
+}
+
+impl EndReason {
+  pub fn mutants_won(&self) -> bool {
+    *self != EndReason::MutantsEliminated
+  }
+}
+
+impl fmt::Display for EndReason {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      EndReason::MutantsEliminated => write!(f, "Il ne reste plus aucun·e mutant·e à bord."),
+      EndReason::ShipTakenOver => write!(f, "Tous les membres d'équipage encore en vie sont du côté des mutants."),
+      EndReason::EarlyEnd(EarlyEndRule::NoHealthyPhysician { min_mutants }) =>
+        write!(f, "Il n'y a plus aucun·e médecin sain·e pour soigner les mutants, et au moins {min_mutants} mutant·e·s sont à bord: la partie est jouée."),
+      EndReason::EarlyEnd(EarlyEndRule::MutantsMajority) =>
+        write!(f, "Les mutants et leurs allié·e·s sont majoritaires et contrôlent les votes: la partie est jouée."),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::SeedableRng;
+
+  use super::*;
+  use crate::config::GameConfig;
+  use crate::game::{Game, GameRng, GameStatus};
+  use crate::player::PlayerId;
+  use crate::role::RoleCatalog;
+
+  fn create_game(roles: &[&str], early_end: Vec<EarlyEndRule>) -> (GameStatus, Vec<PlayerId>) {
+    let catalog = RoleCatalog::default();
+    let players = roles.iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let config = GameConfig { rules: GameRules { early_end, ..Default::default() }, ..Default::default() };
+    let game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), config);
+    let ids = game.get_player_ids(&|_| true);
+    (game, ids)
+  }
+
+  fn kill(game: &mut GameStatus, player: PlayerId) {
+    game.get_mut_player(player).die(1, String::from("Aspiré·e accidentellement par le sas tribord"));
+  }
+
+  #[test]
+  fn no_healthy_physician_needs_enough_mutants() {
+    let rule = EarlyEndRule::NoHealthyPhysician { min_mutants: 2 };
+    let (mut game, ids) = create_game(&["patient0", "physician", "astronaut", "astronaut", "astronaut"], Vec::new());
+    assert!(!rule.applies(&game.get_players()));
+
+    // Without physician, one mutant is not enough
+    kill(&mut game, ids[1]);
+    assert!(!rule.applies(&game.get_players()));
+    game.get_mut_player(ids[2]).infected = true;
+    assert!(rule.applies(&game.get_players()));
+
+    // An infected physician cannot cure anyone either
+    let (mut game, ids) = create_game(&["patient0", "physician", "astronaut", "astronaut", "astronaut"], Vec::new());
+    game.get_mut_player(ids[1]).infected = true;
+    assert!(rule.applies(&game.get_players()));
+  }
+
+  #[test]
+  fn mutants_majority_counts_their_allies() {
+    let rule = EarlyEndRule::MutantsMajority;
+    let (mut game, ids) = create_game(&["patient0", "traitor", "astronaut", "astronaut"], Vec::new());
+    assert!(!rule.applies(&game.get_players())); // half the votes is not a majority
+    kill(&mut game, ids[3]);
+    assert!(rule.applies(&game.get_players()));
+
+    // The allies alone do not win
+    game.get_mut_player(ids[0]).infected = false;
+    assert!(!rule.applies(&game.get_players()));
+  }
+
+  #[test]
+  fn early_ends_stop_the_game() {
+    let (mut game, ids) = create_game(&["patient0", "traitor", "physician", "astronaut", "astronaut"], vec![EarlyEndRule::MutantsMajority]);
+    kill(&mut game, ids[4]);
+    game.check_end_of_game();
+    assert_eq!(game.get_end_reason(), None);
+    kill(&mut game, ids[3]);
+    game.check_end_of_game();
+    assert_eq!(game.get_end_reason(), Some(EndReason::EarlyEnd(EarlyEndRule::MutantsMajority)));
+    assert!(game.ended() && game.get_end_reason().unwrap().mutants_won());
+
+    // Without the rule, the game goes on
+    let (mut game, ids) = create_game(&["patient0", "traitor", "physician", "astronaut", "astronaut"], Vec::new());
+    kill(&mut game, ids[3]);
+    kill(&mut game, ids[4]);
+    game.check_end_of_game();
+    assert_eq!(game.get_end_reason(), None);
+  }
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+use std::error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::backup::is_encrypted;
+use crate::game::{Game, GameStatus, PhaseOfDay};
+use crate::interface::{Idle, Interface};
+
+// The games found in the backup directory, to resume one without typing the path of its last backup
+pub struct SavedGame {
+  pub name: String,
+  pub creation: u64,
+  pub path: PathBuf, // the most recent backup of the game
+  pub date: u32,
+  pub status: Option<(PhaseOfDay, Vec<String>)>, // phase and survivors, unknown until an encrypted backup is decrypted
+}
+
+// What a backup file name tells about it: sporz-<name>-<creation>-day-<date>[-autosave-<number>]
+struct BackupName {
+  name: String,
+  creation: u64,
+  date: u32,
+  autosave: u32, // 0 for the backups written at the beginning of the day, before any autosave of that day
+}
+
+fn parse_backup_name (file_name: &str) -> Option<BackupName> {
+  let rest = file_name.strip_prefix("sporz-")?;
+  let (rest, autosave) = match rest.rsplit_once("-autosave-") {
+    Some((rest, autosave)) => (rest, autosave.parse().ok()?),
+    None => (rest, 0),
+  };
+  let (rest, date) = rest.rsplit_once("-day-")?;
+  let (name, creation) = rest.rsplit_once('-')?; // ship names can contain dashes, but not the creation
+  Some(BackupName { name: name.to_string(), creation: creation.parse().ok()?, date: date.parse().ok()?, autosave })
+}
+
+// Games that are over, or whose last backup cannot be read, are left out (unless it is encrypted, then we cannot know)
+pub fn find_saved_games (dir: &Path) -> Result<Vec<SavedGame>, Box<dyn error::Error>> {
+  if !dir.is_dir() {
+    return Ok(Vec::new());
+  }
+  // The last written backup is the one to resume: after going back in time, the backups of the later days are outdated
+  let mut latest_backups: BTreeMap<(u64, String), (SystemTime, u32, u32, PathBuf)> = BTreeMap::new();
+  for entry in fs::read_dir(dir)? {
+    let entry = entry?;
+    let path = entry.path();
+    let Some(backup) = path.file_name().and_then(|name| name.to_str()).and_then(parse_backup_name) else {
+      continue;
+    };
+    let modified = entry.metadata()?.modified()?;
+    let latest = latest_backups.entry((backup.creation, backup.name)).or_insert((SystemTime::UNIX_EPOCH, 0, 0, PathBuf::new()));
+    if (modified, backup.autosave, backup.date) >= (latest.0, latest.1, latest.2) {
+      *latest = (modified, backup.autosave, backup.date, path);
+    }
+  }
+
+  let mut saved_games = Vec::new();
+  for ((creation, name), (_, _, date, path)) in latest_backups.into_iter().rev() { // most recent games first
+    if is_encrypted(&path)? {
+      saved_games.push(SavedGame { name, creation, path, date, status: None });
+      continue;
+    }
+    let Ok(game) = GameStatus::restore_from_backup(&path.to_string_lossy().to_string(), None) else {
+      continue;
+    };
+    if game.ended() {
+      continue;
+    }
+    let survivors = game.get_players().iter().map(|player| player.name.clone()).collect();
+    saved_games.push(SavedGame { name, creation, path, date: game.get_date(), status: Some((*game.get_phase_of_day(), survivors)) });
+  }
+  Ok(saved_games)
+}
+
+fn time_since (creation: u64) -> String {
+  let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+  let minutes = now.saturating_sub(creation) / 60;
+  match minutes {
+    0 => String::from("à l'instant"),
+    1..=59 => format!("il y a {minutes} min"),
+    60..=1439 => format!("il y a {} h", minutes / 60),
+    _ => format!("il y a {} jours", minutes / 1440),
+  }
+}
+
+impl std::fmt::Display for SavedGame {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(f, "{} (créée {}) - jour {}", self.name, time_since(self.creation), self.date)?;
+    match &self.status {
+      Some((phase, survivors)) => {
+        let phase = match phase {
+          PhaseOfDay::Day => "journée",
+          PhaseOfDay::Twilight => "crépuscule",
+        };
+        write!(f, " ({}) - survivant·e·s: [{}]", phase, survivors.join(", "))
+      },
+      None => write!(f, " - sauvegarde chiffrée"),
+    }
+  }
+}
+
+// Returns the backup to resume, or None to create a new game (only offered if `new_game` is set)
+pub fn pick_saved_game (interface: &mut dyn Interface, saved_games: &[SavedGame], new_game: bool) -> Result<Option<PathBuf>, Idle> {
+  interface.clear_terminal();
+  interface.println("Parties sauvegardées:");
+  let mut choices: Vec<Option<&SavedGame>> = saved_games.iter().map(Some).collect();
+  if new_game {
+    choices.insert(0, None);
+  }
+  let choice = interface.user_select_from_with_custom_display(choices.into_iter(), |choice| match choice {
+    Some(saved_game) => saved_game.to_string(),
+    None => String::from("Créer une nouvelle partie"),
+  })?;
+  Ok(choice.map(|saved_game| saved_game.path.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::SeedableRng;
+
+  use super::*;
+  use crate::config::GameConfig;
+  use crate::game::GameRng;
+  use crate::player::Player;
+  use crate::role::RoleCatalog;
+
+  #[test]
+  fn backup_names_are_parsed() {
+    let backup = parse_backup_name("sporz-Red-Dwarf-1700000000-day-3-autosave-12").unwrap();
+    assert_eq!((backup.name.as_str(), backup.creation, backup.date, backup.autosave), ("Red-Dwarf", 1700000000, 3, 12));
+    let backup = parse_backup_name("sporz-Koursk-1700000000-day-4").unwrap();
+    assert_eq!((backup.name.as_str(), backup.date, backup.autosave), ("Koursk", 4, 0));
+    assert!(parse_backup_name("sporz-Koursk-1700000000-day-4-autosave-3.tmp").is_none());
+    assert!(parse_backup_name("notes.txt").is_none());
+  }
+
+  #[test]
+  fn a_resumed_game_stays_one_saved_game() {
+    let dir = std::env::temp_dir().join(format!("rusty-spores-saved-games-{}", std::process::id()));
+    let backup_dir = Some(dir.to_string_lossy().to_string());
+    let catalog = RoleCatalog::default();
+    let players = ["patient0", "astronaut"].iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let config = GameConfig { autosaves_kept: 2, ..Default::default() };
+    let mut game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), config);
+    game.set_backup_dir(backup_dir.clone());
+    game.autosave().unwrap();
+    let first_autosave = find_saved_games(&dir).unwrap()[0].path.clone();
+    game.autosave().unwrap();
+
+    // Resumed from the first autosave, the next ones belong to the same game and replace the older ones
+    let mut resumed = GameStatus::restore_from_backup(&first_autosave.to_string_lossy().to_string(), None).unwrap();
+    resumed.set_backup_dir(backup_dir);
+    resumed.autosave().unwrap();
+    let saved_games = find_saved_games(&dir).unwrap();
+    let files = fs::read_dir(&dir).unwrap().count();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(saved_games.len(), 1);
+    assert!(saved_games[0].path.to_string_lossy().ends_with("-autosave-3"));
+    assert_eq!(files, 2);
+  }
+}
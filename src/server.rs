@@ -0,0 +1,214 @@
+use std::error;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+
+use crate::action::Action::{GeneralAction, UserAction};
+use crate::game::{Game, GameAccess, GameStatus};
+use crate::interface::{Idle, Interface};
+use crate::menu::{display_ship_status, game_master_actions};
+
+pub mod api;
+pub mod events;
+pub mod http;
+pub mod tcp;
+
+// The game is shared between the terminal of the game master and the devices of the players
+pub type SharedGame = Arc<Mutex<GameStatus>>;
+
+// Lets the players use their own devices (a browser with `http`, nc or telnet with `tcp`),
+// the terminal is left to the game master (end of the day, night, rollback...)
+// Returns the game once it is over
+pub fn serve_game (game: GameStatus, interface: &mut dyn Interface, http: Option<&str>, tcp: Option<&str>) -> Result<GameStatus, Box<dyn error::Error>> {
+  let game = Arc::new(Mutex::new(game));
+  let mut connections = Vec::new();
+  let http_server = match http {
+    Some(address) => {
+      let server = http::HttpServer::start(address, game.clone())?;
+      connections.push(format!("avec leur téléphone sur http://{}", reachable_address(server.address())));
+      Some(server)
+    },
+    None => None,
+  };
+  let tcp_server = match tcp {
+    Some(address) => {
+      let server = tcp::TcpServer::start(address, game.clone())?;
+      let address = reachable_address(server.address());
+      connections.push(format!("depuis un terminal avec: nc {} {}", address.ip(), address.port()));
+      Some(server)
+    },
+    None => None,
+  };
+  let result = run_game_master_terminal(&game, interface, &connections);
+  if let Some(server) = http_server {
+    server.stop();
+  }
+  if let Some(server) = tcp_server {
+    server.stop();
+  }
+  result?;
+  let game = Arc::try_unwrap(game).map_err(|_| "The game is still used by the server")?;
+  Ok(game.into_inner().unwrap()) // only poisoned if the server panicked, and it would have been reported already
+}
+
+fn run_game_master_terminal (game: &SharedGame, interface: &mut dyn Interface, connections: &[String]) -> Result<(), Idle> {
+  loop {
+    let actions_list = {
+      let game = game.lock().unwrap();
+      if game.ended() {
+        return Ok(());
+      }
+      interface.clear_terminal();
+      display_ship_status(&*game, interface);
+      for connection in connections {
+        interface.println(format!("* Les membres d'équipage s'identifient {connection}").as_str());
+      }
+      game_master_actions(&*game)
+    };
+    // The game is not locked while waiting for the game master, so the players can play in the meantime
+    let action = interface.user_select_action(&actions_list)?;
+    {
+      let mut game = game.lock().unwrap();
+      // The api may have moved the game to another phase in the meantime, the day must not end twice
+      if game.ended() || game_master_actions(&*game).iter().all(|current| current.description() != action.description()) {
+        continue;
+      }
+      game.set_game_master_busy(true);
+    }
+    // The action only locks the game for each of its steps, the players can still play while the terminal waits
+    let result = match action {
+      UserAction(_, _) => unreachable!(), // the game master only has general actions
+      GeneralAction(_, run) => run(&mut GameAccess::Shared(game), interface),
+    };
+    game.lock().unwrap().set_game_master_busy(false);
+    result?;
+  }
+}
+
+// Listening on all the interfaces gives no address to type in the phones, the one used to reach other networks usually is the right one
+fn reachable_address (address: SocketAddr) -> SocketAddr {
+  if !address.ip().is_unspecified() {
+    return address;
+  }
+  let local_address = UdpSocket::bind("0.0.0.0:0").and_then(|socket| {
+    socket.connect("8.8.8.8:80")?; // nothing is sent, this only picks the interface
+    socket.local_addr()
+  });
+  match local_address {
+    Ok(local_address) => SocketAddr::new(local_address.ip(), address.port()),
+    Err(_) => address,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::mpsc::{self, Receiver, Sender};
+  use std::thread;
+  use std::time::Duration;
+
+  use rand::SeedableRng;
+  use serde_json::Value;
+
+  use super::*;
+  use crate::action::ActionType;
+  use crate::config::GameConfig;
+  use crate::game::{GameRng, PhaseOfDay};
+  use crate::interface::Sound;
+  use crate::player::Player;
+  use crate::role::RoleCatalog;
+
+  // The game master answers from the test, the terminal really waits for them meanwhile
+  struct ChannelInterface {
+    answers: Receiver<String>,
+    prompts: Sender<String>,
+  }
+
+  impl ChannelInterface {
+    fn next_answer(&mut self, message: &str) -> Result<String, Idle> {
+      let _ = self.prompts.send(message.to_string());
+      self.answers.recv().map_err(|_| Idle) // the game master walked away
+    }
+  }
+
+  impl Interface for ChannelInterface {
+    fn mock(&mut self, _inputs: Vec<String>) {}
+    fn print(&mut self, _text: &str) {}
+    fn println(&mut self, _text: &str) {}
+    fn clear_terminal(&mut self) {}
+
+    fn user_choice(&mut self, message: &str, accepted_answers: &[String]) -> Result<String, Idle> {
+      loop {
+        let answer = self.next_answer(message)?;
+        if accepted_answers.contains(&answer) {
+          return Ok(answer);
+        }
+      }
+    }
+
+    fn user_validate(&mut self, message: &str) -> Result<(), Idle> {
+      self.next_answer(message).map(|_| ())
+    }
+
+    fn user_non_empty_input(&mut self, message: &str) -> Result<String, Idle> {
+      self.next_answer(message)
+    }
+
+    fn set_idle_timeout(&mut self, _timeout: Option<Duration>) {}
+    fn play_sound(&mut self, _sound: Sound) {}
+    fn stop_sound(&mut self) {}
+    fn wait_and_display(&mut self, _message: &str, _wait: Duration, _display_interval: Duration) {}
+  }
+
+  #[test]
+  fn players_are_answered_while_the_game_master_terminal_waits() {
+    let catalog = RoleCatalog::default();
+    let players = ["patient0", "physician", "astronaut", "astronaut"].iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let config = GameConfig { gm_password: Some(String::from("mj")), ..Default::default() };
+    let mut game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), config);
+    let ids = game.get_player_ids(&|_| true);
+    game.set_captain(Some(ids[3]));
+    for id in &ids {
+      game.get_mut_player(*id).has_connected_today = true;
+      game.get_mut_player(*id).set_target(&ActionType::Eliminate, Some(ids[1]));
+    }
+    let game = Arc::new(Mutex::new(game));
+    let server = http::HttpServer::start("127.0.0.1:0", game.clone()).unwrap();
+
+    let (answer, answers) = mpsc::channel();
+    let (prompt, prompts) = mpsc::channel();
+    let terminal = thread::spawn({
+      let game = game.clone();
+      move || run_game_master_terminal(&game, &mut ChannelInterface { answers, prompts: prompt }, &[])
+    });
+    let next_prompt = || prompts.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert!(next_prompt().contains("Quel est votre choix?"));
+    answer.send(String::from("1")).unwrap(); // end of the day
+
+    // The terminal waits for the eliminated player, in the middle of run_end_of_day
+    assert!(next_prompt().contains("Merci de faire venir player-1"));
+    let agent = ureq::AgentBuilder::new().timeout(Duration::from_secs(5)).build();
+    let url = format!("http://{}/api", server.address());
+    let response = agent.get(&format!("{url}/game")).call().unwrap();
+    let body: Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+    assert_eq!((&body["phase"], &body["players"][1]["alive"]), (&Value::from("day"), &Value::from(false)));
+    // The api cannot run a phase behind the back of the game master
+    match agent.post(&format!("{url}/game/end-of-day")).set("Authorization", "Bearer mj").call() {
+      Err(ureq::Error::Status(status, _)) => assert_eq!(status, 409),
+      result => panic!("The phase should have been refused: {:?}", result.map(|response| response.status())),
+    }
+
+    answer.send(String::from("")).unwrap();
+    assert!(next_prompt().contains("Quel est votre choix?"));
+    answer.send(String::from("1")).unwrap(); // Aller dans le sas
+    answer.send(String::from("1")).unwrap(); // Mourir
+    assert!(next_prompt().contains("Quel est votre choix?"));
+    assert!(next_prompt().contains("Quel est votre choix?")); // back to the menu of the game master
+    drop(answer);
+    assert!(terminal.join().unwrap().is_err());
+    let game = game.lock().unwrap();
+    assert!(game.get_phase_of_day() == &PhaseOfDay::Twilight);
+    assert!(!game.game_master_busy());
+    server.stop();
+  }
+}
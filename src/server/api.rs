@@ -0,0 +1,515 @@
+use std::io::Cursor;
+use std::time::Instant;
+
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::action::ActionType;
+use crate::game::{Game, GameAccess, GameStatus, PhaseOfDay, PlayerGame};
+use crate::interface::{Idle, Interface, headless::HeadlessInterface};
+use crate::login::{log_in, LogIn, Terminal};
+use crate::player::Player;
+use crate::role::RoleAction;
+use crate::session::SessionId;
+use crate::{autosave, missing_players, run_captain_election, run_end_of_day, run_night};
+
+use super::http::{bearer_token, terminal};
+
+type JsonResponse = Response<Cursor<Vec<u8>>>;
+
+#[derive(Deserialize)]
+struct LoginRequest {
+  code: String,
+}
+
+#[derive(Deserialize)]
+struct TargetRequest {
+  action: ActionType,
+  target: Option<usize>, // none to cancel the action
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TogglesRequest {
+  mutant_kill: Option<bool>,
+  physician_kill: Option<bool>,
+  traitor_signal: Option<bool>,
+  auto_cure_physician: Option<bool>,
+  auto_kill_physician: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct SuccessorRequest {
+  target: Option<usize>, // none to cancel the choice
+}
+
+#[derive(Deserialize)]
+struct HackerTargetRequest {
+  role: Option<String>, // the id of the role, none to stop hacking
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct PhaseRequest {
+  answers: Vec<String>, // what would have been typed on the terminal during the ceremonies
+}
+
+// The operations of the menus as json, for the frontends of the players and of the game master (see API.md)
+// The players use the token they get for their code, the game master uses their password
+pub fn handle_request (request: &Request, body: &str, game: &mut GameStatus) -> JsonResponse {
+  let token = bearer_token(request);
+  let session = token.as_ref()
+    .map(|token| SessionId::from_token(token))
+    .filter(|session| game.get_session_player(session).is_some());
+  let path = request.url().split('?').next().unwrap_or_default();
+  if game.ended() && request.method() != &Method::Get {
+    return error(409, "The game is over");
+  }
+  match (request.method(), path, session) {
+    (Method::Get, "/api/game", _) => json_response(200, describe_game(game)),
+    (Method::Post, "/api/login", _) => match serde_json::from_str(body) {
+      Ok(LoginRequest { code }) => log_in_player(game, terminal(request), code),
+      Err(parse_error) => error(400, format!("Invalid request: {parse_error}").as_str()),
+    },
+    (Method::Post, "/api/game/election", _) => run_phase(game, terminal(request), token, body, Ceremony::Election),
+    (Method::Post, "/api/game/end-of-day", _) => run_phase(game, terminal(request), token, body, Ceremony::EndOfDay),
+    (Method::Post, "/api/game/night", _) => run_phase(game, terminal(request), token, body, Ceremony::Night),
+    (Method::Post, "/api/logout", Some(session)) => {
+      game.get_session_game(&session).unwrap().log_out(); // only this token, the other devices of the player stay logged in
+      let _ = autosave(game, &mut HeadlessInterface::new(0)); // the errors are reported on stderr
+      json_response(200, json!({}))
+    },
+    (Method::Get, "/api/player", Some(session)) => json_response(200, describe_player(&mut game.get_session_game(&session).unwrap())),
+    (Method::Put, "/api/player/targets", Some(session)) => match serde_json::from_str(body) {
+      Ok(target) => set_target(&mut game.get_session_game(&session).unwrap(), target),
+      Err(parse_error) => error(400, format!("Invalid request: {parse_error}").as_str()),
+    },
+    (Method::Put, "/api/player/toggles", Some(session)) => match serde_json::from_str(body) {
+      Ok(toggles) => set_toggles(&mut game.get_session_game(&session).unwrap(), toggles),
+      Err(parse_error) => error(400, format!("Invalid request: {parse_error}").as_str()),
+    },
+    (Method::Put, "/api/player/successor", Some(session)) => match serde_json::from_str(body) {
+      Ok(successor) => set_successor(&mut game.get_session_game(&session).unwrap(), successor),
+      Err(parse_error) => error(400, format!("Invalid request: {parse_error}").as_str()),
+    },
+    (Method::Put, "/api/player/hacker-target", Some(session)) => match serde_json::from_str(body) {
+      Ok(hacker_target) => set_hacker_target(&mut game.get_session_game(&session).unwrap(), hacker_target),
+      Err(parse_error) => error(400, format!("Invalid request: {parse_error}").as_str()),
+    },
+    (_, "/api/logout" | "/api/player" | "/api/player/targets" | "/api/player/toggles" | "/api/player/successor" | "/api/player/hacker-target", None) => error(401, "Unknown or missing token"),
+    _ => error(404, "Unknown endpoint"),
+  }
+}
+
+fn log_in_player (game: &mut GameStatus, terminal: Terminal, code: String) -> JsonResponse {
+  if let Some(remaining) = game.get_login_guard(terminal).locked_for(Instant::now()) {
+    return json_response(429, json!({ "error": "Locked after too many invalid codes", "retry_after": remaining.as_secs() + 1 }));
+  }
+  match log_in(game, terminal, code) {
+    LogIn::Success(player) => json_response(200, json!({ "token": game.open_session(player).as_str(), "player": player.index() })),
+    LogIn::Invalid => error(401, "Invalid code"),
+    LogIn::Locked(lockout) => json_response(429, json!({ "error": "Invalid code, locked after too many of them", "retry_after": lockout.as_secs() })),
+  }
+}
+
+// What everyone may know, for a display in front of the whole crew
+fn describe_game (game: &dyn Game) -> Value {
+  let players: Vec<Value> = game.get_all_players().map(|player| json!({
+    "id": player.id.index(),
+    "name": player.name,
+    "alive": player.alive,
+    "death_date": player.death_date,
+    "death_cause": player.death_cause,
+  })).collect();
+  json!({
+    "name": game.get_name(),
+    "date": game.get_date(),
+    "phase": match game.get_phase_of_day() {
+      PhaseOfDay::Day => "day",
+      PhaseOfDay::Twilight => "twilight",
+    },
+    "captain": game.get_captain().map(|captain| captain.index()),
+    "players": players,
+    "ended": game.ended(),
+    "end_reason": game.get_end_reason().map(|reason| reason.to_string()),
+  })
+}
+
+// What the menu of the player displays, and the choices it offers
+fn describe_player (game: &mut dyn PlayerGame) -> Value {
+  game.get_mut_current_player().has_connected_today = true;
+  let player = game.get_current_player();
+  let mut targets = Map::new();
+  for action in target_actions(game) {
+    targets.insert(format!("{action:?}"), json!(player.get_target(&action).map(|target| target.index())));
+  }
+  let mut toggles = Map::new();
+  for (toggle, value) in toggle_values(player) {
+    if available_toggles(game).contains(&toggle) {
+      toggles.insert(String::from(toggle), json!(value));
+    }
+  }
+  let physicians: Option<Vec<String>> = player.can(RoleAction::Cure).then(|| game.get_medical_team().into_iter()
+    .map(|physician| physician.name)
+    .collect());
+  let mut description = json!({
+    "id": player.id.index(),
+    "name": player.name,
+    "role": player.role.to_string(),
+    "alive": player.alive,
+    "infected": player.infected,
+    "captain": game.get_captain() == Some(player.id),
+    "physicians": physicians,
+    "messages": player.messages,
+    "targets": targets,
+    "toggles": toggles,
+  });
+  if player.alive && game.get_captain() == Some(player.id) {
+    description["successor"] = json!(game.get_captain_successor().map(|successor| successor.index()));
+  }
+  if player.alive && player.can(RoleAction::Hack) {
+    let hackable_roles: Vec<Value> = game.get_hackable_roles().iter().map(|role| json!({ "id": role.id, "name": role.name })).collect();
+    description["hacker_target"] = json!(player.hacker_target.as_ref().map(|role| &role.id));
+    description["hackable_roles"] = json!(hackable_roles);
+  }
+  description
+}
+
+fn set_target (game: &mut dyn PlayerGame, request: TargetRequest) -> JsonResponse {
+  if !target_actions(game).contains(&request.action) {
+    return error(403, format!("{:?} is not available to the player", request.action).as_str());
+  }
+  let target = match request.target {
+    Some(index) => match game.get_crew().into_iter().find(|member| member.id.index() == index) {
+      Some(member) => Some(member.id),
+      None => return error(400, format!("No living player {index}").as_str()),
+    },
+    None => None,
+  };
+  game.set_current_target(&request.action, target);
+  json_response(200, describe_player(game))
+}
+
+fn set_toggles (game: &mut dyn PlayerGame, request: TogglesRequest) -> JsonResponse {
+  let available = available_toggles(game);
+  let requested = [
+    ("mutant_kill", request.mutant_kill),
+    ("physician_kill", request.physician_kill),
+    ("traitor_signal", request.traitor_signal),
+    ("auto_cure_physician", request.auto_cure_physician),
+    ("auto_kill_physician", request.auto_kill_physician),
+  ];
+  if let Some((toggle, _)) = requested.iter().find(|(toggle, value)| value.is_some() && !available.contains(toggle)) {
+    return error(403, format!("{toggle} is not available to the player").as_str());
+  }
+  let player = game.get_current_player();
+  let auto_cure_physician = request.auto_cure_physician.unwrap_or(player.auto_cure_physician);
+  let auto_kill_physician = request.auto_kill_physician.unwrap_or(player.auto_kill_physician);
+  if auto_cure_physician && auto_kill_physician { // the menu offers to cure, to kill, or nothing
+    return error(400, "auto_cure_physician and auto_kill_physician cannot be both set");
+  }
+  let player = game.get_mut_current_player();
+  player.mutant_kill = request.mutant_kill.unwrap_or(player.mutant_kill);
+  player.physician_kill = request.physician_kill.unwrap_or(player.physician_kill);
+  player.traitor_signal = request.traitor_signal.unwrap_or(player.traitor_signal);
+  (player.auto_cure_physician, player.auto_kill_physician) = (auto_cure_physician, auto_kill_physician);
+  json_response(200, describe_player(game))
+}
+
+// Same rules as the menu of the captain (see captain::add_actions)
+fn set_successor (game: &mut dyn PlayerGame, request: SuccessorRequest) -> JsonResponse {
+  let player = game.get_current_player();
+  if !player.alive || game.get_captain() != Some(player.id) {
+    return error(403, "Only the captain chooses their successor");
+  }
+  let successor = match request.target {
+    Some(index) => match game.get_crew().into_iter().find(|candidate| candidate.id.index() == index && candidate.id != player.id) {
+      Some(candidate) => Some(candidate.id),
+      None => return error(400, format!("No living player {index} other than the captain").as_str()),
+    },
+    None => None,
+  };
+  game.set_captain_successor(successor);
+  json_response(200, describe_player(game))
+}
+
+// Same rules as the menu of the hacker (see hacker::add_actions)
+fn set_hacker_target (game: &mut dyn PlayerGame, request: HackerTargetRequest) -> JsonResponse {
+  let player = game.get_current_player();
+  if !player.alive || !player.can(RoleAction::Hack) {
+    return error(403, "Only the hacker chooses a role to hack");
+  }
+  let hacker_target = match request.role {
+    Some(id) => match game.get_hackable_roles().into_iter().find(|role| role.id == id) {
+      Some(role) => Some(role),
+      None => return error(400, format!("No hackable role {id}").as_str()),
+    },
+    None => None,
+  };
+  game.get_mut_current_player().hacker_target = hacker_target;
+  json_response(200, describe_player(game))
+}
+
+// Same rules as the menu of the player (see display_player_menu)
+fn target_actions (game: &dyn PlayerGame) -> Vec<ActionType> {
+  let player = game.get_current_player();
+  let mut actions = Vec::new();
+  if !player.alive {
+    return actions;
+  }
+  if game.get_phase_of_day() == &PhaseOfDay::Day {
+    match game.get_captain() {
+      Some(_) => actions.push(ActionType::Eliminate),
+      None => actions.push(ActionType::ElectCaptain),
+    }
+  }
+  for action in &player.role.actions {
+    match action {
+      RoleAction::Psychoanalyze => actions.push(ActionType::Psychoanalyze),
+      RoleAction::Cure if !player.infected => actions.push(ActionType::Cure),
+      RoleAction::Genomyze => actions.push(ActionType::Genomyze),
+      RoleAction::Spy => actions.push(ActionType::Spy),
+      _ => (),
+    }
+  }
+  if player.infected {
+    actions.append(&mut vec![ActionType::Infect, ActionType::Paralyze]);
+  }
+  actions
+}
+
+fn toggle_values (player: &Player) -> [(&'static str, bool); 5] {
+  [
+    ("mutant_kill", player.mutant_kill),
+    ("physician_kill", player.physician_kill),
+    ("traitor_signal", player.traitor_signal),
+    ("auto_cure_physician", player.auto_cure_physician),
+    ("auto_kill_physician", player.auto_kill_physician),
+  ]
+}
+
+fn available_toggles (game: &dyn PlayerGame) -> Vec<&'static str> {
+  let player = game.get_current_player();
+  let mut toggles = Vec::new();
+  if !player.alive {
+    return toggles;
+  }
+  if player.infected {
+    toggles.push("mutant_kill");
+  } else {
+    if player.can(RoleAction::Cure) {
+      toggles.append(&mut vec!["physician_kill", "auto_cure_physician", "auto_kill_physician"]);
+    }
+    if player.can(RoleAction::SignalMutants) {
+      toggles.push("traitor_signal");
+    }
+  }
+  toggles
+}
+
+// Nobody is at the terminal during the ceremonies: the given answers are used first, then the validations pass and the choices are drawn
+// The wrong passwords count toward the lockout of the terminal, like the wrong codes
+// The phases the game master runs, the same as on the terminal (see game_master_actions)
+enum Ceremony {
+  Election,
+  EndOfDay,
+  Night,
+}
+
+fn run_phase (game: &mut GameStatus, terminal: Terminal, token: Option<String>, body: &str, ceremony: Ceremony) -> JsonResponse {
+  let Some(password) = game.get_config().gm_password.clone() else {
+    return error(403, "The game has no game master password, the phases can only be run from the terminal");
+  };
+  if let Some(remaining) = game.get_login_guard(terminal).locked_for(Instant::now()) {
+    return json_response(429, json!({ "error": "Locked after too many invalid passwords", "retry_after": remaining.as_secs() + 1 }));
+  }
+  if token.as_ref() != Some(&password) {
+    let rules = game.get_config().login.clone();
+    return match game.get_login_guard(terminal).record_failure(&rules, Instant::now()) {
+      Some(lockout) => json_response(429, json!({ "error": "Invalid game master password, locked after too many of them", "retry_after": lockout.as_secs() })),
+      None => error(401, "Invalid game master password"),
+    };
+  }
+  game.get_login_guard(terminal).record_success();
+  let request: PhaseRequest = match body.trim() {
+    "" => PhaseRequest::default(),
+    body => match serde_json::from_str(body) {
+      Ok(request) => request,
+      Err(parse_error) => return error(400, format!("Invalid request: {parse_error}").as_str()),
+    },
+  };
+  if game.game_master_busy() { // the terminal steps out of the lock while it waits, it must not be overtaken
+    return error(409, "The game master is running an action on the terminal");
+  }
+  let run: fn (&mut GameAccess, &mut dyn Interface) -> Result<(), Idle> = match (ceremony, game.get_phase_of_day(), game.get_captain()) {
+    (Ceremony::Election, PhaseOfDay::Day, None) => run_captain_election,
+    (Ceremony::EndOfDay, PhaseOfDay::Day, Some(_)) => run_end_of_day,
+    (Ceremony::EndOfDay, PhaseOfDay::Day, None) => return error(409, "The captain must be elected first"),
+    (Ceremony::Night, PhaseOfDay::Twilight, _) => run_night,
+    _ => return error(409, "The game is not in this phase"),
+  };
+  let missing_players = missing_players(game);
+  if game.get_phase_of_day() == &PhaseOfDay::Day && !missing_players.is_empty() { // the terminal would ask for them too
+    return json_response(409, json!({ "error": "Some players did not play yet", "missing_players": missing_players }));
+  }
+
+  let first_event = game.get_events().len();
+  let mut interface = HeadlessInterface::new(rand::random()); // not the game's rng, the game unfolds as on the terminal
+  interface.mock(request.answers);
+  let _ = run(&mut GameAccess::Owned(game), &mut interface); // the headless interface never gives up
+  let events: Vec<String> = game.get_events()[first_event..].iter().map(|event| event.describe(game)).collect();
+  json_response(200, json!({ "game": describe_game(game), "events": events }))
+}
+
+fn error (status: u16, message: &str) -> JsonResponse {
+  json_response(status, json!({ "error": message }))
+}
+
+fn json_response (status: u16, value: Value) -> JsonResponse {
+  Response::from_string(value.to_string())
+    .with_status_code(status)
+    .with_header(Header::from_bytes("Content-Type", "application/json").unwrap())
+    .with_header(Header::from_bytes("Cache-Control", "no-store").unwrap()) // the secrets of the players
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::{Arc, Mutex};
+
+  use rand::SeedableRng;
+
+  use super::*;
+  use crate::config::GameConfig;
+  use crate::game::GameRng;
+  use crate::role::RoleCatalog;
+  use crate::server::http::HttpServer;
+
+  // The status and the body of the response, whatever the status
+  fn call (request: ureq::Request, body: Option<Value>) -> (u16, Value) {
+    let response = match body {
+      Some(body) => request.send_string(&body.to_string()),
+      None => request.call(),
+    };
+    let response = match response {
+      Ok(response) => response,
+      Err(ureq::Error::Status(_, response)) => response,
+      Err(error) => panic!("{error}"),
+    };
+    (response.status(), serde_json::from_str(&response.into_string().unwrap()).unwrap())
+  }
+
+  #[test]
+  fn frontends_play_through_the_api() {
+    let catalog = RoleCatalog::default();
+    let players = ["patient0", "physician", "astronaut"].iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let config = GameConfig { gm_password: Some(String::from("mj")), ..Default::default() };
+    let game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), config);
+    let game = Arc::new(Mutex::new(game));
+    let server = HttpServer::start("127.0.0.1:0", game.clone()).unwrap();
+    let url = format!("http://{}/api", server.address());
+    let log_in = |code: &str| {
+      let (status, body) = call(ureq::post(&format!("{url}/login")), Some(json!({ "code": code })));
+      assert_eq!(status, 200);
+      format!("Bearer {}", body["token"].as_str().unwrap())
+    };
+
+    let (_, body) = call(ureq::get(&format!("{url}/game")), None);
+    assert_eq!((body["name"].as_str(), body["phase"].as_str(), body["players"].as_array().unwrap().len()), (Some("Koursk"), Some("day"), 3));
+    assert_eq!(call(ureq::post(&format!("{url}/login")), Some(json!({ "code": "1234" }))).0, 401);
+    assert_eq!(call(ureq::get(&format!("{url}/player")), None).0, 401);
+
+    let mutant = log_in("0");
+    let (_, body) = call(ureq::get(&format!("{url}/player")).set("Authorization", &mutant), None);
+    assert_eq!((body["infected"].as_bool(), &body["targets"]["Infect"], &body["toggles"]["mutant_kill"]), (Some(true), &Value::Null, &json!(false)));
+    let (status, body) = call(ureq::put(&format!("{url}/player/targets")).set("Authorization", &mutant), Some(json!({ "action": "Infect", "target": 2 })));
+    assert_eq!((status, &body["targets"]["Infect"]), (200, &json!(2)));
+    assert_eq!(call(ureq::put(&format!("{url}/player/toggles")).set("Authorization", &mutant), Some(json!({ "mutant_kill": true }))).0, 200);
+    assert_eq!(call(ureq::put(&format!("{url}/player/toggles")).set("Authorization", &mutant), Some(json!({ "physician_kill": true }))).0, 403);
+    assert_eq!(call(ureq::put(&format!("{url}/player/targets")).set("Authorization", &mutant), Some(json!({ "action": "Cure", "target": 2 }))).0, 403);
+    assert!(game.lock().unwrap().get_all_players().next().unwrap().mutant_kill);
+
+    // The day starts with the election of the captain, once everyone voted, and only for the game master
+    assert_eq!(call(ureq::post(&format!("{url}/game/end-of-day")).set("Authorization", "Bearer mj"), None).0, 409);
+    assert_eq!(call(ureq::post(&format!("{url}/game/election")).set("Authorization", "Bearer 1234"), None).0, 401);
+    let (status, body) = call(ureq::post(&format!("{url}/game/election")).set("Authorization", "Bearer mj"), None);
+    assert_eq!((status, body["missing_players"].as_array().unwrap().len()), (409, 3));
+    let players = [&mutant, &log_in("2"), &log_in("1")];
+    for player in players {
+      call(ureq::put(&format!("{url}/player/targets")).set("Authorization", player), Some(json!({ "action": "ElectCaptain", "target": 2 })));
+    }
+    let (status, body) = call(ureq::post(&format!("{url}/game/election")).set("Authorization", "Bearer mj"), None);
+    assert_eq!((status, &body["game"]["captain"]), (200, &json!(2)));
+    assert_eq!(call(ureq::post(&format!("{url}/game/election")).set("Authorization", "Bearer mj"), None).0, 409);
+
+    // Then the crew votes for the elimination
+    for player in players {
+      call(ureq::put(&format!("{url}/player/targets")).set("Authorization", player), Some(json!({ "action": "Eliminate", "target": 1 })));
+    }
+    let (status, body) = call(ureq::post(&format!("{url}/game/end-of-day")).set("Authorization", "Bearer mj"), None);
+    assert_eq!((status, body["game"]["phase"].as_str(), &body["game"]["players"][1]["alive"]), (200, Some("twilight"), &json!(false)));
+    assert!(!body["events"].as_array().unwrap().is_empty());
+    assert_eq!(call(ureq::post(&format!("{url}/game/end-of-day")).set("Authorization", "Bearer mj"), None).0, 409);
+
+    // The mutant kills the last human at night
+    let (_, body) = call(ureq::post(&format!("{url}/game/night")).set("Authorization", "Bearer mj"), None);
+    assert_eq!((&body["game"]["players"][2]["alive"], &body["game"]["ended"]), (&json!(false), &json!(true)));
+    assert_eq!(call(ureq::post(&format!("{url}/logout")).set("Authorization", &mutant), None).0, 409);
+    server.stop();
+  }
+
+  #[test]
+  fn players_make_every_choice_of_their_menu() {
+    let catalog = RoleCatalog::default();
+    let players = ["patient0", "physician", "hacker", "spy"].iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let config = GameConfig { gm_password: Some(String::from("mj")), ..Default::default() };
+    let mut game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), config);
+    let ids = game.get_player_ids(&|_| true);
+    game.set_captain(Some(ids[1]));
+    let game = Arc::new(Mutex::new(game));
+    let server = HttpServer::start("127.0.0.1:0", game.clone()).unwrap();
+    let url = format!("http://{}/api", server.address());
+    let log_in = |code: &str| {
+      let (_, body) = call(ureq::post(&format!("{url}/login")), Some(json!({ "code": code })));
+      format!("Bearer {}", body["token"].as_str().unwrap())
+    };
+    let (physician, hacker) = (log_in("1"), log_in("2"));
+
+    // The captain chooses their successor, among the others
+    let (_, body) = call(ureq::get(&format!("{url}/player")).set("Authorization", &physician), None);
+    assert_eq!((&body["successor"], &body["toggles"]["auto_cure_physician"]), (&Value::Null, &json!(true)));
+    assert_eq!(call(ureq::put(&format!("{url}/player/successor")).set("Authorization", &physician), Some(json!({ "target": 1 }))).0, 400);
+    let (status, body) = call(ureq::put(&format!("{url}/player/successor")).set("Authorization", &physician), Some(json!({ "target": 3 })));
+    assert_eq!((status, &body["successor"]), (200, &json!(3)));
+    assert_eq!(call(ureq::put(&format!("{url}/player/successor")).set("Authorization", &hacker), Some(json!({ "target": 3 }))).0, 403);
+
+    // The physician chooses what happens to the inactive physicians
+    let body = json!({ "auto_kill_physician": true });
+    assert_eq!(call(ureq::put(&format!("{url}/player/toggles")).set("Authorization", &physician), Some(body)).0, 400);
+    let body = json!({ "auto_cure_physician": false, "auto_kill_physician": true });
+    let (status, body) = call(ureq::put(&format!("{url}/player/toggles")).set("Authorization", &physician), Some(body));
+    assert_eq!((status, &body["toggles"]["auto_kill_physician"]), (200, &json!(true)));
+    assert_eq!(call(ureq::put(&format!("{url}/player/toggles")).set("Authorization", &hacker), Some(json!({ "auto_cure_physician": true }))).0, 403);
+
+    // The hacker chooses among the roles that send reports
+    let (_, body) = call(ureq::get(&format!("{url}/player")).set("Authorization", &hacker), None);
+    assert_eq!((&body["hacker_target"], body["hackable_roles"].as_array().unwrap().len()), (&Value::Null, 1));
+    assert_eq!(call(ureq::put(&format!("{url}/player/hacker-target")).set("Authorization", &hacker), Some(json!({ "role": "patient0" }))).0, 400);
+    let (status, body) = call(ureq::put(&format!("{url}/player/hacker-target")).set("Authorization", &hacker), Some(json!({ "role": "spy" })));
+    assert_eq!((status, &body["hacker_target"]), (200, &json!("spy")));
+    assert_eq!(call(ureq::put(&format!("{url}/player/hacker-target")).set("Authorization", &physician), Some(json!({ "role": "spy" }))).0, 403);
+    assert_eq!(game.lock().unwrap().get_player(ids[2]).hacker_target.as_ref().map(|role| role.id.as_str()), Some("spy"));
+
+    // The wrong game master passwords lock the device out, even for the right one
+    for _ in 0..2 {
+      assert_eq!(call(ureq::post(&format!("{url}/game/night")).set("Authorization", "Bearer 1234"), None).0, 401);
+    }
+    assert_eq!(call(ureq::post(&format!("{url}/game/night")).set("Authorization", "Bearer 1234"), None).0, 429);
+    assert_eq!(call(ureq::post(&format!("{url}/game/night")).set("Authorization", "Bearer mj"), None).0, 429);
+    server.stop();
+  }
+}
@@ -0,0 +1,234 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use tiny_http::Request;
+
+use crate::game::{Game, GameStatus};
+use crate::player::PlayerId;
+use crate::session::SessionId;
+
+use super::SharedGame;
+
+// How often the game is checked for news, and how often the idle streams are checked for closed connections
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+struct Subscriber {
+  session: Option<SessionId>, // only the public news for the anonymous streams (the big screen, the login page), and once the player logged out
+  stream: Box<dyn Write + Send>,
+}
+
+// What was already announced: the news are the differences with the game
+struct Seen {
+  date: u32,
+  alive: Vec<bool>,
+  messages: Vec<usize>,
+  ended: bool,
+}
+
+impl Seen {
+  fn new (game: &GameStatus) -> Seen {
+    Seen {
+      date: game.get_date(),
+      alive: game.get_all_players().map(|player| player.alive).collect(),
+      messages: game.get_all_players().map(|player| player.messages.len()).collect(),
+      ended: game.ended(),
+    }
+  }
+}
+
+// Server-sent events: the news are pushed to the connected browsers and frontends (see API.md), nobody has to go back to check
+pub struct EventStreams {
+  subscribers: Arc<Mutex<Vec<Subscriber>>>,
+  stop: Sender<()>,
+  thread: JoinHandle<()>,
+}
+
+impl EventStreams {
+  pub fn start (game: SharedGame) -> EventStreams {
+    let subscribers: Arc<Mutex<Vec<Subscriber>>> = Arc::new(Mutex::new(Vec::new()));
+    let (stop, stopped) = mpsc::channel();
+    let thread = thread::spawn({
+      let subscribers = subscribers.clone();
+      move || {
+        let mut seen = Seen::new(&game.lock().unwrap());
+        let mut last_write = Instant::now();
+        while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(CHECK_INTERVAL) {
+          let game = game.lock().unwrap();
+          let news = find_news(&game, &seen);
+          seen = Seen::new(&game);
+          if news.is_empty() && last_write.elapsed() < KEEP_ALIVE_INTERVAL {
+            continue;
+          }
+          last_write = Instant::now();
+          // The sessions are resolved at each check, the private news stop as soon as the player logged out
+          let mut current = std::mem::take(&mut *subscribers.lock().unwrap());
+          let texts: Vec<String> = current.iter().map(|subscriber| {
+            let player = subscriber.session.as_ref().and_then(|session| game.get_session_player(session));
+            let mut text = String::from(":\n\n"); // a comment, so even the streams without news are checked
+            for (recipient, kind, data) in &news {
+              if recipient.is_none() || *recipient == player {
+                text.push_str(format!("event: {kind}\ndata: {data}\n\n").as_str());
+              }
+            }
+            text
+          }).collect();
+          drop(game);
+          // Written without any lock, a slow client holds neither the game nor the new subscriptions
+          // The streams that cannot be written are closed, the browser will open a new one if it is still there
+          let mut texts = texts.iter();
+          current.retain_mut(|subscriber| {
+            let text = texts.next().unwrap();
+            subscriber.stream.write_all(text.as_bytes()).and_then(|_| subscriber.stream.flush()).is_ok()
+          });
+          subscribers.lock().unwrap().append(&mut current);
+        }
+      }
+    });
+    EventStreams { subscribers, stop, thread }
+  }
+
+  // The response never ends, so it is written by hand: the responses of tiny_http are sent once complete
+  pub fn subscribe (&self, request: Request, session: Option<SessionId>) {
+    let mut stream = request.into_writer();
+    let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-store\r\nConnection: close\r\n\r\nretry: 3000\n\n";
+    if stream.write_all(head.as_bytes()).and_then(|_| stream.flush()).is_ok() {
+      self.subscribers.lock().unwrap().push(Subscriber { session, stream });
+    }
+  }
+
+  pub fn stop (self) {
+    let _ = self.stop.send(());
+    let _ = self.thread.join();
+    self.subscribers.lock().unwrap().clear(); // closes the streams
+  }
+}
+
+// The news, with the player they are meant for (none for everyone): the deaths, the new day, the new messages and the end of the game
+fn find_news (game: &GameStatus, seen: &Seen) -> Vec<(Option<PlayerId>, &'static str, Value)> {
+  let mut news = Vec::new();
+  for player in game.get_all_players() {
+    if !player.alive && seen.alive.get(player.id.index()) == Some(&true) {
+      let death_cause = player.death_cause.clone().unwrap_or_default();
+      news.push((None, "death", json!({
+        "player": player.id.index(),
+        "name": player.name,
+        "death_cause": death_cause,
+        "text": format!("{} n'est plus parmi nous: {}", player.name, death_cause),
+      })));
+    }
+  }
+  if game.get_date() > seen.date {
+    news.push((None, "day", json!({
+      "date": game.get_date(),
+      "text": format!("Une nouvelle journée commence: nous sommes le {}ème jour après détection de l'infection", game.get_date()),
+    })));
+  }
+  for player in game.get_all_players() {
+    let count = player.messages.len().saturating_sub(seen.messages.get(player.id.index()).copied().unwrap_or_default());
+    if count > 0 {
+      news.push((Some(player.id), "messages", json!({
+        "count": count,
+        "text": if count == 1 { String::from("Vous avez un nouveau message") } else { format!("Vous avez {count} nouveaux messages") },
+      })));
+    }
+  }
+  if let (Some(reason), false) = (game.get_end_reason(), seen.ended) {
+    news.push((None, "end", json!({
+      "reason": reason.to_string(),
+      "text": format!("La partie est terminée: {reason}"),
+    })));
+  }
+  news
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::{BufRead, BufReader};
+  use std::net::TcpStream;
+
+  use rand::SeedableRng;
+
+  use super::*;
+  use crate::game::GameRng;
+  use crate::message::Message;
+  use crate::player::Player;
+  use crate::role::RoleCatalog;
+  use crate::server::http::HttpServer;
+
+  fn open_stream (server: &HttpServer, authorization: Option<&str>) -> BufReader<TcpStream> {
+    let mut stream = TcpStream::connect(server.address()).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+    let authorization = authorization.map(|authorization| format!("Authorization: {authorization}\r\n")).unwrap_or_default();
+    stream.write_all(format!("GET /events HTTP/1.1\r\nHost: sporz\r\n{authorization}\r\n").as_bytes()).unwrap();
+    let mut stream = BufReader::new(stream);
+    read_until(&mut stream, "retry:");
+    stream
+  }
+
+  // The events received until the line containing the text, the comments are skipped
+  fn read_until (stream: &mut BufReader<TcpStream>, text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    loop {
+      let mut line = String::new();
+      stream.read_line(&mut line).unwrap();
+      if line.starts_with("event:") {
+        lines.push(line.trim().to_string());
+      }
+      if line.contains(text) {
+        return lines;
+      }
+    }
+  }
+
+  #[test]
+  fn news_are_pushed_to_the_connected_clients() {
+    let catalog = RoleCatalog::default();
+    let players = ["patient0", "physician", "astronaut"].iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), Default::default());
+    let players = game.get_player_ids(&|_| true);
+    let game = Arc::new(Mutex::new(game));
+    let server = HttpServer::start("127.0.0.1:0", game.clone()).unwrap();
+    let login = ureq::post(&format!("http://{}/api/login", server.address())).send_string("{\"code\": \"2\"}").unwrap();
+    let token: Value = serde_json::from_str(&login.into_string().unwrap()).unwrap();
+    let authorization = format!("Bearer {}", token["token"].as_str().unwrap());
+    let mut player_stream = open_stream(&server, Some(authorization.as_str()));
+    let mut public_stream = open_stream(&server, None);
+
+    {
+      let mut game = game.lock().unwrap();
+      game.get_mut_player(players[1]).die(1, String::from("Tué·e par les mutants"));
+      game.send_message(players[2], String::from("Ordinateur Central"), String::from("Bonjour"));
+      game.prepare_new_turn();
+    }
+    assert_eq!(read_until(&mut player_stream, "nouveau message"), vec!["event: death", "event: day", "event: messages"]);
+    assert_eq!(read_until(&mut public_stream, "jour après"), vec!["event: death", "event: day"]);
+
+    // The messages are only announced to their recipients
+    {
+      let mut game = game.lock().unwrap();
+      game.broadcast(Message { date: 2, source: String::from("Ordinateur Central"), content: String::from("Au revoir") });
+      game.prepare_new_turn();
+    }
+    assert_eq!(read_until(&mut player_stream, "nouveau message"), vec!["event: day", "event: messages"]);
+    assert_eq!(read_until(&mut public_stream, "jour après"), vec!["event: day"]);
+
+    // Once the player logged out, their stream only gets the news for everyone
+    ureq::post(&format!("http://{}/api/logout", server.address())).set("Authorization", &authorization).call().unwrap();
+    {
+      let mut game = game.lock().unwrap();
+      game.send_message(players[2], String::from("Ordinateur Central"), String::from("Bonjour"));
+      game.prepare_new_turn();
+    }
+    assert_eq!(read_until(&mut player_stream, "jour après"), vec!["event: day"]);
+    game.lock().unwrap().prepare_new_turn(); // the news of a day come before its messages
+    assert_eq!(read_until(&mut player_stream, "jour après"), vec!["event: day"]);
+    server.stop();
+  }
+}
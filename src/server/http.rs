@@ -0,0 +1,328 @@
+use std::error;
+use std::io::Cursor;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::game::{Game, GameStatus};
+use crate::interface::{Idle, colors::strip_colors, web::{Prompt, WebInterface}};
+use crate::login::{log_in, LogIn, Terminal};
+use crate::menu::display_player_menu;
+use crate::session::SessionId;
+
+use super::{SharedGame, api, events::EventStreams};
+
+const SESSION_COOKIE: &str = "sporz-session";
+
+type HttpResponse = Response<Cursor<Vec<u8>>>;
+
+// The news pushed by the server (see EventStreams) are displayed on top of the page, the page itself is not reloaded while the player is typing
+const NEWS_SCRIPT: &str = r#"<script>
+const news = new EventSource("/events");
+for (const kind of ["death", "day", "messages", "end"]) {
+  news.addEventListener(kind, (event) => {
+    const item = document.createElement("p");
+    item.innerHTML = "<strong></strong> <a href=\"/\">Actualiser</a>";
+    item.querySelector("strong").textContent = JSON.parse(event.data).text;
+    document.getElementById("news").prepend(item);
+  });
+}
+</script>"#;
+
+// Serves the menus of the players to their browsers, and the json api under /api/: the requests are handled one at a time, each one with the game locked
+pub struct HttpServer {
+  server: Arc<Server>,
+  thread: JoinHandle<()>,
+}
+
+impl HttpServer {
+  pub fn start (address: &str, game: SharedGame) -> Result<HttpServer, Box<dyn error::Error>> {
+    let server = Arc::new(Server::http(address).map_err(|error| format!("Cannot listen on {address}: {error}"))?);
+    let requests = server.clone();
+    let thread = thread::spawn(move || {
+      let events = EventStreams::start(game.clone());
+      for mut request in requests.incoming_requests() {
+        if (request.method(), request.url()) == (&Method::Get, "/events") {
+          // The browsers send their cookie, the other frontends their token
+          let session = cookie(&request, SESSION_COOKIE).or_else(|| bearer_token(&request)).map(|token| SessionId::from_token(&token));
+          events.subscribe(request, session);
+          continue;
+        }
+        let response = handle_request(&mut request, &game);
+        let _ = request.respond(response); // the browser may be gone already, nothing to do about it
+      }
+      events.stop();
+    });
+    Ok(HttpServer { server, thread })
+  }
+
+  pub fn address (&self) -> SocketAddr {
+    self.server.server_addr().to_ip().unwrap() // always listening on an ip address
+  }
+
+  pub fn stop (self) {
+    self.server.unblock();
+    let _ = self.thread.join();
+  }
+}
+
+fn handle_request (request: &mut Request, game: &SharedGame) -> HttpResponse {
+  let mut body = String::new();
+  if request.as_reader().read_to_string(&mut body).is_err() {
+    return page(400, String::from("<p>Requête invalide</p>"));
+  }
+  let form: Vec<(String, String)> = form_urlencoded::parse(body.as_bytes()).into_owned().collect();
+
+  let mut game = game.lock().unwrap();
+  if request.url().starts_with("/api/") {
+    return api::handle_request(request, &body, &mut game);
+  }
+  let session = cookie(request, SESSION_COOKIE)
+    .map(|token| SessionId::from_token(&token))
+    .filter(|session| game.get_session_player(session).is_some());
+  if game.ended() {
+    return page(200, String::from("<p>La partie est terminée, rendez-vous sur le terminal de control.</p>"));
+  }
+  match (request.method(), request.url(), session) {
+    (Method::Get, "/", None) => login_page(None),
+    (Method::Get, "/", Some(session)) => play(&mut game, session, Vec::new()),
+    (Method::Post, "/", Some(session)) => {
+      // Each answer comes with the fingerprint of the screen it answered, a missing one never matches
+      let screens: Vec<String> = form.iter().filter(|(name, _)| name == "screen").map(|(_, screen)| screen.clone()).collect();
+      let answers = form.iter().filter(|(name, _)| name == "answer").enumerate()
+        .map(|(idx, (_, answer))| (answer.clone(), screens.get(idx).cloned().unwrap_or_default()))
+        .collect();
+      play(&mut game, session, answers)
+    },
+    (Method::Post, "/", None) => redirect(), // the session is over, back to the identification
+    (Method::Post, "/login", _) => {
+      let code = form.into_iter().find(|(name, _)| name == "code").map(|(_, code)| code).unwrap_or_default();
+      log_in_player(&mut game, terminal(request), code)
+    },
+    _ => page(404, String::from("<p>Page introuvable</p>")),
+  }
+}
+
+fn log_in_player (game: &mut GameStatus, terminal: Terminal, code: String) -> HttpResponse {
+  if let Some(remaining) = game.get_login_guard(terminal).locked_for(Instant::now()) {
+    return login_page(Some(format!("Terminal verrouillé suite à plusieurs codes invalides, réessayez dans {} secondes.", remaining.as_secs() + 1)));
+  }
+  match log_in(game, terminal, code) {
+    LogIn::Success(player_id) => {
+      let session = game.open_session(player_id);
+      redirect().with_header(header("Set-Cookie", format!("{SESSION_COOKIE}={}; Path=/; HttpOnly; SameSite=Strict", session.as_str()).as_str()))
+    },
+    LogIn::Invalid => login_page(Some(String::from("Code invalide"))),
+    LogIn::Locked(lockout) => login_page(Some(format!("Code invalide. Tentative d'intrusion détectée: terminal verrouillé pendant {} secondes.", lockout.as_secs()))),
+  }
+}
+
+// The menu of the player is replayed with all the answers given on this screen, until it needs one more
+fn play (game: &mut GameStatus, session: SessionId, answers: Vec<(String, String)>) -> HttpResponse {
+  let mut interface = WebInterface::new(answers);
+  let result = display_player_menu(game, &mut interface, &session);
+  let logged_out = game.get_session_player(&session).is_none();
+  match result {
+    Ok(()) if logged_out => {
+      redirect().with_header(header("Set-Cookie", format!("{SESSION_COOKIE}=; Path=/; Max-Age=0").as_str()))
+    },
+    Ok(()) => redirect(), // the action is done, back to the status of the player
+    Err(Idle) => player_page(&interface),
+  }
+}
+
+fn login_page (error: Option<String>) -> HttpResponse {
+  let mut body = String::new();
+  if let Some(error) = error {
+    body.push_str(format!("<p><strong>{}</strong></p>\n", escape_html(&error)).as_str());
+  }
+  body.push_str("<form method=\"post\" action=\"/login\">\n");
+  body.push_str("<label>Entrez votre code d'identification: <input name=\"code\" type=\"password\" autocomplete=\"off\" autofocus></label>\n");
+  body.push_str("<button>Identification</button>\n</form>");
+  page(200, body)
+}
+
+fn player_page (interface: &WebInterface) -> HttpResponse {
+  let mut body = String::new();
+  if interface.changed() {
+    body.push_str("<p><strong>Le menu a changé depuis votre dernier choix, vérifiez-le avant de continuer.</strong></p>\n");
+  }
+  body.push_str(format!("<pre>{}</pre>\n<form method=\"post\" action=\"/\">\n", escape_html(&strip_colors(interface.screen()))).as_str());
+  for (answer, screen) in interface.given_answers() {
+    body.push_str(format!("<input type=\"hidden\" name=\"answer\" value=\"{}\">\n", escape_html(answer)).as_str());
+    body.push_str(format!("<input type=\"hidden\" name=\"screen\" value=\"{}\">\n", escape_html(screen)).as_str());
+  }
+  body.push_str(format!("<input type=\"hidden\" name=\"screen\" value=\"{}\">\n", escape_html(interface.fingerprint())).as_str());
+  match interface.prompt() {
+    Some(Prompt::Choice { message, options }) => {
+      body.push_str(format!("<p>{}</p>\n", escape_html(&strip_colors(message))).as_str());
+      for (answer, text) in options {
+        body.push_str(format!("<button name=\"answer\" value=\"{}\">{}</button>\n", escape_html(answer), escape_html(&strip_colors(text))).as_str());
+      }
+    },
+    Some(Prompt::Validate { message }) => {
+      body.push_str(format!("<p>{}</p>\n<button name=\"answer\" value=\"\">OK</button>\n", escape_html(&strip_colors(message))).as_str());
+    },
+    Some(Prompt::Text { message }) => {
+      body.push_str(format!("<label>{} <input name=\"answer\" autocomplete=\"off\" autofocus></label>\n<button>Valider</button>\n", escape_html(&strip_colors(message))).as_str());
+    },
+    None => (), // the menus always end with an input
+  }
+  body.push_str("</form>");
+  page(200, body)
+}
+
+fn page (status: u16, body: String) -> HttpResponse {
+  let html = format!("<!DOCTYPE html>
+<html lang=\"fr\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>Sporz</title>
+<style>body {{ font-family: monospace; }} pre {{ white-space: pre-wrap; }} button {{ display: block; width: 100%; margin: 0.5em 0; padding: 0.8em; }}</style>
+</head>
+<body>
+<div id=\"news\"></div>
+{body}
+{NEWS_SCRIPT}
+</body>
+</html>
+");
+  Response::from_string(html)
+    .with_status_code(status)
+    .with_header(header("Content-Type", "text/html; charset=utf-8"))
+    .with_header(header("Cache-Control", "no-store")) // the pages of the players are secret
+}
+
+fn redirect () -> HttpResponse {
+  Response::from_string(String::new()).with_status_code(303).with_header(header("Location", "/"))
+}
+
+fn header (field: &str, value: &str) -> Header {
+  Header::from_bytes(field.as_bytes(), value.as_bytes()).unwrap() // only called with valid headers
+}
+
+fn cookie (request: &Request, name: &str) -> Option<String> {
+  request.headers().iter()
+    .filter(|header| header.field.equiv("Cookie"))
+    .flat_map(|header| header.value.as_str().split(';'))
+    .find_map(|cookie| cookie.trim().strip_prefix(name)?.strip_prefix('=').map(String::from))
+}
+
+// The wrong codes lock the device that sent them, not the other terminals
+pub fn terminal (request: &Request) -> Terminal {
+  Terminal::Remote(request.remote_addr().map(|address| address.ip()).unwrap_or(Ipv4Addr::UNSPECIFIED.into()))
+}
+
+pub fn bearer_token (request: &Request) -> Option<String> {
+  request.headers().iter()
+    .find(|header| header.field.equiv("Authorization"))
+    .and_then(|header| header.value.as_str().strip_prefix("Bearer "))
+    .map(|token| token.trim().to_string())
+}
+
+fn escape_html (text: &str) -> String {
+  text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Mutex;
+
+  use rand::SeedableRng;
+
+  use super::*;
+  use crate::action::ActionType;
+  use crate::game::GameRng;
+  use crate::player::Player;
+  use crate::role::RoleCatalog;
+
+  fn start_server (roles: &[&str]) -> (SharedGame, HttpServer) {
+    let catalog = RoleCatalog::default();
+    let players = roles.iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let mut game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), Default::default());
+    let captain = game.get_player_ids(&|_| true)[1];
+    game.set_captain(Some(captain)); // elected, the crew votes for the elimination
+    let game = Arc::new(Mutex::new(game));
+    let server = HttpServer::start("127.0.0.1:0", game.clone()).unwrap();
+    (game, server)
+  }
+
+  // What the browser sends when the player clicks an answer: the hidden fields of the page, then the answer
+  fn submit (client: &ureq::Agent, url: &str, cookie: &str, page: &str, answer: &str) -> ureq::Response {
+    let mut form: Vec<(&str, &str)> = page.split("<input type=\"hidden\" name=\"").skip(1)
+      .map(|field| {
+        let (name, rest) = field.split_once('"').unwrap();
+        let value = rest.split("value=\"").nth(1).unwrap().split('"').next().unwrap();
+        (name, value)
+      })
+      .collect();
+    form.push(("answer", answer));
+    client.post(url).set("Cookie", cookie).send_form(&form).unwrap()
+  }
+
+  #[test]
+  fn players_play_from_their_browser() {
+    let (game, server) = start_server(&["patient0", "physician", "astronaut"]);
+    let players = game.lock().unwrap().get_player_ids(&|_| true);
+    let url = format!("http://{}", server.address());
+    let client = ureq::AgentBuilder::new().redirects(0).build();
+
+    assert!(client.get(&url).call().unwrap().into_string().unwrap().contains("code d'identification"));
+    let page = client.post(&format!("{url}/login")).send_form(&[("code", "1234")]).unwrap().into_string().unwrap();
+    assert!(page.contains("Code invalide"));
+    let response = client.post(&format!("{url}/login")).send_form(&[("code", "2")]).unwrap();
+    assert_eq!(response.status(), 303);
+    let cookie = response.header("Set-Cookie").unwrap().split(';').next().unwrap().to_string();
+    let page = client.get(&url).set("Cookie", &cookie).call().unwrap().into_string().unwrap();
+    assert!(page.contains("Bienvenue player-2"));
+
+    // Voting takes two answers: the action, then the target
+    let page = submit(&client, &url, &cookie, &page, "1").into_string().unwrap();
+    assert!(page.contains("Choisissez un·e camarade à éliminer"));
+    let response = submit(&client, &url, &cookie, &page, "0");
+    assert_eq!(response.status(), 303);
+    assert_eq!(game.lock().unwrap().get_player(players[2]).get_target(&ActionType::Eliminate), Some(&players[0]));
+
+    // The answers of an older page are not replayed blindly
+    let response = client.post(&url).set("Cookie", &cookie).send_form(&[("answer", "0")]).unwrap();
+    assert!(response.into_string().unwrap().contains("Le menu a changé"));
+
+    let page = client.get(&url).set("Cookie", &cookie).call().unwrap().into_string().unwrap();
+    let response = submit(&client, &url, &cookie, &page, "0"); // log out
+    assert!(response.header("Set-Cookie").unwrap().contains("Max-Age=0"));
+    let page = client.get(&url).set("Cookie", &cookie).call().unwrap().into_string().unwrap();
+    assert!(page.contains("code d'identification"));
+    server.stop();
+  }
+
+  #[test]
+  fn answers_are_asked_again_when_the_menu_changed() {
+    let (game, server) = start_server(&["patient0", "physician", "astronaut", "astronaut"]);
+    let players = game.lock().unwrap().get_player_ids(&|_| true);
+    let url = format!("http://{}", server.address());
+    let client = ureq::AgentBuilder::new().redirects(0).build();
+    let response = client.post(&format!("{url}/login")).send_form(&[("code", "3")]).unwrap();
+    let cookie = response.header("Set-Cookie").unwrap().split(';').next().unwrap().to_string();
+    let page = client.get(&url).set("Cookie", &cookie).call().unwrap().into_string().unwrap();
+    let targets = submit(&client, &url, &cookie, &page, "1").into_string().unwrap();
+    assert!(targets.contains("value=\"1\">player-1<"));
+
+    // The first target dies while the player is choosing: the second answer would now be someone else
+    game.lock().unwrap().get_mut_player(players[0]).die(1, String::from("Aspiré·e accidentellement par le sas tribord"));
+    let page = submit(&client, &url, &cookie, &targets, "1").into_string().unwrap();
+    assert!(page.contains("Le menu a changé"));
+    assert!(page.contains("Choisissez un·e camarade à éliminer"));
+    assert_eq!(game.lock().unwrap().get_player(players[3]).get_target(&ActionType::Eliminate), None);
+
+    let response = submit(&client, &url, &cookie, &page, "1");
+    assert_eq!(response.status(), 303);
+    assert_eq!(game.lock().unwrap().get_player(players[3]).get_target(&ActionType::Eliminate), Some(&players[2]));
+    server.stop();
+  }
+}
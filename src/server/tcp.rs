@@ -0,0 +1,236 @@
+use std::error;
+use std::io::Write;
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+
+use crate::game::{Game, GameStatus};
+use crate::interface::{Idle, Interface, tcp::TcpInterface};
+use crate::login::Terminal;
+use crate::menu::{display_player_menu, display_remote_home_menu, run_log_out};
+use crate::session::SessionId;
+
+use super::SharedGame;
+
+// Kept to close the connections when the game is over, and wait for their sessions to end
+type Connections = Arc<Mutex<Vec<(TcpStream, JoinHandle<()>)>>>;
+
+// Each connection is a terminal of its own, with the menus of the players (the game master ones stay on the local terminal)
+// The game is only locked while a menu runs, never while the connection is waited for
+pub struct TcpServer {
+  address: SocketAddr,
+  stopped: Arc<AtomicBool>,
+  connections: Connections,
+  thread: JoinHandle<()>,
+}
+
+impl TcpServer {
+  pub fn start (address: &str, game: SharedGame) -> Result<TcpServer, Box<dyn error::Error>> {
+    let listener = TcpListener::bind(address).map_err(|error| format!("Cannot listen on {address}: {error}"))?;
+    let address = listener.local_addr()?;
+    let stopped = Arc::new(AtomicBool::new(false));
+    let connections: Connections = Arc::new(Mutex::new(Vec::new()));
+    let thread = thread::spawn({
+      let stopped = stopped.clone();
+      let connections = connections.clone();
+      move || {
+        for stream in listener.incoming() {
+          if stopped.load(Ordering::SeqCst) {
+            break;
+          }
+          let Ok(stream) = stream else {
+            continue;
+          };
+          let Ok(kept) = stream.try_clone() else {
+            continue;
+          };
+          let game = game.clone();
+          let session = thread::spawn(move || run_session(stream, game));
+          let mut connections = connections.lock().unwrap();
+          connections.retain(|(_, session)| !session.is_finished()); // the players who left, their streams are closed
+          connections.push((kept, session));
+        }
+      }
+    });
+    Ok(TcpServer { address, stopped, connections, thread })
+  }
+
+  pub fn address (&self) -> SocketAddr {
+    self.address
+  }
+
+  pub fn stop (self) {
+    self.stopped.store(true, Ordering::SeqCst);
+    let _ = TcpStream::connect(self.address); // wakes up the listener, which is waiting for a connection
+    let _ = self.thread.join();
+    for (mut stream, session) in self.connections.lock().unwrap().drain(..) {
+      let _ = stream.write_all("\nLa partie est terminée, rendez-vous sur le terminal de control.\n".as_bytes());
+      let _ = stream.shutdown(Shutdown::Both); // the session stops waiting for an answer
+      let _ = session.join();
+    }
+  }
+}
+
+fn run_session (stream: TcpStream, game: SharedGame) {
+  let Ok(terminal) = stream.peer_addr().map(|address| Terminal::Remote(address.ip())) else {
+    return;
+  };
+  let Ok(mut interface) = TcpInterface::new(stream) else {
+    return;
+  };
+  let mut session = None; // the player logged in on this connection
+  let mut notice = None; // the outcome of the last identification
+  while !interface.closed() {
+    // The game is locked while the menu is replayed with the answers read so far, never while an answer is awaited
+    let idle_timeout = {
+      let mut game = game.lock().unwrap();
+      if game.ended() {
+        break;
+      }
+      interface.start_replay();
+      if run_menu(&mut game, &mut interface, terminal, &mut session, &mut notice).is_ok() {
+        interface.end_replay(); // the menu is done, the next one starts
+        continue;
+      }
+      game.get_config().idle_timeout()
+    };
+    interface.show_replay();
+    interface.set_idle_timeout(session.as_ref().and(idle_timeout));
+    if interface.read_answer().is_err() {
+      interface.end_replay();
+      if let Some(current_session) = session.take() {
+        interface.set_idle_timeout(None);
+        let mut game = game.lock().unwrap();
+        if let Some(mut turn) = game.get_session_game(&current_session) {
+          let _ = run_log_out(&mut turn, &mut interface); // logging out only fails if the connection is gone
+        }
+      }
+    }
+  }
+}
+
+// Once the menu is done, the session of the connection changes when a player logged in or out
+fn run_menu (game: &mut GameStatus, interface: &mut TcpInterface, terminal: Terminal, session: &mut Option<SessionId>, notice: &mut Option<String>) -> Result<(), Idle> {
+  match session.clone() {
+    Some(current_session) => {
+      display_player_menu(game, interface, &current_session)?;
+      if game.get_session_player(&current_session).is_none() {
+        *session = None;
+      }
+    },
+    None => match display_remote_home_menu(game, interface, terminal, notice.as_deref())? {
+      Ok(new_session) => (*session, *notice) = (Some(new_session), None),
+      Err(message) => *notice = Some(message),
+    },
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::{BufRead, BufReader};
+  use std::time::Duration;
+
+  use rand::SeedableRng;
+
+  use super::*;
+  use crate::action::ActionType;
+  use crate::game::GameRng;
+  use crate::player::Player;
+  use crate::role::RoleCatalog;
+
+  fn read_until (reader: &mut impl BufRead, text: &str) -> String {
+    let mut output = Vec::new();
+    while !String::from_utf8_lossy(&output).contains(text) {
+      let mut byte = [0];
+      reader.read_exact(&mut byte).unwrap();
+      output.push(byte[0]);
+    }
+    String::from_utf8_lossy(&output).to_string()
+  }
+
+  // One answer, then what is displayed until the next question
+  fn answer (stream: &mut TcpStream, output: &mut impl BufRead, answer: &str, prompt: &str) -> String {
+    stream.write_all(format!("{answer}\n").as_bytes()).unwrap();
+    read_until(output, prompt)
+  }
+
+  #[test]
+  fn each_connection_has_its_own_session() {
+    let catalog = RoleCatalog::default();
+    let players = ["patient0", "physician", "astronaut", "astronaut"].iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let mut game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), Default::default());
+    let players = game.get_player_ids(&|_| true);
+    game.set_captain(Some(players[3])); // elected, the crew votes for the elimination
+    let game = Arc::new(Mutex::new(game));
+    let server = TcpServer::start("127.0.0.1:0", game.clone()).unwrap();
+
+    let connect = || {
+      let stream = TcpStream::connect(server.address()).unwrap();
+      stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+      (stream.try_clone().unwrap(), BufReader::new(stream))
+    };
+    let (mut first, mut first_output) = connect();
+    let (mut second, mut second_output) = connect();
+    const CODE: &str = "code d'identification:";
+    const CHOICE: &str = "Quel est votre choix?";
+
+    // Only the identification is offered, the game master actions stay on the local terminal
+    let screen = read_until(&mut first_output, CODE);
+    assert!(!screen.contains("Fin de la journée"));
+    read_until(&mut second_output, CODE);
+    let screen = answer(&mut first, &mut first_output, "1234", CODE);
+    assert!(screen.contains("Code invalide"));
+
+    // The game is not locked while a connection is waited for
+    let screen = answer(&mut first, &mut first_output, "2", CHOICE);
+    assert!(screen.contains("Bienvenue player-2"));
+    answer(&mut first, &mut first_output, "1", CHOICE);
+    assert!(game.try_lock().is_ok());
+    answer(&mut second, &mut second_output, "1", CHOICE);
+    answer(&mut first, &mut first_output, "0", CHOICE);
+    assert_eq!(game.lock().unwrap().get_player(players[2]).get_target(&ActionType::Eliminate), Some(&players[0]));
+
+    // The first session still plays its own player after the second one logged in
+    answer(&mut first, &mut first_output, "1", CHOICE);
+    answer(&mut first, &mut first_output, "1", CHOICE);
+    assert_eq!(game.lock().unwrap().get_player(players[2]).get_target(&ActionType::Eliminate), Some(&players[1]));
+    assert_eq!(game.lock().unwrap().get_player(players[1]).get_target(&ActionType::Eliminate), None);
+
+    // An answer is not given to a question that changed in the meantime
+    answer(&mut second, &mut second_output, "1", CHOICE);
+    game.lock().unwrap().get_mut_player(players[0]).die(1, String::from("Aspiré·e accidentellement par le sas tribord"));
+    let screen = answer(&mut second, &mut second_output, "1", CHOICE);
+    assert!(screen.contains("Le menu a changé"));
+    assert_eq!(game.lock().unwrap().get_player(players[1]).get_target(&ActionType::Eliminate), None);
+    server.stop();
+  }
+
+  #[test]
+  fn closed_connections_are_forgotten() {
+    let catalog = RoleCatalog::default();
+    let players = ["patient0", "physician", "astronaut"].iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), Default::default());
+    let server = TcpServer::start("127.0.0.1:0", Arc::new(Mutex::new(game))).unwrap();
+    let connect = || {
+      let stream = TcpStream::connect(server.address()).unwrap();
+      stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+      let mut output = BufReader::new(stream.try_clone().unwrap());
+      read_until(&mut output, "code d'identification:");
+      stream
+    };
+
+    drop(connect());
+    while !server.connections.lock().unwrap().iter().all(|(_, session)| session.is_finished()) {
+      thread::sleep(Duration::from_millis(10));
+    }
+    let _connected = connect();
+    assert_eq!(server.connections.lock().unwrap().len(), 1);
+    server.stop();
+  }
+}
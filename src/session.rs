@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use rand::Rng;
+
+use crate::player::PlayerId;
+
+// A player logged in on one of the terminals: the local one, a browser, a remote terminal or another frontend
+// The id is the token given to the frontend, it must not be guessed to play as someone else
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SessionId(String);
+
+impl SessionId {
+  pub fn from_token (token: &str) -> SessionId {
+    SessionId(token.to_string())
+  }
+
+  pub fn as_str (&self) -> &str {
+    &self.0
+  }
+}
+
+// Each session knows which player it belongs to, and only gives access to that player (see Game::get_session_game)
+// A player can have several sessions (a phone and a laptop), logging out of one of them leaves the others open
+#[derive(Debug, Default)]
+pub struct Sessions {
+  players: HashMap<SessionId, PlayerId>,
+}
+
+impl Sessions {
+  pub fn open (&mut self, player: PlayerId) -> SessionId {
+    // Not the game's rng: the sessions must not be predictable, and must not change how the game unfolds
+    let token: String = rand::thread_rng().gen::<[u8; 16]>().iter().map(|byte| format!("{byte:02x}")).collect();
+    let session = SessionId(token);
+    self.players.insert(session.clone(), player);
+    session
+  }
+
+  pub fn close (&mut self, session: &SessionId) {
+    self.players.remove(session);
+  }
+
+  pub fn player (&self, session: &SessionId) -> Option<PlayerId> {
+    self.players.get(session).copied()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::SeedableRng;
+
+  use super::*;
+  use crate::action::ActionType;
+  use crate::game::{Game, GameRng, GameStatus, PlayerGame};
+  use crate::player::{CrewMember, Player};
+  use crate::role::RoleCatalog;
+
+  #[test]
+  fn a_session_cannot_reach_the_data_of_another_session() {
+    let catalog = RoleCatalog::default();
+    let players = ["patient0", "physician", "physician", "astronaut"].iter().enumerate()
+      .map(|(idx, role)| Player::new(idx, idx.to_string(), format!("player-{idx}"), catalog.get(role).unwrap().clone()))
+      .collect();
+    let mut game = GameStatus::new(String::from("Koursk"), players, false, 1, GameRng::seed_from_u64(1), Default::default());
+    let players = game.get_player_ids(&|_| true);
+    game.set_captain(Some(players[2]));
+    let first = game.open_session(players[1]);
+    let second = game.open_session(players[2]);
+    assert_ne!(first, second);
+    game.get_session_game(&second).unwrap().set_current_target(&ActionType::Cure, Some(players[3]));
+    game.get_session_game(&second).unwrap().set_captain_successor(Some(players[3]));
+    game.send_message(players[2], String::from("Ordinateur Central"), String::from("Secret"));
+
+    // The others are only crew members: a name, not their role, targets or messages
+    let mut turn = game.get_session_game(&first).unwrap();
+    assert_eq!(turn.get_crew_member(players[2]), CrewMember { id: players[2], name: String::from("player-2") });
+    assert_eq!(turn.get_current_target(&ActionType::Cure), None);
+    assert_eq!(turn.get_captain_successor(), None); // the choice of the captain
+    assert!(turn.get_hackable_roles().is_empty()); // not the hacker
+
+    // The changes only reach the player of the session
+    turn.set_current_target(&ActionType::Cure, Some(players[0]));
+    turn.set_captain_successor(Some(players[0])); // not the captain
+    turn.log_out();
+    assert_eq!(game.get_player(players[2]).get_target(&ActionType::Cure), Some(&players[3]));
+    assert_eq!(game.get_player(players[1]).get_target(&ActionType::Cure), Some(&players[0]));
+    assert_eq!(game.get_captain_successor(), Some(players[3]));
+    assert!(game.get_session_game(&first).is_none());
+    assert!(game.get_session_game(&SessionId::from_token("guessed")).is_none());
+    assert_eq!(game.get_session_game(&second).unwrap().get_current_player().id, players[2]);
+  }
+}
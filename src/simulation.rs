@@ -0,0 +1,86 @@
+use std::error;
+
+use rand::{Rng, SeedableRng, seq::SliceRandom};
+use rand::rngs::StdRng;
+
+use crate::action::ActionType;
+use crate::config::GameConfig;
+use crate::game::{Game, GameAccess, GameStatus, PhaseOfDay, PlayerGame};
+use crate::game_creator::create_game;
+use crate::interface::{Interface, headless::HeadlessInterface};
+use crate::role::{Role, RoleAction, RoleCatalog};
+use crate::{run_captain_election, run_end_of_day, run_night};
+
+// Games stuck in a loop (nobody ever eliminated and nobody infected) are stopped after that many days
+const MAX_DAYS: u32 = 100;
+
+// Plays a whole game without anyone at the keyboard, every choice of the players being random
+// The choices of the players come from their own rng, so the game's rng is used exactly as in a real game
+pub fn simulate_game (catalog: RoleCatalog, config: GameConfig, players: usize, seed: u64) -> Result<GameStatus, Box<dyn error::Error>> {
+  let mut interface = HeadlessInterface::new(seed);
+  interface.mock(creation_inputs(players));
+  let mut game = create_game(&mut interface, false, seed, catalog, config)?;
+  let mut rng = StdRng::seed_from_u64(seed);
+
+  while !game.ended() && game.get_date() <= MAX_DAYS {
+    play_random_day(&mut game, &mut rng);
+    if game.get_captain().is_none() { // the day starts with the election, then the crew votes again
+      run_captain_election(&mut GameAccess::Owned(&mut game), &mut interface)?;
+      play_random_day(&mut game, &mut rng);
+    }
+    run_end_of_day(&mut GameAccess::Owned(&mut game), &mut interface)?;
+    if !game.ended() && game.get_phase_of_day() == &PhaseOfDay::Twilight {
+      run_night(&mut GameAccess::Owned(&mut game), &mut interface)?;
+    }
+  }
+  Ok(game)
+}
+
+// Answers of the game creation menu: name the ship, add the players, start the game
+fn creation_inputs (players: usize) -> Vec<String> {
+  let mut inputs = vec![String::from("1"), String::from("Simulation")];
+  for idx in 0..players {
+    inputs.append(&mut vec![String::from("2"), format!("player-{idx}"), String::new()]);
+  }
+  inputs.push(String::from("7"));
+  inputs
+}
+
+fn play_random_day (game: &mut GameStatus, rng: &mut StdRng) {
+  let targets = game.get_player_ids(&|_| true);
+  let hackable_roles: Vec<Role> = game.get_players().iter()
+    .filter(|player| player.role.hackable)
+    .map(|player| player.role.clone())
+    .collect();
+  for player_id in targets.clone() {
+    let mut turn = game.get_player_game(player_id);
+    let mut actions = match turn.get_captain() {
+      Some(_) => vec![ActionType::Eliminate],
+      None => vec![ActionType::ElectCaptain],
+    };
+    let player = turn.get_mut_current_player();
+    player.has_connected_today = true;
+    if player.infected {
+      actions.append(&mut vec![ActionType::Infect, ActionType::Paralyze]);
+      player.mutant_kill = rng.gen_bool(0.3);
+    }
+    for action in player.role.actions.clone() {
+      match action {
+        RoleAction::Psychoanalyze => actions.push(ActionType::Psychoanalyze),
+        RoleAction::Cure if !player.infected => {
+          actions.push(ActionType::Cure);
+          player.physician_kill = rng.gen_bool(0.2);
+        },
+        RoleAction::Cure => (),
+        RoleAction::Genomyze => actions.push(ActionType::Genomyze),
+        RoleAction::Spy => actions.push(ActionType::Spy),
+        RoleAction::CountMutants => (),
+        RoleAction::Hack => player.hacker_target = hackable_roles.choose(rng).cloned(),
+        RoleAction::SignalMutants => player.traitor_signal = rng.gen_bool(0.5),
+      }
+    }
+    for action in actions {
+      turn.set_current_target(&action, targets.choose(rng).copied());
+    }
+  }
+}